@@ -0,0 +1,33 @@
+-- Append-only audit trail of every mutation made through the repository
+CREATE TABLE audit_events (
+    id UUID PRIMARY KEY,
+    indicator_id UUID, -- no FK: history must survive indicator deletion
+    source_id UUID,
+    action VARCHAR(50) NOT NULL, -- create, update, delete, sighting, enrichment, score_change, source_change
+    actor VARCHAR(255) NOT NULL,
+    actor_verified BOOLEAN NOT NULL, -- false when the client named itself (X-Actor)
+    origin VARCHAR(255) NOT NULL, -- api, collector:<name>, scheduler
+    request_id VARCHAR(255),
+    reason TEXT,
+    before JSONB,
+    after JSONB,
+    diff JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_indicator ON audit_events (indicator_id, created_at);
+CREATE INDEX idx_audit_actor ON audit_events (actor);
+CREATE INDEX idx_audit_created ON audit_events (created_at);
+
+-- Reject any attempt to rewrite history
+CREATE OR REPLACE FUNCTION reject_audit_mutation()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_events_append_only
+    BEFORE UPDATE OR DELETE ON audit_events
+    FOR EACH ROW
+    EXECUTE FUNCTION reject_audit_mutation();
@@ -0,0 +1,43 @@
+-- Hash algorithm of hash indicators
+CREATE TYPE hash_algorithm AS ENUM ('md5', 'sha1', 'sha256', 'sha512', 'ssdeep', 'tlsh', 'imphash');
+
+-- Files known under several hashes
+CREATE TABLE file_objects (
+    id UUID PRIMARY KEY,
+    md5 TEXT,
+    sha1 TEXT,
+    sha256 TEXT,
+    sha512 TEXT,
+    ssdeep TEXT,
+    tlsh TEXT,
+    imphash TEXT,
+    size BIGINT,
+    file_type TEXT,
+    names TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_file_objects_md5 ON file_objects (md5) WHERE md5 IS NOT NULL;
+CREATE UNIQUE INDEX idx_file_objects_sha1 ON file_objects (sha1) WHERE sha1 IS NOT NULL;
+CREATE UNIQUE INDEX idx_file_objects_sha256 ON file_objects (sha256) WHERE sha256 IS NOT NULL;
+
+CREATE TRIGGER update_file_objects_updated_at
+    BEFORE UPDATE ON file_objects
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE indicators
+    ADD COLUMN hash_algorithm hash_algorithm,
+    ADD COLUMN file_object_id UUID REFERENCES file_objects(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_indicators_file_object ON indicators (file_object_id) WHERE file_object_id IS NOT NULL;
+
+-- Backfill algorithms for existing hex digests
+UPDATE indicators SET hash_algorithm = CASE length(value)
+        WHEN 32 THEN 'md5'
+        WHEN 40 THEN 'sha1'
+        WHEN 64 THEN 'sha256'
+        WHEN 128 THEN 'sha512'
+    END::hash_algorithm
+WHERE ioc_type = 'hash';
@@ -0,0 +1,3 @@
+-- Dedicated IOC type for networks and address ranges. Kept in its own migration:
+-- a new enum value cannot be used in the transaction that adds it.
+ALTER TYPE ioc_type ADD VALUE IF NOT EXISTS 'cidr';
@@ -0,0 +1,35 @@
+-- Networks covered by CIDR indicators, for containment lookups
+ALTER TABLE indicators ADD COLUMN network CIDR;
+
+CREATE INDEX idx_indicators_network ON indicators USING gist (network inet_ops)
+    WHERE network IS NOT NULL;
+
+-- Parse a value as a network, ignoring host bits; NULL when it is not one
+CREATE OR REPLACE FUNCTION try_parse_network(value TEXT)
+RETURNS CIDR AS $$
+BEGIN
+    RETURN network(value::inet);
+EXCEPTION WHEN others THEN
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+-- Networks were previously stored as opaque 'ip' values
+UPDATE indicators SET
+    ioc_type = 'cidr',
+    network = try_parse_network(value)
+WHERE ioc_type = 'ip'
+  AND value LIKE '%/%'
+  AND try_parse_network(value) IS NOT NULL;
+
+-- Rewrite to the canonical network text unless that would collide with a row already stored
+UPDATE indicators i SET value = network::text
+WHERE ioc_type = 'cidr'
+  AND network IS NOT NULL
+  AND value <> network::text
+  AND NOT EXISTS (
+      SELECT 1 FROM indicators o
+      WHERE o.ioc_type = 'cidr' AND o.value = i.network::text
+  );
+
+DROP FUNCTION try_parse_network(TEXT);
@@ -0,0 +1,9 @@
+-- Host and network artefacts reported by honeypots and EDR tooling
+ALTER TYPE ioc_type ADD VALUE IF NOT EXISTS 'asn';
+ALTER TYPE ioc_type ADD VALUE IF NOT EXISTS 'file_path';
+ALTER TYPE ioc_type ADD VALUE IF NOT EXISTS 'registry_key';
+ALTER TYPE ioc_type ADD VALUE IF NOT EXISTS 'mutex';
+ALTER TYPE ioc_type ADD VALUE IF NOT EXISTS 'user_agent';
+ALTER TYPE ioc_type ADD VALUE IF NOT EXISTS 'ja3';
+ALTER TYPE ioc_type ADD VALUE IF NOT EXISTS 'ja4';
+ALTER TYPE ioc_type ADD VALUE IF NOT EXISTS 'cert_fingerprint';
@@ -0,0 +1,12 @@
+-- Directed links between indicators (URL hosted_on domain, ...)
+CREATE TABLE indicator_relations (
+    id UUID PRIMARY KEY,
+    source_indicator_id UUID NOT NULL REFERENCES indicators(id) ON DELETE CASCADE,
+    target_indicator_id UUID NOT NULL REFERENCES indicators(id) ON DELETE CASCADE,
+    relation_type VARCHAR(50) NOT NULL, -- hosted_on
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT unique_indicator_relation UNIQUE (source_indicator_id, target_indicator_id, relation_type)
+);
+
+CREATE INDEX idx_indicator_relations_target ON indicator_relations (target_indicator_id);
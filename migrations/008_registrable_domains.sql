@@ -0,0 +1,15 @@
+-- Registrable domain (public suffix + one label) of domain and URL indicators,
+-- filled in by the application since it needs the public suffix list
+ALTER TABLE indicators ADD COLUMN registrable_domain TEXT;
+
+CREATE INDEX idx_indicators_registrable_domain ON indicators (registrable_domain)
+    WHERE registrable_domain IS NOT NULL;
+
+-- Host part of a URL indicator, or the value itself for a domain
+CREATE OR REPLACE FUNCTION indicator_host(indicator_type ioc_type, indicator_value TEXT)
+RETURNS TEXT AS $$
+    SELECT CASE
+        WHEN indicator_type = 'url' THEN substring(indicator_value from '^[^:]+://(?:[^@/]*@)?([^/:?#]+)')
+        ELSE indicator_value
+    END;
+$$ LANGUAGE sql IMMUTABLE;
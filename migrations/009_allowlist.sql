@@ -0,0 +1,31 @@
+-- Allowlist entries managed through the API (bundled and local lists are loaded from files)
+CREATE TYPE allowlist_match AS ENUM ('exact', 'cidr', 'suffix', 'regex');
+CREATE TYPE allowlist_action AS ENUM ('tag', 'cap_severity', 'reject');
+
+CREATE TABLE allowlist_entries (
+    id UUID PRIMARY KEY,
+    match_type allowlist_match NOT NULL,
+    value TEXT NOT NULL,
+    ioc_type ioc_type,
+    action allowlist_action NOT NULL DEFAULT 'reject',
+    max_severity severity,
+    comment TEXT,
+    created_by VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Indicators an allowlist rejected, tagged or capped, per feed
+CREATE TABLE allowlist_suppressions (
+    id UUID PRIMARY KEY,
+    feed VARCHAR(255) NOT NULL,
+    ioc_type ioc_type NOT NULL,
+    value TEXT NOT NULL,
+    action allowlist_action NOT NULL,
+    list_name VARCHAR(255) NOT NULL,
+    pattern TEXT NOT NULL,
+    entry_id UUID, -- no FK: the report must survive entry deletion
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_allowlist_suppressions_feed ON allowlist_suppressions (feed, created_at);
+CREATE INDEX idx_allowlist_suppressions_created ON allowlist_suppressions (created_at);
@@ -0,0 +1,26 @@
+-- Durable enrichment queue, claimed by workers with FOR UPDATE SKIP LOCKED
+-- Enum order matters: GREATEST() merges duplicate requests into the broadest mode
+CREATE TYPE enrichment_mode AS ENUM ('expired', 'cached', 'force');
+CREATE TYPE enrichment_job_status AS ENUM ('pending', 'running', 'dead');
+
+CREATE TABLE enrichment_jobs (
+    id UUID PRIMARY KEY,
+    indicator_id UUID NOT NULL REFERENCES indicators(id) ON DELETE CASCADE,
+    priority SMALLINT NOT NULL, -- manual 30, sighting 20, feed 10, refresh 0
+    mode enrichment_mode NOT NULL DEFAULT 'cached',
+    status enrichment_job_status NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    requested_by VARCHAR(255) NOT NULL,
+    run_after TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- At most one queued or in-flight job per indicator; completed jobs are deleted
+CREATE UNIQUE INDEX idx_enrichment_jobs_active ON enrichment_jobs (indicator_id)
+    WHERE status IN ('pending', 'running');
+CREATE INDEX idx_enrichment_jobs_ready ON enrichment_jobs (priority DESC, run_after)
+    WHERE status = 'pending';
+CREATE INDEX idx_enrichment_jobs_status ON enrichment_jobs (status, updated_at);
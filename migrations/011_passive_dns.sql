@@ -0,0 +1,17 @@
+-- Passive DNS history: every DNS answer seen by enrichment, kept across lookups
+CREATE TYPE pdns_rrtype AS ENUM ('A', 'AAAA', 'CNAME', 'NS', 'MX', 'PTR');
+
+CREATE TABLE passive_dns (
+    id UUID PRIMARY KEY,
+    rrname VARCHAR(255) NOT NULL, -- queried name, or the address for PTR
+    rrtype pdns_rrtype NOT NULL,
+    rdata VARCHAR(255) NOT NULL,  -- answer (address or name)
+    first_seen TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    count BIGINT NOT NULL DEFAULT 1,
+
+    CONSTRAINT unique_passive_dns_record UNIQUE (rrname, rrtype, rdata)
+);
+
+-- Reverse lookups: which names pointed at an address or name
+CREATE INDEX idx_passive_dns_rdata ON passive_dns (rdata);
@@ -0,0 +1,8 @@
+-- Keep every enrichment snapshot instead of overwriting the previous result
+ALTER TABLE enrichments DROP CONSTRAINT unique_enrichment;
+ALTER TABLE enrichments ADD COLUMN is_latest BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE enrichments ADD COLUMN changes JSONB; -- material changes from the previous snapshot
+
+-- One current snapshot per type/provider per indicator
+CREATE UNIQUE INDEX unique_latest_enrichment ON enrichments (indicator_id, enrichment_type, provider) WHERE is_latest;
+CREATE INDEX idx_enrichments_history ON enrichments (indicator_id, provider, fetched_at DESC);
@@ -0,0 +1,6 @@
+-- abuse.ch feeds collected alongside Feodo Tracker
+INSERT INTO ioc_sources (id, name, source_type, url, api_key_required, reliability_score, enabled) VALUES
+    ('00000000-0000-0000-0000-000000000007', 'urlhaus', 'feed', 'https://urlhaus.abuse.ch', FALSE, 85, TRUE),
+    ('00000000-0000-0000-0000-000000000008', 'threatfox', 'feed', 'https://threatfox.abuse.ch', FALSE, 75, TRUE),
+    ('00000000-0000-0000-0000-000000000009', 'malwarebazaar', 'feed', 'https://bazaar.abuse.ch', TRUE, 90, TRUE)
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,19 @@
+-- Incremental feed sync: opaque position saved after each successful fetch
+-- (e.g. the newest OTX pulse modification time)
+ALTER TABLE ioc_sources ADD COLUMN sync_cursor TEXT;
+
+-- Report context an indicator was published with (OTX pulse, ...)
+CREATE TABLE indicator_context (
+    id UUID PRIMARY KEY,
+    indicator_id UUID NOT NULL REFERENCES indicators(id) ON DELETE CASCADE,
+    source_id UUID REFERENCES ioc_sources(id) ON DELETE SET NULL,
+    report_id TEXT NOT NULL,          -- e.g. pulse:<id>
+    report_name TEXT,
+    description TEXT,
+    report_urls TEXT[] NOT NULL DEFAULT '{}',
+    attack_ids TEXT[] NOT NULL DEFAULT '{}',  -- MITRE ATT&CK technique ids
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT unique_indicator_context UNIQUE (indicator_id, report_id)
+);
@@ -0,0 +1,28 @@
+-- Honeypot sessions and the credentials/commands seen in them
+CREATE TABLE honeypot_sessions (
+    session_id TEXT PRIMARY KEY,
+    source_ip TEXT NOT NULL,
+    protocol VARCHAR(50) NOT NULL,
+    category VARCHAR(50) NOT NULL,
+    first_seen TIMESTAMPTZ NOT NULL,
+    last_seen TIMESTAMPTZ NOT NULL,
+    event_count INTEGER NOT NULL DEFAULT 0,
+    login_attempts INTEGER NOT NULL DEFAULT 0,
+    command_count INTEGER NOT NULL DEFAULT 0,
+    download_urls TEXT[] NOT NULL DEFAULT '{}'
+);
+
+CREATE INDEX idx_honeypot_sessions_ip ON honeypot_sessions (source_ip);
+CREATE INDEX idx_honeypot_sessions_last_seen ON honeypot_sessions (last_seen);
+
+CREATE TABLE honeypot_events (
+    id UUID PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES honeypot_sessions(session_id) ON DELETE CASCADE,
+    observed_at TIMESTAMPTZ NOT NULL,
+    username TEXT,
+    password TEXT,
+    command TEXT
+);
+
+CREATE INDEX idx_honeypot_events_session ON honeypot_events (session_id);
+CREATE INDEX idx_honeypot_events_observed ON honeypot_events (observed_at);
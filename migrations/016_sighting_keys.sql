@@ -0,0 +1,5 @@
+-- Feed sightings carry the upstream event key so a replayed event is recorded once
+ALTER TABLE sightings ADD COLUMN event_key TEXT;
+
+CREATE UNIQUE INDEX idx_sightings_event_key ON sightings (source, event_key)
+    WHERE event_key IS NOT NULL;
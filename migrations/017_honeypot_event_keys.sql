@@ -0,0 +1,8 @@
+-- Key honeypot events within their session so replays are not counted twice.
+-- Every event is kept (not only logins and commands) so session counts can be
+-- derived from the events actually inserted.
+ALTER TABLE honeypot_events ADD COLUMN event_key TEXT;
+UPDATE honeypot_events SET event_key = id::text;
+ALTER TABLE honeypot_events ALTER COLUMN event_key SET NOT NULL;
+
+CREATE UNIQUE INDEX idx_honeypot_events_key ON honeypot_events (session_id, event_key);
@@ -0,0 +1,4 @@
+-- Consecutive refreshes of an expired enrichment that failed or returned no
+-- data. Each one pushes expires_at out further so stuck rows stop crowding
+-- the refresh batch; a successful refresh resets it.
+ALTER TABLE enrichments ADD COLUMN refresh_failures INTEGER NOT NULL DEFAULT 0;
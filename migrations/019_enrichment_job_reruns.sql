@@ -0,0 +1,6 @@
+-- Requests for an indicator whose job is already running are kept as a
+-- follow-up run instead of being dropped; the job is re-queued with them when
+-- it finishes.
+ALTER TABLE enrichment_jobs ADD COLUMN rerun_mode enrichment_mode;
+ALTER TABLE enrichment_jobs ADD COLUMN rerun_priority SMALLINT;
+ALTER TABLE enrichment_jobs ADD COLUMN rerun_requested_by VARCHAR(255);
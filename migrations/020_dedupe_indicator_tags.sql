@@ -0,0 +1,7 @@
+-- Re-ingesting an indicator appended its tags and source again; the upsert
+-- now merges them as sets, so collapse the duplicates already stored.
+UPDATE indicators
+SET tags = ARRAY(SELECT DISTINCT unnest(tags)),
+    source_ids = ARRAY(SELECT DISTINCT unnest(source_ids))
+WHERE cardinality(tags) <> (SELECT COUNT(DISTINCT t) FROM unnest(tags) AS t)
+   OR cardinality(source_ids) <> (SELECT COUNT(DISTINCT s) FROM unnest(source_ids) AS s);
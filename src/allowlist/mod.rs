@@ -0,0 +1,317 @@
+//! Allowlist (warninglist) engine
+//!
+//! Keeps known-benign values such as public resolvers, private ranges and top
+//! sites out of the blocklists. Rules come from lists bundled with the binary,
+//! an optional local directory of list files, and entries managed via the API.
+
+use anyhow::{Context, Result};
+use ipnetwork::IpNetwork;
+use regex::Regex;
+use std::path::Path;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+use crate::models::ioc_utils::{detect_ioc_type, indicator_network, normalize_ioc, url_host};
+use crate::models::{AllowlistAction, AllowlistEntry, AllowlistMatch, IocType, Severity};
+
+/// Lists shipped with the binary
+const BUNDLED_LISTS: [(&str, &str); 4] = [
+    ("public_dns", include_str!("../../data/allowlists/public_dns.txt")),
+    ("special_use", include_str!("../../data/allowlists/special_use.txt")),
+    ("cdn_ranges", include_str!("../../data/allowlists/cdn_ranges.txt")),
+    ("top_domains", include_str!("../../data/allowlists/top_domains.txt")),
+];
+
+/// Tag added to indicators kept despite matching the allowlist
+pub const ALLOWLISTED_TAG: &str = "allowlisted";
+
+/// A compiled allowlist rule
+#[derive(Debug, Clone)]
+pub struct AllowlistRule {
+    pub list_name: String,
+    pub pattern: String,
+    pub ioc_type: Option<IocType>,
+    pub action: AllowlistAction,
+    pub max_severity: Option<Severity>,
+    pub entry_id: Option<Uuid>,
+    matcher: Matcher,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Exact(Option<IocType>, String),
+    Cidr(IpNetwork),
+    Suffix(String),
+    Regex(Regex),
+}
+
+impl AllowlistRule {
+    /// Compile a rule, validating its pattern
+    pub fn new(
+        list_name: &str,
+        match_type: AllowlistMatch,
+        pattern: &str,
+        ioc_type: Option<IocType>,
+        action: AllowlistAction,
+        max_severity: Option<Severity>,
+    ) -> Result<Self> {
+        let pattern = pattern.trim();
+        let matcher = match match_type {
+            AllowlistMatch::Exact => {
+                let value_type = ioc_type.clone().or_else(|| detect_ioc_type(pattern));
+                let normalized = match &value_type {
+                    Some(t) => normalize_ioc(pattern, t),
+                    None => pattern.to_string(),
+                };
+                Matcher::Exact(value_type, normalized)
+            }
+            AllowlistMatch::Cidr => Matcher::Cidr(
+                pattern
+                    .parse::<IpNetwork>()
+                    .with_context(|| format!("Invalid CIDR: {}", pattern))?,
+            ),
+            AllowlistMatch::Suffix => {
+                let suffix = normalize_ioc(pattern.trim_start_matches("*.").trim_start_matches('.'), &IocType::Domain);
+                anyhow::ensure!(!suffix.is_empty(), "Empty domain suffix");
+                Matcher::Suffix(suffix)
+            }
+            AllowlistMatch::Regex => Matcher::Regex(
+                Regex::new(pattern).with_context(|| format!("Invalid regex: {}", pattern))?,
+            ),
+        };
+
+        Ok(Self {
+            list_name: list_name.to_string(),
+            pattern: pattern.to_string(),
+            ioc_type,
+            action,
+            max_severity,
+            entry_id: None,
+            matcher,
+        })
+    }
+
+    /// Compile an entry stored in the database
+    pub fn from_entry(entry: &AllowlistEntry) -> Result<Self> {
+        let mut rule = Self::new(
+            "api",
+            entry.match_type,
+            &entry.value,
+            entry.ioc_type.clone(),
+            entry.action,
+            entry.max_severity.clone(),
+        )?;
+        rule.entry_id = Some(entry.id);
+        Ok(rule)
+    }
+
+    fn matches(&self, ioc_type: &IocType, value: &str) -> bool {
+        if self.ioc_type.as_ref().is_some_and(|t| t != ioc_type) {
+            return false;
+        }
+
+        match &self.matcher {
+            Matcher::Exact(rule_type, expected) => {
+                rule_type.as_ref().is_none_or(|t| t == ioc_type) && value == expected
+            }
+            Matcher::Cidr(range) => {
+                let network = match ioc_type {
+                    IocType::Url => url_host(value)
+                        .and_then(|(host_type, host)| indicator_network(&host, &host_type)),
+                    _ => indicator_network(value, ioc_type),
+                };
+                network.is_some_and(|n| range.contains(n.network()) && n.prefix() >= range.prefix())
+            }
+            Matcher::Suffix(suffix) => {
+                let host = match ioc_type {
+                    IocType::Domain => Some(value.to_string()),
+                    IocType::Url => url_host(value)
+                        .filter(|(host_type, _)| *host_type == IocType::Domain)
+                        .map(|(_, host)| host),
+                    IocType::Email => value.rsplit_once('@').map(|(_, domain)| domain.to_string()),
+                    _ => None,
+                };
+                host.is_some_and(|h| h == *suffix || h.ends_with(&format!(".{}", suffix)))
+            }
+            Matcher::Regex(regex) => regex.is_match(value),
+        }
+    }
+}
+
+/// The rule an indicator matched
+#[derive(Debug, Clone)]
+pub struct AllowlistHit {
+    pub list_name: String,
+    pub pattern: String,
+    pub action: AllowlistAction,
+    pub max_severity: Option<Severity>,
+    pub entry_id: Option<Uuid>,
+}
+
+impl AllowlistHit {
+    /// Highest severity an indicator may keep under this hit
+    pub fn severity_cap(&self) -> Option<Severity> {
+        match self.action {
+            AllowlistAction::CapSeverity => Some(self.max_severity.clone().unwrap_or(Severity::Low)),
+            _ => None,
+        }
+    }
+}
+
+/// Returned by the repository when an allowlist rule rejects an indicator
+#[derive(Debug, thiserror::Error)]
+#[error("{value} is allowlisted by {} ({})", hit.list_name, hit.pattern)]
+pub struct AllowlistRejection {
+    pub ioc_type: IocType,
+    pub value: String,
+    pub hit: AllowlistHit,
+}
+
+/// Number of rules loaded per list
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AllowlistSummary {
+    pub list_name: String,
+    pub rules: usize,
+}
+
+/// Thread-safe set of allowlist rules consulted on every indicator write
+#[derive(Debug, Default)]
+pub struct Allowlist {
+    file_rules: RwLock<Vec<AllowlistRule>>,
+    entry_rules: RwLock<Vec<AllowlistRule>>,
+}
+
+impl Allowlist {
+    /// Replace the rules loaded from bundled and local list files
+    pub fn set_file_rules(&self, rules: Vec<AllowlistRule>) {
+        *self.file_rules.write().unwrap() = rules;
+    }
+
+    /// Replace the rules managed through the API; invalid entries are skipped
+    pub fn set_entries(&self, entries: &[AllowlistEntry]) {
+        let rules = entries
+            .iter()
+            .filter_map(|entry| match AllowlistRule::from_entry(entry) {
+                Ok(rule) => Some(rule),
+                Err(e) => {
+                    tracing::warn!(entry = %entry.id, error = %e, "Skipping invalid allowlist entry");
+                    None
+                }
+            })
+            .collect();
+        *self.entry_rules.write().unwrap() = rules;
+    }
+
+    /// Match a normalized value; when several rules match the strictest action wins
+    pub fn check(&self, ioc_type: &IocType, value: &str) -> Option<AllowlistHit> {
+        let file_rules = self.file_rules.read().unwrap();
+        let entry_rules = self.entry_rules.read().unwrap();
+
+        entry_rules
+            .iter()
+            .chain(file_rules.iter())
+            .filter(|rule| rule.matches(ioc_type, value))
+            .max_by_key(|rule| rule.action)
+            .map(|rule| AllowlistHit {
+                list_name: rule.list_name.clone(),
+                pattern: rule.pattern.clone(),
+                action: rule.action,
+                max_severity: rule.max_severity.clone(),
+                entry_id: rule.entry_id,
+            })
+    }
+
+    /// Rule counts per list
+    pub fn summary(&self) -> Vec<AllowlistSummary> {
+        let file_rules = self.file_rules.read().unwrap();
+        let entry_rules = self.entry_rules.read().unwrap();
+
+        let mut summary: Vec<AllowlistSummary> = vec![];
+        for rule in entry_rules.iter().chain(file_rules.iter()) {
+            match summary.iter_mut().find(|s| s.list_name == rule.list_name) {
+                Some(s) => s.rules += 1,
+                None => summary.push(AllowlistSummary {
+                    list_name: rule.list_name.clone(),
+                    rules: 1,
+                }),
+            }
+        }
+        summary
+    }
+}
+
+/// Parse a list file.
+///
+/// One pattern per line, `exact:`, `cidr:`, `suffix:` or `regex:` prefixed (no
+/// prefix means exact). `#` starts a comment. `@action`, `@max_severity` and
+/// `@ioc_type` directives apply to the lines that follow them.
+pub fn parse_list(list_name: &str, text: &str) -> Result<Vec<AllowlistRule>> {
+    let mut action = AllowlistAction::Reject;
+    let mut max_severity = None;
+    let mut ioc_type = None;
+    let mut rules = vec![];
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(directive) = line.strip_prefix('@') {
+            let (key, value) = directive.split_once(char::is_whitespace).unwrap_or((directive, ""));
+            let value = serde_json::Value::String(value.trim().to_string());
+            let parsed = match key {
+                "action" => serde_json::from_value(value).map(|v| action = v),
+                "max_severity" => serde_json::from_value(value).map(|v| max_severity = Some(v)),
+                "ioc_type" => serde_json::from_value(value).map(|v| ioc_type = Some(v)),
+                _ => anyhow::bail!("{}:{}: unknown directive @{}", list_name, number + 1, key),
+            };
+            parsed.with_context(|| format!("{}:{}: invalid @{}", list_name, number + 1, key))?;
+            continue;
+        }
+
+        let (match_type, pattern) = match line.split_once(':') {
+            Some(("exact", rest)) => (AllowlistMatch::Exact, rest),
+            Some(("cidr", rest)) => (AllowlistMatch::Cidr, rest),
+            Some(("suffix", rest)) => (AllowlistMatch::Suffix, rest),
+            Some(("regex", rest)) => (AllowlistMatch::Regex, rest),
+            _ => (AllowlistMatch::Exact, line),
+        };
+
+        let rule = AllowlistRule::new(list_name, match_type, pattern, ioc_type.clone(), action, max_severity.clone())
+            .with_context(|| format!("{}:{}", list_name, number + 1))?;
+        rules.push(rule);
+    }
+
+    Ok(rules)
+}
+
+/// Rules from the lists bundled with the binary
+pub fn bundled_rules() -> Vec<AllowlistRule> {
+    BUNDLED_LISTS
+        .iter()
+        .flat_map(|(name, text)| {
+            parse_list(&format!("bundled:{}", name), text).expect("bundled allowlist is valid")
+        })
+        .collect()
+}
+
+/// Rules from every `*.txt` list in a directory, named `local:<file stem>`
+pub fn load_dir(dir: &Path) -> Result<Vec<AllowlistRule>> {
+    let mut rules = vec![];
+    let entries = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read allowlist directory {:?}", dir))?;
+
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "txt") {
+            continue;
+        }
+        let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("list");
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read allowlist {:?}", path))?;
+        rules.extend(parse_list(&format!("local:{}", name), &text)?);
+    }
+
+    Ok(rules)
+}
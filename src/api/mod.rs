@@ -1,59 +1,223 @@
 //! REST API for threat intelligence
 
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    async_trait,
+    body::Bytes,
+    response::{IntoResponse, Response},
+    extract::{DefaultBodyLimit, FromRequestParts, Path, Query, State},
+    http::{header, request::Parts, HeaderMap, StatusCode},
     routing::{delete, get, post},
     Json, Router,
 };
+use serde::Deserialize;
 use serde_json::{json, Value};
 use std::sync::Arc;
+use subtle::ConstantTimeEq;
 use uuid::Uuid;
 
+use crate::models::ioc_extract::{extract_indicators, DocumentFormat, ExtractionResult};
+use crate::models::ioc_utils::{
+    defang, detect_ioc_type, indicator_network, ip_range_to_networks, normalize_ioc, parse_ip_range,
+    public_suffix, registrable_domain,
+};
 use crate::models::{
-    BulkImportRequest, BulkImportResponse, CreateIndicatorRequest, DashboardStats,
-    Indicator, IndicatorFilter, IndicatorResponse, PaginatedResponse,
+    AllowlistEntry, AuditContext, AuditEvent, AuditFilter, BatchLookupRequest, BatchLookupResponse,
+    BatchLookupResult, BulkImportRequest, BulkImportResponse, CreateAllowlistEntryRequest,
+    CommandStat, CreateIndicatorRequest, CredentialStat, DashboardStats, DomainRollup, EnrichMode, Enrichment,
+    EnrichmentJobStatus, EnrichmentPriority, EnrichmentQueueStats, FastFluxAssessment, HoneypotActivity,
+    HoneypotSession, HoneypotSessionDetail, Indicator, IndicatorFilter, IndicatorResponse, IocType,
+    PaginatedResponse, PassiveDnsResponse, SuppressionSummary,
 };
+use crate::allowlist::AllowlistRejection;
+use crate::collectors::{honeytrap, run_collector, FeedCollector, FeedResult};
 use crate::storage::ThreatIntelRepo;
+use metrics_exporter_prometheus::PrometheusHandle;
 use crate::enrichment::EnrichmentEngine;
 
 /// Application state shared across handlers
 pub struct AppState {
     pub repo: ThreatIntelRepo,
     pub enrichment: Arc<EnrichmentEngine>,
+    pub collectors: Vec<Arc<dyn FeedCollector>>,
+    pub metrics: PrometheusHandle,
+    /// Bearer token required by the HoneyTrap push endpoint (disabled when unset)
+    pub honeytrap_token: Option<String>,
 }
 
 /// Create the API router
 pub fn create_router(state: Arc<AppState>) -> Router {
-    Router::new()
+    let router = Router::new()
         // Health check
         .route("/health", get(health_check))
+        .route("/metrics", get(render_metrics))
         
         // Indicators
         .route("/api/v1/indicators", get(list_indicators))
         .route("/api/v1/indicators", post(create_indicator))
         .route("/api/v1/indicators/bulk", post(bulk_import))
+        .route("/api/v1/indicators/export", get(export_indicators))
         .route("/api/v1/indicators/:id", get(get_indicator))
         .route("/api/v1/indicators/:id", delete(delete_indicator))
         .route("/api/v1/indicators/:id/enrich", post(enrich_indicator))
+        .route("/api/v1/indicators/:id/enrichments/:provider/history", get(get_enrichment_history))
         .route("/api/v1/indicators/:id/sightings", post(add_sighting))
+        .route("/api/v1/enrichment/queue", get(get_enrichment_queue))
         
         // Lookup (by value instead of ID)
         .route("/api/v1/lookup", get(lookup_indicator))
+        .route("/api/v1/lookup/batch", post(batch_lookup))
         .route("/api/v1/lookup/:value", get(lookup_indicator_by_path))
+
+        // Registrable-domain rollups
+        .route("/api/v1/domains/:domain", get(get_domain_rollup))
+
+        // Passive DNS history
+        .route("/api/v1/pdns/:value", get(get_passive_dns))
         
+        // Extraction from free text, emails and reports
+        .route(
+            "/api/v1/extract",
+            post(extract).layer(DefaultBodyLimit::max(MAX_EXTRACT_BYTES)),
+        )
+
         // Statistics
         .route("/api/v1/stats", get(get_stats))
+
+        // Audit log
+        .route("/api/v1/audit", get(list_audit_events))
+
+        // Allowlist
+        .route("/api/v1/allowlist", get(list_allowlist))
+        .route("/api/v1/allowlist", post(create_allowlist_entry))
+        .route("/api/v1/allowlist/check", get(check_allowlist))
+        .route("/api/v1/allowlist/report", get(get_suppression_report))
+        .route("/api/v1/allowlist/:id", delete(delete_allowlist_entry))
         
         // Sources/Feeds
         .route("/api/v1/sources", get(list_sources))
         .route("/api/v1/feeds/refresh", post(refresh_feeds))
-        
-        .with_state(state)
+
+        // Honeypot session analytics
+        .route("/api/v1/honeypot/sessions", get(list_honeypot_sessions))
+        .route("/api/v1/honeypot/sessions/:session_id", get(get_honeypot_session))
+        .route("/api/v1/honeypot/credentials", get(get_honeypot_credentials))
+        .route("/api/v1/honeypot/commands", get(get_honeypot_commands))
+        .route("/api/v1/honeypot/activity", get(get_honeypot_activity));
+
+    // Push ingestion, only with a token to check pushes against
+    let router = match state.honeytrap_token {
+        Some(_) => router.route(
+            "/api/v1/ingest/honeytrap",
+            post(ingest_honeytrap).layer(DefaultBodyLimit::max(MAX_INGEST_BYTES)),
+        ),
+        None => router,
+    };
+
+    router.with_state(state)
+}
+
+/// Maximum number of values accepted by a single batch lookup
+const MAX_BATCH_LOOKUP: usize = 1000;
+
+/// Maximum number of indicators returned by a wildcard lookup
+const MAX_WILDCARD_MATCHES: i64 = 1000;
+
+/// Maximum number of indicators returned by a single export
+const MAX_EXPORT: i64 = 100_000;
+
+/// Largest document accepted by the extraction endpoint
+const MAX_EXTRACT_BYTES: usize = 10 * 1024 * 1024;
+
+/// Largest batch of pushed honeypot events
+const MAX_INGEST_BYTES: usize = 10 * 1024 * 1024;
+
+/// A domain resolving to at least this many addresses across this many /16
+/// networks within the window is flagged as possible fast flux
+const FAST_FLUX_MIN_IPS: i64 = 5;
+const FAST_FLUX_MIN_NETWORKS: i64 = 3;
+
+// ==================== Rendering ====================
+
+/// Response rendering options shared by indicator, lookup and export endpoints
+#[derive(Debug, Default, Deserialize)]
+struct RenderParams {
+    /// Render indicator values in defanged form (`hxxps://evil[.]com`)
+    #[serde(default)]
+    defang: bool,
+}
+
+impl RenderParams {
+    fn value(&self, value: &str, ioc_type: &IocType) -> String {
+        if self.defang {
+            defang(value, ioc_type)
+        } else {
+            value.to_string()
+        }
+    }
+
+    fn indicator(&self, mut indicator: Indicator) -> Indicator {
+        indicator.value = self.value(&indicator.value, &indicator.ioc_type);
+        indicator
+    }
+
+    /// History entries carry indicator snapshots in `before`/`after`
+    fn audit_event(&self, mut event: AuditEvent) -> AuditEvent {
+        if self.defang {
+            for snapshot in [&mut event.before, &mut event.after].into_iter().flatten() {
+                let ioc_type = snapshot
+                    .get("ioc_type")
+                    .and_then(|t| serde_json::from_value::<IocType>(t.clone()).ok());
+                if let (Some(ioc_type), Some(Value::String(value))) = (ioc_type, snapshot.get_mut("value")) {
+                    *value = defang(value, &ioc_type);
+                }
+            }
+        }
+        event
+    }
+}
+
+// ==================== Audit context ====================
+
+/// Header naming the principal performing the request
+const ACTOR_HEADER: &str = "x-actor";
+/// Header carrying the caller's correlation id
+const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Header carrying a free-text justification for the change
+const REASON_HEADER: &str = "x-audit-reason";
+
+fn header_str(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AuditContext {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let actor = header_str(&parts.headers, ACTOR_HEADER).unwrap_or_else(|| "anonymous".to_string());
+        let request_id = header_str(&parts.headers, REQUEST_ID_HEADER)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        let mut ctx = AuditContext::api(actor, Some(request_id));
+        ctx.reason = header_str(&parts.headers, REASON_HEADER);
+        Ok(ctx)
+    }
 }
 
 // ==================== Handlers ====================
 
+/// Map a repository failure to a 500 response
+fn internal_error(e: anyhow::Error) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": e.to_string() })),
+    )
+}
+
 async fn health_check() -> Json<Value> {
     Json(json!({
         "status": "healthy",
@@ -62,15 +226,24 @@ async fn health_check() -> Json<Value> {
     }))
 }
 
+/// Prometheus metrics
+async fn render_metrics(State(state): State<Arc<AppState>>) -> String {
+    state.metrics.render()
+}
+
 async fn list_indicators(
     State(state): State<Arc<AppState>>,
     Query(filter): Query<IndicatorFilter>,
+    Query(render): Query<RenderParams>,
 ) -> Result<Json<PaginatedResponse<Indicator>>, (StatusCode, Json<Value>)> {
     state
         .repo
         .search_indicators(&filter)
         .await
-        .map(Json)
+        .map(|mut page| {
+            page.data = page.data.into_iter().map(|i| render.indicator(i)).collect();
+            Json(page)
+        })
         .map_err(|e| {
             tracing::error!(error = %e, "Failed to list indicators");
             (
@@ -82,13 +255,26 @@ async fn list_indicators(
 
 async fn create_indicator(
     State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    Query(render): Query<RenderParams>,
     Json(req): Json<CreateIndicatorRequest>,
 ) -> Result<(StatusCode, Json<Indicator>), (StatusCode, Json<Value>)> {
     let indicator = state
         .repo
-        .upsert_indicator(&req, None)
+        .upsert_indicator(&req, None, &ctx)
         .await
         .map_err(|e| {
+            if let Some(rejection) = e.downcast_ref::<AllowlistRejection>() {
+                return (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    Json(json!({
+                        "error": e.to_string(),
+                        "allowlisted": true,
+                        "list_name": rejection.hit.list_name,
+                        "pattern": rejection.hit.pattern,
+                    })),
+                );
+            }
             tracing::error!(error = %e, "Failed to create indicator");
             (
                 StatusCode::BAD_REQUEST,
@@ -96,34 +282,28 @@ async fn create_indicator(
             )
         })?;
 
-    // Trigger async enrichment
-    let enrichment = state.enrichment.clone();
-    let repo = state.repo.clone();
-    let indicator_clone = indicator.clone();
-    
-    tokio::spawn(async move {
-        let results = enrichment.enrich_all(&indicator_clone).await;
-        for (enrichment_type, provider, data, ttl) in results {
-            if let Err(e) = repo
-                .add_enrichment(indicator_clone.id, &enrichment_type, &provider, data, Some(ttl))
-                .await
-            {
-                tracing::warn!(error = %e, "Failed to save enrichment");
-            }
-        }
-    });
+    // Queue enrichment
+    if let Err(e) = state
+        .repo
+        .enqueue_enrichment(indicator.id, EnrichmentPriority::Manual, EnrichMode::Cached, &ctx.actor)
+        .await
+    {
+        tracing::warn!(indicator = %indicator.id, error = %e, "Failed to queue enrichment");
+    }
 
-    Ok((StatusCode::CREATED, Json(indicator)))
+    Ok((StatusCode::CREATED, Json(render.indicator(indicator))))
 }
 
 async fn bulk_import(
     State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
     Json(req): Json<BulkImportRequest>,
 ) -> Result<Json<BulkImportResponse>, (StatusCode, Json<Value>)> {
     let total = req.indicators.len();
     let mut created = 0;
     let mut updated = 0;
     let mut failed = 0;
+    let mut suppressed = 0;
     let mut errors = vec![];
 
     for mut indicator_req in req.indicators {
@@ -140,11 +320,23 @@ async fn bulk_import(
             indicator_req.tags = Some(tags);
         }
 
-        match state.repo.upsert_indicator(&indicator_req, None).await {
-            Ok(_) => created += 1,
-            Err(e) => {
-                failed += 1;
-                errors.push(format!("{}: {}", indicator_req.value, e));
+        for indicator_req in expand_ranges(indicator_req) {
+            match state.repo.upsert_indicator(&indicator_req, None, &ctx).await {
+                Ok(indicator) => {
+                    created += 1;
+                    if let Err(e) = state
+                        .repo
+                        .enqueue_enrichment(indicator.id, EnrichmentPriority::Feed, EnrichMode::Cached, &ctx.actor)
+                        .await
+                    {
+                        tracing::warn!(indicator = %indicator.id, error = %e, "Failed to queue enrichment");
+                    }
+                }
+                Err(e) if e.is::<AllowlistRejection>() => suppressed += 1,
+                Err(e) => {
+                    failed += 1;
+                    errors.push(format!("{}: {}", indicator_req.value, e));
+                }
             }
         }
     }
@@ -154,13 +346,32 @@ async fn bulk_import(
         created,
         updated,
         failed,
+        suppressed,
         errors,
     }))
 }
 
+/// Split an address range into one request per covering CIDR block
+fn expand_ranges(req: CreateIndicatorRequest) -> Vec<CreateIndicatorRequest> {
+    let is_network = matches!(req.ioc_type, None | Some(IocType::Cidr));
+    let Some((start, end)) = parse_ip_range(req.value.trim()).filter(|_| is_network) else {
+        return vec![req];
+    };
+
+    ip_range_to_networks(start, end)
+        .into_iter()
+        .map(|network| CreateIndicatorRequest {
+            value: network.to_string(),
+            ioc_type: Some(IocType::Cidr),
+            ..req.clone()
+        })
+        .collect()
+}
+
 async fn get_indicator(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
+    Query(render): Query<RenderParams>,
 ) -> Result<Json<IndicatorResponse>, (StatusCode, Json<Value>)> {
     let indicator = state
         .repo
@@ -181,26 +392,94 @@ async fn get_indicator(
 
     let enrichments = state.repo.get_enrichments(id).await.unwrap_or_default();
     let sightings_count = state.repo.count_sightings(id).await.unwrap_or(0);
+    let history: Vec<AuditEvent> = state
+        .repo
+        .get_indicator_history(id)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|event| render.audit_event(event))
+        .collect();
+    let context = state.repo.get_indicator_context(id).await.unwrap_or_default();
+
+    // Other hashes of the same file
+    let file_record = match indicator.file_object_id {
+        Some(file_id) => state.repo.get_file_record(file_id).await.unwrap_or_default(),
+        None => None,
+    };
+    let (file, mut related_indicators) = match file_record {
+        Some(record) => {
+            let siblings: Vec<Indicator> = record
+                .indicators
+                .into_iter()
+                .filter(|i| i.id != indicator.id)
+                .map(|i| render.indicator(i))
+                .collect();
+            (Some(record.file), siblings)
+        }
+        None => (None, vec![]),
+    };
+
+    // Explicitly linked indicators (e.g. the host a URL is served from)
+    let mut relations = vec![];
+    for (relation, related) in state.repo.get_related_indicators(id).await.unwrap_or_default() {
+        if !related_indicators.iter().any(|i| i.id == related.id) {
+            related_indicators.push(render.indicator(related));
+        }
+        relations.push(relation);
+    }
 
     Ok(Json(IndicatorResponse {
-        indicator,
+        indicator: render.indicator(indicator),
         enrichments,
         sightings_count,
-        related_indicators: vec![], // TODO: implement related lookup
+        related_indicators,
+        relations,
+        context,
+        file,
+        history,
     }))
 }
 
 async fn delete_indicator(
-    State(_state): State<Arc<AppState>>,
-    Path(_id): Path<Uuid>,
-) -> StatusCode {
-    // TODO: implement delete
-    StatusCode::NO_CONTENT
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let deleted = state
+        .repo
+        .delete_indicator(id, &ctx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to delete indicator");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            )
+        })?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Indicator not found" })),
+        ))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EnrichParams {
+    /// Re-query every provider even when a fresh result is stored
+    #[serde(default)]
+    force: bool,
 }
 
 async fn enrich_indicator(
     State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
     Path(id): Path<Uuid>,
+    Query(params): Query<EnrichParams>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
     let indicator = state
         .repo
@@ -219,27 +498,75 @@ async fn enrich_indicator(
             )
         })?;
 
-    let results = state.enrichment.enrich_all(&indicator).await;
-    let mut enrichments_added = 0;
-
-    for (enrichment_type, provider, data, ttl) in results {
-        if let Ok(_) = state
-            .repo
-            .add_enrichment(id, &enrichment_type, &provider, data, Some(ttl))
-            .await
-        {
-            enrichments_added += 1;
-        }
-    }
+    let mode = if params.force { EnrichMode::Force } else { EnrichMode::Cached };
+    let summary = state
+        .enrichment
+        .enrich_and_store(&state.repo, &indicator, mode, &ctx)
+        .await;
 
     Ok(Json(json!({
         "message": "Enrichment complete",
-        "enrichments_added": enrichments_added,
+        "enrichments_added": summary.fetched,
+        "enrichments_cached": summary.cached,
+        "enrichments_changed": summary.changed,
     })))
 }
 
+#[derive(Debug, Deserialize)]
+struct HistoryParams {
+    limit: Option<i64>,
+}
+
+/// Enrichment snapshots from one provider, newest first
+async fn get_enrichment_history(
+    State(state): State<Arc<AppState>>,
+    Path((id, provider)): Path<(Uuid, String)>,
+    Query(params): Query<HistoryParams>,
+) -> Result<Json<Vec<Enrichment>>, (StatusCode, Json<Value>)> {
+    state
+        .repo
+        .get_enrichment_history(id, &provider, params.limit.unwrap_or(50).clamp(1, 500))
+        .await
+        .map(Json)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            )
+        })
+}
+
+#[derive(Debug, Deserialize)]
+struct QueueParams {
+    /// Which jobs to list (default: dead)
+    status: Option<EnrichmentJobStatus>,
+    limit: Option<i64>,
+}
+
+/// Enrichment queue depth and recent jobs
+async fn get_enrichment_queue(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<QueueParams>,
+) -> Result<Json<EnrichmentQueueStats>, (StatusCode, Json<Value>)> {
+    state
+        .repo
+        .get_enrichment_queue_stats(
+            params.status.unwrap_or(EnrichmentJobStatus::Dead),
+            params.limit.unwrap_or(100).clamp(1, 1000),
+        )
+        .await
+        .map(Json)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            )
+        })
+}
+
 async fn add_sighting(
     State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
     Path(id): Path<Uuid>,
     Json(body): Json<Value>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
@@ -252,7 +579,7 @@ async fn add_sighting(
 
     let sighting = state
         .repo
-        .add_sighting(id, source, context)
+        .add_sighting(id, source, context, None, &ctx)
         .await
         .map_err(|e| {
             (
@@ -261,6 +588,14 @@ async fn add_sighting(
             )
         })?;
 
+    if let Err(e) = state
+        .repo
+        .enqueue_enrichment(id, EnrichmentPriority::Sighting, EnrichMode::Cached, &ctx.actor)
+        .await
+    {
+        tracing::warn!(indicator = %id, error = %e, "Failed to queue enrichment");
+    }
+
     Ok(Json(json!({
         "id": sighting.id,
         "observed_at": sighting.observed_at,
@@ -278,20 +613,30 @@ async fn lookup_indicator(
         )
     })?;
 
-    lookup_by_value(&state, value).await
+    let render = RenderParams {
+        defang: params.get("defang").is_some_and(|v| v == "true"),
+    };
+
+    lookup_by_value(&state, value, &render).await
 }
 
 async fn lookup_indicator_by_path(
     State(state): State<Arc<AppState>>,
     Path(value): Path<String>,
+    Query(render): Query<RenderParams>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    lookup_by_value(&state, &value).await
+    lookup_by_value(&state, &value, &render).await
 }
 
 async fn lookup_by_value(
     state: &Arc<AppState>,
     value: &str,
+    render: &RenderParams,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    if let Some(base) = value.trim().strip_prefix("*.") {
+        return wildcard_lookup(state, base, render).await;
+    }
+
     let indicator = state
         .repo
         .get_indicator_by_value(value)
@@ -303,22 +648,429 @@ async fn lookup_by_value(
             )
         })?;
 
+    let matching_networks = match detect_ioc_type(value).and_then(|ioc_type| {
+        indicator_network(&normalize_ioc(value, &ioc_type), &ioc_type)
+    }) {
+        Some(network) => state
+            .repo
+            .get_containing_networks(network)
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": e.to_string() })),
+                )
+            })?
+            .into_iter()
+            .filter(|n| indicator.as_ref().is_none_or(|i| i.id != n.id))
+            .map(|n| render.indicator(n))
+            .collect(),
+        None => vec![],
+    };
+
     match indicator {
         Some(ind) => {
             let enrichments = state.repo.get_enrichments(ind.id).await.unwrap_or_default();
+            let file = match ind.file_object_id {
+                Some(file_id) => state.repo.get_file_record(file_id).await.unwrap_or_default(),
+                None => None,
+            }
+            .map(|mut record| {
+                record.indicators = record.indicators.into_iter().map(|i| render.indicator(i)).collect();
+                record
+            });
+
             Ok(Json(json!({
                 "found": true,
-                "indicator": ind,
+                "indicator": render.indicator(ind),
                 "enrichments": enrichments,
+                "file": file,
+                "matching_networks": matching_networks,
             })))
         }
         None => Ok(Json(json!({
-            "found": false,
-            "value": value,
+            "found": !matching_networks.is_empty(),
+            "value": match detect_ioc_type(value) {
+                Some(ioc_type) => render.value(value, &ioc_type),
+                None => value.to_string(),
+            },
+            "matching_networks": matching_networks,
         }))),
     }
 }
 
+/// `*.evil.co.uk`: every domain and URL indicator on the domain or below it
+async fn wildcard_lookup(
+    state: &Arc<AppState>,
+    base: &str,
+    render: &RenderParams,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let base = normalize_ioc(base, &IocType::Domain);
+    let indicators = state
+        .repo
+        .find_under_domain(&base, MAX_WILDCARD_MATCHES)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            )
+        })?;
+
+    Ok(Json(json!({
+        "found": !indicators.is_empty(),
+        "wildcard": format!("*.{}", render.value(&base, &IocType::Domain)),
+        "registrable_domain": registrable_domain(&base),
+        "total": indicators.len(),
+        "indicators": indicators.into_iter().map(|i| render.indicator(i)).collect::<Vec<_>>(),
+    })))
+}
+
+async fn batch_lookup(
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    Query(render): Query<RenderParams>,
+    Json(req): Json<BatchLookupRequest>,
+) -> Result<Json<BatchLookupResponse>, (StatusCode, Json<Value>)> {
+    if req.values.len() > MAX_BATCH_LOOKUP {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(json!({
+                "error": format!("Batch lookup accepts at most {} values", MAX_BATCH_LOOKUP),
+            })),
+        ));
+    }
+
+    // Detect and normalize every value up front
+    let keys: Vec<Option<(IocType, String)>> = req
+        .values
+        .iter()
+        .map(|value| {
+            detect_ioc_type(value).map(|ioc_type| {
+                let normalized = normalize_ioc(value, &ioc_type);
+                (ioc_type, normalized)
+            })
+        })
+        .collect();
+
+    let unique_keys: Vec<(IocType, String)> = keys
+        .iter()
+        .flatten()
+        .cloned()
+        .collect::<std::collections::HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    let matches = state
+        .repo
+        .get_indicators_with_enrichments(&unique_keys)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Batch lookup failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            )
+        })?;
+
+    let networks: Vec<_> = unique_keys
+        .iter()
+        .filter_map(|(ioc_type, value)| indicator_network(value, ioc_type))
+        .collect::<std::collections::HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    let mut containing: std::collections::HashMap<_, Vec<Indicator>> = std::collections::HashMap::new();
+    for (queried, network) in state
+        .repo
+        .get_containing_networks_batch(&networks)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Batch network lookup failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            )
+        })?
+    {
+        containing.entry(queried).or_default().push(network);
+    }
+
+    let by_key: std::collections::HashMap<(IocType, String), _> = matches
+        .into_iter()
+        .map(|(indicator, enrichments)| {
+            ((indicator.ioc_type.clone(), indicator.value.clone()), (indicator, enrichments))
+        })
+        .collect();
+
+    let sighting_source = req.source.as_deref().unwrap_or("batch_lookup");
+    let mut results = Vec::with_capacity(req.values.len());
+
+    for (value, key) in req.values.into_iter().zip(keys) {
+        let Some((ioc_type, normalized)) = key else {
+            results.push(BatchLookupResult {
+                value,
+                ioc_type: None,
+                normalized: None,
+                found: false,
+                indicator: None,
+                enrichments: vec![],
+                threat_score: None,
+                matching_networks: vec![],
+                error: Some("Could not detect IOC type".to_string()),
+            });
+            continue;
+        };
+
+        let hit = by_key.get(&(ioc_type.clone(), normalized.clone()));
+
+        if let (Some((indicator, _)), true) = (hit, req.record_sightings) {
+            let context = json!({ "lookup_value": value });
+            if let Err(e) = state
+                .repo
+                .add_sighting(indicator.id, sighting_source, Some(context), None, &ctx)
+                .await
+            {
+                tracing::warn!(error = %e, indicator = %indicator.value, "Failed to record sighting");
+            }
+        }
+
+        let matching_networks: Vec<Indicator> = indicator_network(&normalized, &ioc_type)
+            .and_then(|network| containing.get(&network))
+            .into_iter()
+            .flatten()
+            .filter(|n| hit.is_none_or(|(indicator, _)| indicator.id != n.id))
+            .cloned()
+            .collect();
+
+        results.push(BatchLookupResult {
+            value: render.value(&value, &ioc_type),
+            normalized: Some(render.value(&normalized, &ioc_type)),
+            ioc_type: Some(ioc_type),
+            found: hit.is_some() || !matching_networks.is_empty(),
+            threat_score: hit
+                .map(|(indicator, _)| indicator.threat_score)
+                .or_else(|| matching_networks.first().map(|n| n.threat_score)),
+            matching_networks: matching_networks.into_iter().map(|n| render.indicator(n)).collect(),
+            indicator: hit.map(|(indicator, _)| render.indicator(indicator.clone())),
+            enrichments: hit.map(|(_, enrichments)| enrichments.clone()).unwrap_or_default(),
+            error: None,
+        });
+    }
+
+    Ok(Json(BatchLookupResponse {
+        total: results.len(),
+        found: results.iter().filter(|r| r.found).count(),
+        results,
+    }))
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+    Txt,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportParams {
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+/// Everything stored under a registrable domain, grouped by host
+async fn get_domain_rollup(
+    State(state): State<Arc<AppState>>,
+    Path(domain): Path<String>,
+) -> Result<Json<DomainRollup>, (StatusCode, Json<Value>)> {
+    let domain = normalize_ioc(&domain, &IocType::Domain);
+    let registrable = registrable_domain(&domain).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("{} has no registrable domain", domain) })),
+        )
+    })?;
+
+    let subdomains = state
+        .repo
+        .get_domain_rollup(&registrable)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            )
+        })?;
+
+    Ok(Json(DomainRollup {
+        public_suffix: public_suffix(&registrable),
+        total: subdomains.iter().map(|s| s.indicators).sum(),
+        registrable_domain: registrable,
+        subdomains,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct PassiveDnsParams {
+    limit: Option<i64>,
+    /// Window for the fast-flux assessment (default: 24)
+    window_hours: Option<i64>,
+}
+
+/// Forward and reverse passive DNS history of a domain or IP
+async fn get_passive_dns(
+    State(state): State<Arc<AppState>>,
+    Path(value): Path<String>,
+    Query(params): Query<PassiveDnsParams>,
+) -> Result<Json<PassiveDnsResponse>, (StatusCode, Json<Value>)> {
+    let ioc_type = detect_ioc_type(&value)
+        .filter(|t| matches!(t, IocType::Domain | IocType::Ip))
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": format!("{} is not a domain or IP address", value) })),
+            )
+        })?;
+    let value = normalize_ioc(&value, &ioc_type);
+
+    let (forward, reverse) = state
+        .repo
+        .get_passive_dns(&value, params.limit.unwrap_or(100).clamp(1, 1000))
+        .await
+        .map_err(internal_error)?;
+
+    let fast_flux = match ioc_type {
+        IocType::Domain => {
+            let window_hours = params.window_hours.unwrap_or(24).clamp(1, 24 * 30);
+            let (distinct_ips, distinct_networks) = state
+                .repo
+                .get_resolution_churn(&value, chrono::Duration::hours(window_hours))
+                .await
+                .map_err(internal_error)?;
+            Some(FastFluxAssessment {
+                window_hours,
+                distinct_ips,
+                distinct_networks,
+                suspected: distinct_ips >= FAST_FLUX_MIN_IPS && distinct_networks >= FAST_FLUX_MIN_NETWORKS,
+            })
+        }
+        _ => None,
+    };
+
+    Ok(Json(PassiveDnsResponse { value, forward, reverse, fast_flux }))
+}
+
+/// Export every indicator matching the list filters as JSON, CSV or a plain value list
+async fn export_indicators(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<IndicatorFilter>,
+    Query(params): Query<ExportParams>,
+    Query(render): Query<RenderParams>,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let indicators: Vec<Indicator> = state
+        .repo
+        .export_indicators(&filter, MAX_EXPORT)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to export indicators");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            )
+        })?
+        .into_iter()
+        .map(|i| render.indicator(i))
+        .collect();
+
+    let response = match params.format {
+        ExportFormat::Json => Json(indicators).into_response(),
+        ExportFormat::Txt => {
+            let body: String = indicators.iter().map(|i| format!("{}\n", i.value)).collect();
+            ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], body).into_response()
+        }
+        ExportFormat::Csv => {
+            let mut body = String::from(
+                "ioc_type,value,severity,confidence,threat_score,tlp,first_seen,last_seen,tags\n",
+            );
+            for i in &indicators {
+                let row = [
+                    i.ioc_type.to_string(),
+                    i.value.clone(),
+                    json!(i.severity).as_str().unwrap_or_default().to_string(),
+                    i.confidence.to_string(),
+                    i.threat_score.to_string(),
+                    json!(i.tlp).as_str().unwrap_or_default().to_string(),
+                    i.first_seen.to_rfc3339(),
+                    i.last_seen.to_rfc3339(),
+                    i.tags.join(";"),
+                ];
+                let escaped: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+                body.push_str(&escaped.join(","));
+                body.push('\n');
+            }
+            ([(header::CONTENT_TYPE, "text/csv; charset=utf-8")], body).into_response()
+        }
+    };
+
+    Ok(response)
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractParams {
+    format: Option<DocumentFormat>,
+    filename: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractRequest {
+    text: String,
+    format: Option<DocumentFormat>,
+}
+
+/// Extract candidate indicators from pasted text (JSON `{"text": ...}`) or an
+/// uploaded .txt/.eml/.html body; nothing is stored until the caller bulk imports
+async fn extract(
+    Query(params): Query<ExtractParams>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<ExtractionResult>, (StatusCode, Json<Value>)> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("text/plain");
+
+    let (text, body_format) = if content_type.starts_with("application/json") {
+        let req: ExtractRequest = serde_json::from_slice(&body).map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": format!("Invalid JSON body: {}", e) })),
+            )
+        })?;
+        (req.text, req.format)
+    } else {
+        (String::from_utf8_lossy(&body).into_owned(), None)
+    };
+
+    let format = params
+        .format
+        .or(body_format)
+        .or_else(|| params.filename.as_deref().and_then(DocumentFormat::from_filename))
+        .or_else(|| DocumentFormat::from_content_type(content_type))
+        .unwrap_or_default();
+
+    Ok(Json(extract_indicators(&text, format)))
+}
+
 async fn get_stats(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<DashboardStats>, (StatusCode, Json<Value>)> {
@@ -335,6 +1087,24 @@ async fn get_stats(
         })
 }
 
+async fn list_audit_events(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<AuditFilter>,
+) -> Result<Json<PaginatedResponse<AuditEvent>>, (StatusCode, Json<Value>)> {
+    state
+        .repo
+        .list_audit_events(&filter)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to query audit log");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            )
+        })
+}
+
 async fn list_sources(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
@@ -352,11 +1122,307 @@ async fn list_sources(
     Ok(Json(json!({ "sources": sources })))
 }
 
+#[derive(Debug, Deserialize)]
+struct RefreshParams {
+    /// Only refresh this feed
+    feed: Option<String>,
+}
+
+/// Run the configured collectors in the background
 async fn refresh_feeds(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<RefreshParams>,
+) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
+    let collectors: Vec<Arc<dyn FeedCollector>> = state
+        .collectors
+        .iter()
+        .filter(|c| c.is_configured())
+        .filter(|c| params.feed.as_deref().is_none_or(|feed| feed == c.name()))
+        .cloned()
+        .collect();
+
+    if collectors.is_empty() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "No configured feed matches" })),
+        ));
+    }
+
+    let feeds: Vec<&str> = collectors.iter().map(|c| c.name()).collect();
+    let repo = state.repo.clone();
+    tokio::spawn(async move {
+        for collector in collectors {
+            run_collector(collector.as_ref(), &repo).await;
+        }
+    });
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "message": "Feed refresh triggered",
+            "feeds": feeds,
+        })),
+    ))
+}
+
+/// Store HoneyTrap events pushed as a JSON event, a JSON array or NDJSON.
+/// Each event is recorded as a sighting of its attacker IP.
+async fn ingest_honeytrap(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<FeedResult>, (StatusCode, Json<Value>)> {
+    let token = state.honeytrap_token.as_deref().unwrap_or_default();
+    let presented = header_str(&headers, header::AUTHORIZATION.as_str());
+    let presented = presented.as_deref().and_then(|h| h.strip_prefix("Bearer ")).unwrap_or_default();
+    // Constant time, and never equal when no token is configured
+    if token.is_empty() || !bool::from(presented.as_bytes().ct_eq(token.as_bytes())) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "Invalid or missing bearer token" })),
+        ));
+    }
+
+    let (events, malformed) = honeytrap::parse_events(&body);
+    if events.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "No valid HoneyTrap events in body", "malformed": malformed })),
+        ));
+    }
+
+    let mut result = honeytrap::ingest(&state.repo, &events, None).await;
+    if malformed > 0 {
+        result.errors.push(format!("{} malformed events skipped", malformed));
+    }
+
+    Ok(Json(result))
+}
+
+// ==================== Allowlist ====================
+
+async fn list_allowlist(
+    State(state): State<Arc<AppState>>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // TODO: trigger feed refresh job
+    let entries = state
+        .repo
+        .list_allowlist_entries()
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            )
+        })?;
+
     Ok(Json(json!({
-        "message": "Feed refresh triggered",
+        "entries": entries,
+        "lists": state.repo.allowlist().summary(),
     })))
 }
+
+async fn create_allowlist_entry(
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    Json(req): Json<CreateAllowlistEntryRequest>,
+) -> Result<(StatusCode, Json<AllowlistEntry>), (StatusCode, Json<Value>)> {
+    state
+        .repo
+        .create_allowlist_entry(&req, &ctx)
+        .await
+        .map(|entry| (StatusCode::CREATED, Json(entry)))
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": format!("{:#}", e) })),
+            )
+        })
+}
+
+async fn delete_allowlist_entry(
+    State(state): State<Arc<AppState>>,
+    ctx: AuditContext,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let deleted = state
+        .repo
+        .delete_allowlist_entry(id, &ctx)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            )
+        })?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Allowlist entry not found" })),
+        ))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AllowlistCheckParams {
+    value: String,
+    ioc_type: Option<IocType>,
+}
+
+/// Show which rule, if any, a value would match
+async fn check_allowlist(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AllowlistCheckParams>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let ioc_type = params.ioc_type.or_else(|| detect_ioc_type(&params.value)).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Could not detect IOC type" })),
+        )
+    })?;
+    let normalized = normalize_ioc(&params.value, &ioc_type);
+
+    Ok(Json(match state.repo.allowlist().check(&ioc_type, &normalized) {
+        Some(hit) => json!({
+            "allowlisted": true,
+            "ioc_type": ioc_type,
+            "value": normalized,
+            "list_name": hit.list_name,
+            "pattern": hit.pattern,
+            "action": hit.action,
+            "max_severity": hit.severity_cap(),
+        }),
+        None => json!({
+            "allowlisted": false,
+            "ioc_type": ioc_type,
+            "value": normalized,
+        }),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct SuppressionReportParams {
+    since: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Indicators suppressed per feed
+async fn get_suppression_report(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SuppressionReportParams>,
+) -> Result<Json<Vec<SuppressionSummary>>, (StatusCode, Json<Value>)> {
+    state
+        .repo
+        .get_suppression_report(params.since)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            )
+        })
+}
+
+// ==================== Honeypot ====================
+
+#[derive(Debug, Deserialize)]
+struct HoneypotSessionParams {
+    ip: Option<String>,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    limit: Option<i64>,
+}
+
+/// Honeypot sessions, most recent first, optionally for one attacker IP
+async fn list_honeypot_sessions(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HoneypotSessionParams>,
+) -> Result<Json<Vec<HoneypotSession>>, (StatusCode, Json<Value>)> {
+    state
+        .repo
+        .list_honeypot_sessions(params.ip.as_deref(), params.since, params.limit.unwrap_or(100).clamp(1, 1000))
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+async fn get_honeypot_session(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<HoneypotSessionDetail>, (StatusCode, Json<Value>)> {
+    state
+        .repo
+        .get_honeypot_session(&session_id)
+        .await
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Honeypot session not found" })),
+            )
+        })
+}
+
+#[derive(Debug, Deserialize)]
+struct HoneypotTopParams {
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    limit: Option<i64>,
+}
+
+/// Most attempted username/password pairs
+async fn get_honeypot_credentials(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HoneypotTopParams>,
+) -> Result<Json<Vec<CredentialStat>>, (StatusCode, Json<Value>)> {
+    state
+        .repo
+        .top_honeypot_credentials(params.since, params.limit.unwrap_or(50).clamp(1, 1000))
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+/// Most executed commands
+async fn get_honeypot_commands(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HoneypotTopParams>,
+) -> Result<Json<Vec<CommandStat>>, (StatusCode, Json<Value>)> {
+    state
+        .repo
+        .top_honeypot_commands(params.since, params.limit.unwrap_or(50).clamp(1, 1000))
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+#[derive(Debug, Deserialize)]
+struct HoneypotActivityParams {
+    bucket: Option<String>,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Sessions, attacker IPs, login attempts and commands over time
+async fn get_honeypot_activity(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HoneypotActivityParams>,
+) -> Result<Json<Vec<HoneypotActivity>>, (StatusCode, Json<Value>)> {
+    let bucket = params.bucket.as_deref().unwrap_or("hour");
+    if !matches!(bucket, "hour" | "day" | "week") {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "bucket must be hour, day or week" })),
+        ));
+    }
+    let since = params
+        .since
+        .unwrap_or_else(|| chrono::Utc::now() - chrono::Duration::days(7));
+
+    state
+        .repo
+        .honeypot_activity(bucket, since)
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
@@ -0,0 +1,435 @@
+//! abuse.ch feed collectors (URLhaus, ThreatFox, MalwareBazaar, Feodo Tracker)
+//!
+//! Each feed is its own collector and `ioc_sources` row. The bulk downloads
+//! are public; MalwareBazaar's API needs an abuse.ch Auth-Key, which is also
+//! sent to the other feeds when configured.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::collectors::FeedCollector;
+use crate::models::{CreateIndicatorRequest, HashAlgorithm, IocType, Severity, Tlp};
+
+const URLHAUS_ONLINE_CSV: &str = "https://urlhaus.abuse.ch/downloads/csv_online/";
+const THREATFOX_RECENT_JSON: &str = "https://threatfox.abuse.ch/export/json/recent/";
+const MALWAREBAZAAR_API_URL: &str = "https://mb-api.abuse.ch/api/v1/";
+const FEODO_TRACKER_IPS: &str = "https://feodotracker.abuse.ch/downloads/ipblocklist.txt";
+
+/// HTTP client shared by the abuse.ch collectors
+#[derive(Clone)]
+struct AbuseChClient {
+    client: Client,
+    auth_key: Option<String>,
+}
+
+impl AbuseChClient {
+    fn new(auth_key: Option<String>) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(120))
+            .build()
+            .expect("Failed to create HTTP client");
+        Self { client, auth_key }
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.auth_key {
+            Some(key) => request.header("Auth-Key", key),
+            None => request,
+        }
+    }
+
+    async fn get_text(&self, url: &str) -> Result<String> {
+        let response = self
+            .authorize(self.client.get(url))
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch {}", url))?;
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to fetch {}: {}", url, response.status());
+        }
+        Ok(response.text().await?)
+    }
+}
+
+/// Split one line of an abuse.ch CSV export (`"a","b,c","d"`)
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Comma separated abuse.ch tags, lowercased
+fn split_tags(tags: &str) -> impl Iterator<Item = String> + '_ {
+    tags.split(',')
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty() && t != "none")
+}
+
+// ==================== URLhaus ====================
+
+/// URLhaus collector: URLs currently serving malware
+pub struct UrlhausCollector {
+    http: AbuseChClient,
+}
+
+impl UrlhausCollector {
+    pub fn new(auth_key: Option<String>) -> Self {
+        Self { http: AbuseChClient::new(auth_key) }
+    }
+
+    /// Rows of `id,dateadded,url,url_status,last_online,threat,tags,urlhaus_link,reporter`
+    fn parse(text: &str) -> Vec<CreateIndicatorRequest> {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let fields = parse_csv_line(line);
+                let [_, _, url, status, _, threat, tags, ..] = fields.as_slice() else {
+                    return None;
+                };
+                if status != "online" {
+                    return None;
+                }
+
+                let mut all_tags = vec!["urlhaus".to_string(), format!("threat:{}", threat)];
+                all_tags.extend(split_tags(tags));
+
+                Some(CreateIndicatorRequest {
+                    value: url.clone(),
+                    ioc_type: Some(IocType::Url),
+                    hash_algorithm: None,
+                    severity: Some(Severity::High),
+                    confidence: Some(85),
+                    tlp: Some(Tlp::White),
+                    tags: Some(all_tags),
+                    source: Some("urlhaus".to_string()),
+                    expiration_days: Some(30),
+                    context: None,
+                })
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl FeedCollector for UrlhausCollector {
+    fn name(&self) -> &'static str {
+        "urlhaus"
+    }
+
+    async fn fetch(&self) -> Result<Vec<CreateIndicatorRequest>> {
+        let text = self.http.get_text(URLHAUS_ONLINE_CSV).await?;
+        Ok(Self::parse(&text))
+    }
+}
+
+// ==================== ThreatFox ====================
+
+#[derive(Debug, Deserialize)]
+struct ThreatFoxIoc {
+    ioc_value: String,
+    ioc_type: String,
+    threat_type: Option<String>,
+    malware_printable: Option<String>,
+    confidence_level: Option<i32>,
+    tags: Option<String>,
+}
+
+/// ThreatFox collector: IOCs shared in the last 48 hours
+pub struct ThreatFoxCollector {
+    http: AbuseChClient,
+}
+
+impl ThreatFoxCollector {
+    pub fn new(auth_key: Option<String>) -> Self {
+        Self { http: AbuseChClient::new(auth_key) }
+    }
+
+    /// Convert ThreatFox IOC type to our IocType
+    fn convert_type(ioc_type: &str) -> Option<(IocType, Option<HashAlgorithm>)> {
+        match ioc_type {
+            "ip:port" => Some((IocType::Ip, None)),
+            "domain" => Some((IocType::Domain, None)),
+            "url" => Some((IocType::Url, None)),
+            "md5_hash" => Some((IocType::Hash, Some(HashAlgorithm::Md5))),
+            "sha1_hash" => Some((IocType::Hash, Some(HashAlgorithm::Sha1))),
+            "sha256_hash" => Some((IocType::Hash, Some(HashAlgorithm::Sha256))),
+            _ => None,
+        }
+    }
+
+    fn convert(ioc: ThreatFoxIoc) -> Option<CreateIndicatorRequest> {
+        let (ioc_type, hash_algorithm) = Self::convert_type(&ioc.ioc_type)?;
+        let mut tags = vec!["threatfox".to_string()];
+
+        // `ip:port` values keep the port as a tag
+        let value = match ioc.ioc_type.as_str() {
+            "ip:port" => {
+                let (ip, port) = ioc.ioc_value.rsplit_once(':')?;
+                tags.push(format!("port:{}", port));
+                ip.trim_matches(['[', ']']).to_string()
+            }
+            _ => ioc.ioc_value,
+        };
+
+        if let Some(ref threat) = ioc.threat_type {
+            tags.push(format!("threat:{}", threat));
+        }
+        if let Some(ref malware) = ioc.malware_printable
+            && malware != "Unknown malware"
+        {
+            tags.push(format!("malware:{}", malware));
+        }
+        tags.extend(ioc.tags.as_deref().map(split_tags).into_iter().flatten());
+
+        let severity = match ioc.threat_type.as_deref() {
+            Some("botnet_cc") | Some("payload_delivery") => Severity::High,
+            _ => Severity::Medium,
+        };
+
+        Some(CreateIndicatorRequest {
+            value,
+            ioc_type: Some(ioc_type),
+            hash_algorithm,
+            severity: Some(severity),
+            confidence: Some(ioc.confidence_level.unwrap_or(50).clamp(0, 100)),
+            tlp: Some(Tlp::White),
+            tags: Some(tags),
+            source: Some("threatfox".to_string()),
+            expiration_days: Some(90),
+            context: None,
+        })
+    }
+}
+
+#[async_trait]
+impl FeedCollector for ThreatFoxCollector {
+    fn name(&self) -> &'static str {
+        "threatfox"
+    }
+
+    async fn fetch(&self) -> Result<Vec<CreateIndicatorRequest>> {
+        let text = self.http.get_text(THREATFOX_RECENT_JSON).await?;
+        // Keyed by ThreatFox IOC id
+        let export: HashMap<String, Vec<ThreatFoxIoc>> =
+            serde_json::from_str(&text).context("Failed to parse ThreatFox export")?;
+
+        Ok(export.into_values().flatten().filter_map(Self::convert).collect())
+    }
+}
+
+// ==================== MalwareBazaar ====================
+
+#[derive(Debug, Deserialize)]
+struct BazaarResponse {
+    query_status: String,
+    #[serde(default)]
+    data: Vec<BazaarSample>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BazaarSample {
+    sha256_hash: String,
+    file_type: Option<String>,
+    signature: Option<String>,
+    tags: Option<Vec<String>>,
+}
+
+/// MalwareBazaar collector: samples submitted in the last hour
+pub struct MalwareBazaarCollector {
+    http: AbuseChClient,
+}
+
+impl MalwareBazaarCollector {
+    pub fn new(auth_key: Option<String>) -> Self {
+        Self { http: AbuseChClient::new(auth_key) }
+    }
+
+    fn convert(sample: BazaarSample) -> CreateIndicatorRequest {
+        let mut tags = vec!["malwarebazaar".to_string()];
+        if let Some(ref signature) = sample.signature {
+            tags.push(format!("malware:{}", signature));
+        }
+        if let Some(ref file_type) = sample.file_type {
+            tags.push(format!("file_type:{}", file_type));
+        }
+        tags.extend(sample.tags.into_iter().flatten().map(|t| t.to_lowercase()));
+
+        CreateIndicatorRequest {
+            value: sample.sha256_hash,
+            ioc_type: Some(IocType::Hash),
+            hash_algorithm: Some(HashAlgorithm::Sha256),
+            severity: Some(Severity::High),
+            confidence: Some(90),
+            tlp: Some(Tlp::White),
+            tags: Some(tags),
+            source: Some("malwarebazaar".to_string()),
+            expiration_days: Some(365),
+            context: None,
+        }
+    }
+}
+
+#[async_trait]
+impl FeedCollector for MalwareBazaarCollector {
+    fn name(&self) -> &'static str {
+        "malwarebazaar"
+    }
+
+    async fn fetch(&self) -> Result<Vec<CreateIndicatorRequest>> {
+        let response = self
+            .http
+            .authorize(self.http.client.post(MALWAREBAZAAR_API_URL))
+            .form(&[("query", "get_recent"), ("selector", "time")])
+            .send()
+            .await
+            .context("Failed to fetch MalwareBazaar samples")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("MalwareBazaar API error: {} - {}", status, body);
+        }
+
+        let data: BazaarResponse = response
+            .json()
+            .await
+            .context("Failed to parse MalwareBazaar response")?;
+
+        match data.query_status.as_str() {
+            "ok" => Ok(data.data.into_iter().map(Self::convert).collect()),
+            "no_results" => Ok(vec![]),
+            status => anyhow::bail!("MalwareBazaar query failed: {}", status),
+        }
+    }
+
+    fn is_configured(&self) -> bool {
+        self.http.auth_key.is_some()
+    }
+}
+
+// ==================== Feodo Tracker ====================
+
+/// Feodo Tracker collector: botnet C2 servers (banking trojans)
+pub struct FeodoTrackerCollector {
+    http: AbuseChClient,
+}
+
+impl FeodoTrackerCollector {
+    pub fn new(auth_key: Option<String>) -> Self {
+        Self { http: AbuseChClient::new(auth_key) }
+    }
+}
+
+#[async_trait]
+impl FeedCollector for FeodoTrackerCollector {
+    fn name(&self) -> &'static str {
+        "feodo_tracker"
+    }
+
+    async fn fetch(&self) -> Result<Vec<CreateIndicatorRequest>> {
+        let text = self.http.get_text(FEODO_TRACKER_IPS).await?;
+
+        Ok(text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter(|ip| ip.parse::<std::net::IpAddr>().is_ok())
+            .map(|ip| CreateIndicatorRequest {
+                value: ip.to_string(),
+                ioc_type: Some(IocType::Ip),
+                hash_algorithm: None,
+                severity: Some(Severity::High),
+                confidence: Some(80),
+                tlp: Some(Tlp::White),
+                tags: Some(vec!["botnet".to_string(), "banking_trojan".to_string()]),
+                source: Some("feodo_tracker".to_string()),
+                expiration_days: Some(30),
+                context: None,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn threatfox(ioc_type: &str, value: &str) -> ThreatFoxIoc {
+        ThreatFoxIoc {
+            ioc_value: value.to_string(),
+            ioc_type: ioc_type.to_string(),
+            threat_type: Some("botnet_cc".to_string()),
+            malware_printable: Some("Cobalt Strike".to_string()),
+            confidence_level: Some(100),
+            tags: Some("CobaltStrike,c2, None".to_string()),
+        }
+    }
+
+    #[test]
+    fn splits_quoted_csv_fields() {
+        assert_eq!(parse_csv_line(r#""a","b,c","d""#), vec!["a", "b,c", "d"]);
+        assert_eq!(parse_csv_line(r#""say ""hi""",,x"#), vec![r#"say "hi""#, "", "x"]);
+        assert_eq!(parse_csv_line(""), vec![""]);
+    }
+
+    #[test]
+    fn urlhaus_keeps_online_urls() {
+        let csv = r#"################################################################
+# abuse.ch URLhaus Database Dump (CSV - online URLs only)      #
+# id,dateadded,url,url_status,last_online,threat,tags,urlhaus_link,reporter
+"3161234","2024-06-01 10:12:03","http://198.51.100.7:44321/Mozi.m","online","2024-06-01 10:12:03","malware_download","elf,Mozi","https://urlhaus.abuse.ch/url/3161234/","lrz_urlhaus"
+"3161233","2024-06-01 10:11:45","http://evil.example.test/bins/x86","offline","","malware_download","None","https://urlhaus.abuse.ch/url/3161233/","abuse_ch"
+"truncated","row"
+"#;
+
+        let indicators = UrlhausCollector::parse(csv);
+        assert_eq!(indicators.len(), 1);
+        assert_eq!(indicators[0].value, "http://198.51.100.7:44321/Mozi.m");
+        assert_eq!(
+            indicators[0].tags.as_deref().unwrap(),
+            ["urlhaus", "threat:malware_download", "elf", "mozi"]
+        );
+    }
+
+    #[test]
+    fn threatfox_splits_ip_port() {
+        let ioc = ThreatFoxCollector::convert(threatfox("ip:port", "198.51.100.7:8443")).unwrap();
+        assert_eq!(ioc.value, "198.51.100.7");
+        assert_eq!(ioc.ioc_type, Some(IocType::Ip));
+        assert_eq!(ioc.severity, Some(Severity::High));
+        assert_eq!(
+            ioc.tags.as_deref().unwrap(),
+            ["threatfox", "port:8443", "threat:botnet_cc", "malware:Cobalt Strike", "cobaltstrike", "c2"]
+        );
+
+        let ioc = ThreatFoxCollector::convert(threatfox("ip:port", "[2001:db8::7]:443")).unwrap();
+        assert_eq!(ioc.value, "2001:db8::7");
+        assert!(ioc.tags.unwrap().contains(&"port:443".to_string()));
+
+        assert!(ThreatFoxCollector::convert(threatfox("ip:port", "198.51.100.7")).is_none());
+        assert!(ThreatFoxCollector::convert(threatfox("unknown_type", "x")).is_none());
+
+        let hash = ThreatFoxCollector::convert(threatfox("sha1_hash", "da39a3ee5e6b4b0d3255bfef95601890afd80709")).unwrap();
+        assert_eq!(hash.hash_algorithm, Some(HashAlgorithm::Sha1));
+    }
+}
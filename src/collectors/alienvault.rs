@@ -2,15 +2,56 @@
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
+use chrono::Utc;
 use reqwest::Client;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-use crate::collectors::FeedCollector;
-use crate::models::{CreateIndicatorRequest, IocType, Severity, Tlp};
+use crate::collectors::{FeedBatch, FeedCollector};
+use crate::models::{ContextInput, CreateIndicatorRequest, HashAlgorithm, IocType, Severity, Tlp};
 
 const OTX_API_URL: &str = "https://otx.alienvault.com/api/v1";
 
+/// Pulses per page
+const PAGE_SIZE: &str = "50";
+
+/// Pages followed per run; a longer backlog resumes on the next run
+const MAX_PAGES: usize = 200;
+
+/// Window fetched when there is no saved cursor yet
+const INITIAL_SYNC_DAYS: i64 = 7;
+
+/// Sync position saved between runs. A completed listing leaves only the
+/// newest `modified` seen; a listing cut short by `MAX_PAGES` also keeps the
+/// page to continue from, so a long backlog is worked through over several
+/// runs instead of restarting each time.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct OtxCursor {
+    /// `modified_since` of the listing in progress
+    since: String,
+    /// Newest pulse `modified` stored so far
+    newest: Option<String>,
+    /// Next page of the listing in progress
+    next: Option<String>,
+}
+
+impl OtxCursor {
+    /// A saved cursor: a bare timestamp or a listing in progress
+    fn parse(cursor: &str) -> Self {
+        serde_json::from_str(cursor).unwrap_or_else(|_| OtxCursor {
+            since: cursor.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn to_cursor_string(&self) -> String {
+        match self.next {
+            Some(_) => serde_json::to_string(self).unwrap_or_default(),
+            None => self.newest.clone().unwrap_or_else(|| self.since.clone()),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct OtxPulseResponse {
     results: Vec<OtxPulse>,
@@ -22,11 +63,32 @@ struct OtxPulse {
     id: String,
     name: String,
     description: Option<String>,
+    modified: Option<String>,
     tags: Vec<String>,
     indicators: Vec<OtxIndicator>,
     tlp: Option<String>,
     adversary: Option<String>,
     malware_families: Vec<String>,
+    #[serde(default)]
+    references: Vec<String>,
+    #[serde(default)]
+    attack_ids: Vec<OtxAttackId>,
+}
+
+/// ATT&CK technique, either a bare id or an object depending on the endpoint
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OtxAttackId {
+    Id(String),
+    Technique { id: String },
+}
+
+impl OtxAttackId {
+    fn id(&self) -> &str {
+        match self {
+            OtxAttackId::Id(id) | OtxAttackId::Technique { id } => id,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -41,6 +103,8 @@ struct OtxIndicator {
 pub struct AlienVaultCollector {
     client: Client,
     api_key: String,
+    base_url: String,
+    max_pages: usize,
 }
 
 impl AlienVaultCollector {
@@ -51,42 +115,82 @@ impl AlienVaultCollector {
             .build()
             .expect("Failed to create HTTP client");
 
-        Self { client, api_key }
+        Self {
+            client,
+            api_key,
+            base_url: OTX_API_URL.to_string(),
+            max_pages: MAX_PAGES,
+        }
     }
 
-    /// Fetch subscribed pulses
-    async fn fetch_subscribed_pulses(&self) -> Result<Vec<OtxPulse>> {
-        let response = self.client
-            .get(format!("{}/pulses/subscribed", OTX_API_URL))
-            .header("X-OTX-API-KEY", &self.api_key)
-            .query(&[("limit", "50"), ("modified_since", "7d")])
-            .send()
-            .await
-            .context("Failed to fetch OTX pulses")?;
+    /// Fetch subscribed pulses following pagination, from the cursor's next
+    /// page if a listing is in progress. Returns the pulses and the page to
+    /// continue from when `max_pages` cut the listing short.
+    async fn fetch_subscribed_pulses(&self, cursor: &OtxCursor) -> Result<(Vec<OtxPulse>, Option<String>)> {
+        let mut pulses = vec![];
+        let mut request = match cursor.next {
+            Some(ref next) => self.client.get(next),
+            None => self
+                .client
+                .get(format!("{}/pulses/subscribed", self.base_url))
+                .query(&[("limit", PAGE_SIZE), ("modified_since", cursor.since.as_str())]),
+        };
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("OTX API error: {} - {}", status, body);
-        }
+        let mut pending = None;
+        for _ in 0..self.max_pages {
+            let response = request
+                .header("X-OTX-API-KEY", &self.api_key)
+                .send()
+                .await
+                .context("Failed to fetch OTX pulses")?;
 
-        let data: OtxPulseResponse = response
-            .json()
-            .await
-            .context("Failed to parse OTX response")?;
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                anyhow::bail!("OTX API error: {} - {}", status, body);
+            }
+
+            let data: OtxPulseResponse = response
+                .json()
+                .await
+                .context("Failed to parse OTX response")?;
+            pulses.extend(data.results);
+
+            match data.next {
+                Some(next) => {
+                    request = self.client.get(&next);
+                    pending = Some(next);
+                }
+                None => return Ok((pulses, None)),
+            }
+        }
 
-        Ok(data.results)
+        tracing::warn!(pages = self.max_pages, "OTX subscription listing truncated; resuming next run");
+        Ok((pulses, pending))
     }
 
     /// Convert OTX indicator type to our IocType
     fn convert_type(otx_type: &str) -> Option<IocType> {
         match otx_type {
             "IPv4" | "IPv6" => Some(IocType::Ip),
+            "CIDR" => Some(IocType::Cidr),
             "domain" | "hostname" => Some(IocType::Domain),
             "URL" | "URI" => Some(IocType::Url),
-            "FileHash-MD5" | "FileHash-SHA1" | "FileHash-SHA256" => Some(IocType::Hash),
+            "FileHash-MD5" | "FileHash-SHA1" | "FileHash-SHA256" | "FileHash-IMPHASH" => Some(IocType::Hash),
             "email" => Some(IocType::Email),
             "CVE" => Some(IocType::Cve),
+            "FilePath" => Some(IocType::FilePath),
+            "Mutex" => Some(IocType::Mutex),
+            "JA3" => Some(IocType::Ja3),
+            "SSLCertFingerprint" => Some(IocType::CertFingerprint),
+            _ => None,
+        }
+    }
+
+    /// Hash algorithm for OTX hash types whose shape is ambiguous
+    fn convert_hash_algorithm(otx_type: &str) -> Option<HashAlgorithm> {
+        match otx_type {
+            "FileHash-IMPHASH" => Some(HashAlgorithm::Imphash),
             _ => None,
         }
     }
@@ -101,6 +205,64 @@ impl AlienVaultCollector {
             _ => Tlp::Amber,
         }
     }
+
+    /// Indicators of a pulse with its tags and report context
+    fn convert_pulse(pulse: OtxPulse) -> Vec<CreateIndicatorRequest> {
+        let tlp = Self::convert_tlp(pulse.tlp.as_deref());
+
+        let mut base_tags: Vec<String> = pulse.tags.clone();
+        base_tags.push(format!("pulse:{}", pulse.id));
+
+        if let Some(ref adversary) = pulse.adversary {
+            base_tags.push(format!("adversary:{}", adversary));
+        }
+
+        for malware in &pulse.malware_families {
+            base_tags.push(format!("malware:{}", malware));
+        }
+
+        let attack_ids: Vec<String> = pulse.attack_ids.iter().map(|a| a.id().to_string()).collect();
+        for attack_id in &attack_ids {
+            base_tags.push(format!("attack:{}", attack_id));
+        }
+
+        let pulse_description = pulse.description.filter(|d| !d.trim().is_empty());
+
+        pulse
+            .indicators
+            .into_iter()
+            .filter_map(|indicator| {
+                let ioc_type = Self::convert_type(&indicator.indicator_type)?;
+                let mut tags = base_tags.clone();
+                tags.push(format!("otx_type:{}", indicator.indicator_type));
+
+                // The indicator's own note is more specific than the pulse summary
+                let description = indicator
+                    .description
+                    .filter(|d| !d.trim().is_empty())
+                    .or_else(|| pulse_description.clone());
+
+                Some(CreateIndicatorRequest {
+                    value: indicator.indicator,
+                    ioc_type: Some(ioc_type),
+                    hash_algorithm: Self::convert_hash_algorithm(&indicator.indicator_type),
+                    severity: Some(Severity::Medium),
+                    confidence: Some(70),
+                    tlp: Some(tlp.clone()),
+                    tags: Some(tags),
+                    source: Some("alienvault_otx".to_string()),
+                    expiration_days: Some(90),
+                    context: Some(ContextInput {
+                        report_id: format!("pulse:{}", pulse.id),
+                        report_name: Some(pulse.name.clone()),
+                        description,
+                        report_urls: pulse.references.clone(),
+                        attack_ids: attack_ids.clone(),
+                    }),
+                })
+            })
+            .collect()
+    }
 }
 
 #[async_trait]
@@ -110,46 +272,196 @@ impl FeedCollector for AlienVaultCollector {
     }
 
     async fn fetch(&self) -> Result<Vec<CreateIndicatorRequest>> {
-        let pulses = self.fetch_subscribed_pulses().await?;
-        let mut indicators = vec![];
-
-        for pulse in pulses {
-            let tlp = Self::convert_tlp(pulse.tlp.as_deref());
-            
-            let mut base_tags: Vec<String> = pulse.tags.clone();
-            base_tags.push(format!("pulse:{}", pulse.id));
-            
-            if let Some(ref adversary) = pulse.adversary {
-                base_tags.push(format!("adversary:{}", adversary));
-            }
-            
-            for malware in &pulse.malware_families {
-                base_tags.push(format!("malware:{}", malware));
-            }
+        Ok(self.fetch_since(None).await?.indicators)
+    }
 
-            for indicator in pulse.indicators {
-                if let Some(ioc_type) = Self::convert_type(&indicator.indicator_type) {
-                    let mut tags = base_tags.clone();
-                    tags.push(format!("otx_type:{}", indicator.indicator_type));
-
-                    indicators.push(CreateIndicatorRequest {
-                        value: indicator.indicator,
-                        ioc_type: Some(ioc_type),
-                        severity: Some(Severity::Medium),
-                        confidence: Some(70),
-                        tlp: Some(tlp.clone()),
-                        tags: Some(tags),
-                        source: Some("alienvault_otx".to_string()),
-                        expiration_days: Some(90),
-                    });
-                }
-            }
-        }
+    async fn fetch_since(&self, cursor: Option<&str>) -> Result<FeedBatch> {
+        let cursor = match cursor {
+            Some(cursor) => OtxCursor::parse(cursor),
+            None => OtxCursor {
+                since: (Utc::now() - chrono::Duration::days(INITIAL_SYNC_DAYS))
+                    .format("%Y-%m-%dT%H:%M:%S")
+                    .to_string(),
+                ..Default::default()
+            },
+        };
+        let (pulses, next) = self.fetch_subscribed_pulses(&cursor).await?;
+
+        // OTX timestamps share one ISO format, so the newest sorts last. The
+        // listing's `modified_since` stays until its last page is stored, as
+        // the pages are not ordered by `modified`.
+        let newest = pulses
+            .iter()
+            .filter_map(|p| p.modified.clone())
+            .chain(cursor.newest.clone())
+            .max();
+        let next_cursor = OtxCursor {
+            since: cursor.since,
+            newest,
+            next,
+        };
+
+        let indicators = pulses.into_iter().flat_map(Self::convert_pulse).collect();
 
-        Ok(indicators)
+        Ok(FeedBatch {
+            indicators,
+            sightings: vec![],
+            relations: vec![],
+            cursor: Some(next_cursor.to_cursor_string()),
+        })
     }
 
     fn is_configured(&self) -> bool {
         !self.api_key.is_empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn collector(server: &MockServer) -> AlienVaultCollector {
+        let mut otx = AlienVaultCollector::new("test-key".to_string());
+        otx.base_url = server.uri();
+        otx
+    }
+
+    fn pulse(id: &str, modified: &str, indicator: &str) -> Value {
+        json!({
+            "id": id,
+            "name": format!("Pulse {}", id),
+            "description": "",
+            "modified": modified,
+            "tags": ["phishing"],
+            "tlp": "green",
+            "adversary": null,
+            "malware_families": [],
+            "indicators": [{ "indicator": indicator, "type": "domain", "description": null }]
+        })
+    }
+
+    /// First page of a listing since `since`, pointing at page 2
+    async fn mount_first_page(server: &MockServer, since: &str) {
+        Mock::given(method("GET"))
+            .and(path("/pulses/subscribed"))
+            .and(query_param("modified_since", since))
+            .and(header("X-OTX-API-KEY", "test-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "results": [pulse("a", "2024-05-03T08:00:00.000000", "one.example.test")],
+                "next": format!("{}/pulses/subscribed?page=2", server.uri()),
+            })))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    async fn mount_second_page(server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("/pulses/subscribed"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "results": [pulse("b", "2024-05-02T09:30:00.000000", "two.example.test")],
+                "next": null,
+            })))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    fn values(batch: &FeedBatch) -> Vec<&str> {
+        batch.indicators.iter().map(|i| i.value.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn follows_pages_and_saves_newest_modified() {
+        let server = MockServer::start().await;
+        mount_first_page(&server, "2024-05-01T00:00:00").await;
+        mount_second_page(&server).await;
+
+        let batch = collector(&server).fetch_since(Some("2024-05-01T00:00:00")).await.unwrap();
+
+        assert_eq!(values(&batch), vec!["one.example.test", "two.example.test"]);
+        // Pages are not ordered by `modified`; the newest of all of them wins
+        assert_eq!(batch.cursor.as_deref(), Some("2024-05-03T08:00:00.000000"));
+    }
+
+    #[tokio::test]
+    async fn truncated_listing_resumes_from_the_next_page() {
+        let server = MockServer::start().await;
+        mount_first_page(&server, "2024-05-01T00:00:00").await;
+        mount_second_page(&server).await;
+
+        let mut otx = collector(&server);
+        otx.max_pages = 1;
+
+        let first = otx.fetch_since(Some("2024-05-01T00:00:00")).await.unwrap();
+        assert_eq!(values(&first), vec!["one.example.test"]);
+        let saved = first.cursor.unwrap();
+        assert_eq!(
+            OtxCursor::parse(&saved),
+            OtxCursor {
+                since: "2024-05-01T00:00:00".to_string(),
+                newest: Some("2024-05-03T08:00:00.000000".to_string()),
+                next: Some(format!("{}/pulses/subscribed?page=2", server.uri())),
+            }
+        );
+
+        // The next run continues at page 2 instead of starting over
+        let second = otx.fetch_since(Some(&saved)).await.unwrap();
+        assert_eq!(values(&second), vec!["two.example.test"]);
+        assert_eq!(second.cursor.as_deref(), Some("2024-05-03T08:00:00.000000"));
+    }
+
+    #[tokio::test]
+    async fn empty_listing_keeps_the_cursor() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/pulses/subscribed"))
+            .and(query_param("modified_since", "2024-05-03T08:00:00.000000"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "results": [], "next": null })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let batch = collector(&server).fetch_since(Some("2024-05-03T08:00:00.000000")).await.unwrap();
+
+        assert!(batch.indicators.is_empty());
+        assert_eq!(batch.cursor.as_deref(), Some("2024-05-03T08:00:00.000000"));
+    }
+
+    #[test]
+    fn pulse_context_and_attack_tags() {
+        let pulse: OtxPulse = serde_json::from_value(json!({
+            "id": "p1",
+            "name": "Campaign",
+            "description": "Pulse summary",
+            "modified": null,
+            "tags": [],
+            "tlp": "white",
+            "adversary": "APT-X",
+            "malware_families": ["Emotet"],
+            "references": ["https://report.example.test"],
+            "attack_ids": ["T1566", { "id": "T1059", "name": "Command and Scripting Interpreter" }],
+            "indicators": [
+                { "indicator": "198.51.100.7", "type": "IPv4", "description": "C2 server" },
+                { "indicator": "ignored", "type": "YARA", "description": null }
+            ]
+        }))
+        .unwrap();
+
+        let indicators = AlienVaultCollector::convert_pulse(pulse);
+
+        assert_eq!(indicators.len(), 1);
+        let tags = indicators[0].tags.as_ref().unwrap();
+        for tag in ["pulse:p1", "adversary:APT-X", "malware:Emotet", "attack:T1566", "attack:T1059", "otx_type:IPv4"] {
+            assert!(tags.contains(&tag.to_string()), "missing {}", tag);
+        }
+        let context = indicators[0].context.as_ref().unwrap();
+        assert_eq!(context.report_id, "pulse:p1");
+        assert_eq!(context.description.as_deref(), Some("C2 server"));
+        assert_eq!(context.attack_ids, vec!["T1566", "T1059"]);
+    }
+}
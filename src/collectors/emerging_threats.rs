@@ -9,8 +9,6 @@ use crate::collectors::FeedCollector;
 use crate::models::{CreateIndicatorRequest, IocType, Severity, Tlp};
 
 const ET_COMPROMISED_IPS: &str = "https://rules.emergingthreats.net/blockrules/compromised-ips.txt";
-const FEODO_TRACKER_IPS: &str = "https://feodotracker.abuse.ch/downloads/ipblocklist.txt";
-const URLHAUS_URLS: &str = "https://urlhaus.abuse.ch/downloads/text_online/";
 
 pub struct EmergingThreatsCollector {
     client: Client,
@@ -44,12 +42,14 @@ impl EmergingThreatsCollector {
                 indicators.push(CreateIndicatorRequest {
                     value: ip.to_string(),
                     ioc_type: Some(IocType::Ip),
+                    hash_algorithm: None,
                     severity: Some(Severity::High),
                     confidence: Some(80),
                     tlp: Some(Tlp::White),
                     tags: Some(tags.clone()),
                     source: Some(source.to_string()),
                     expiration_days: Some(30),
+                    context: None,
                 });
             }
         }
@@ -70,26 +70,11 @@ impl FeedCollector for EmergingThreatsCollector {
     }
 
     async fn fetch(&self) -> Result<Vec<CreateIndicatorRequest>> {
-        let mut all_indicators = vec![];
-
         // Fetch compromised IPs
-        if let Ok(indicators) = self.fetch_ip_list(
+        self.fetch_ip_list(
             ET_COMPROMISED_IPS,
             "emerging_threats",
             vec!["compromised".to_string(), "et_rules".to_string()],
-        ).await {
-            all_indicators.extend(indicators);
-        }
-
-        // Fetch Feodo Tracker (banking trojans)
-        if let Ok(indicators) = self.fetch_ip_list(
-            FEODO_TRACKER_IPS,
-            "feodo_tracker",
-            vec!["botnet".to_string(), "banking_trojan".to_string()],
-        ).await {
-            all_indicators.extend(indicators);
-        }
-
-        Ok(all_indicators)
+        ).await
     }
 }
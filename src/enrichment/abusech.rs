@@ -0,0 +1,443 @@
+//! abuse.ch enrichment providers (URLhaus for URLs, MalwareBazaar for hashes)
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use governor::Quota;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::num::NonZeroU32;
+
+use crate::enrichment::{EnrichmentProvider, ProviderHttpError};
+use crate::models::{Indicator, IocType};
+
+const URLHAUS_API_URL: &str = "https://urlhaus-api.abuse.ch/v1";
+const MALWAREBAZAAR_API_URL: &str = "https://mb-api.abuse.ch/api/v1";
+
+fn client() -> Client {
+    Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .expect("Failed to create HTTP client")
+}
+
+/// abuse.ch publishes no hard limit and asks for fair use of its APIs
+fn fair_use_quota() -> Quota {
+    Quota::per_minute(NonZeroU32::new(60).unwrap())
+}
+
+/// POST an abuse.ch API form, returning the raw JSON body
+async fn query(client: &Client, provider: &'static str, url: &str, auth_key: &str, form: &[(&str, &str)]) -> Result<Value> {
+    let response = client
+        .post(url)
+        .header("Auth-Key", auth_key)
+        .form(form)
+        .send()
+        .await
+        .with_context(|| format!("Failed to send request to {}", provider))?;
+
+    if !response.status().is_success() {
+        return Err(ProviderHttpError::from_response(provider, response).await.into());
+    }
+
+    response
+        .json()
+        .await
+        .with_context(|| format!("Failed to parse {} response", provider))
+}
+
+// ==================== URLhaus ====================
+
+#[derive(Debug, Deserialize)]
+struct UrlhausUrl {
+    query_status: String,
+    url_status: Option<String>,
+    threat: Option<String>,
+    date_added: Option<String>,
+    last_online: Option<String>,
+    urlhaus_reference: Option<String>,
+    host: Option<String>,
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+    #[serde(default)]
+    blacklists: Option<Value>,
+    #[serde(default)]
+    payloads: Option<Vec<UrlhausPayload>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UrlhausPayload {
+    firstseen: Option<String>,
+    filename: Option<String>,
+    file_type: Option<String>,
+    response_sha256: Option<String>,
+    signature: Option<String>,
+}
+
+/// URLhaus URL lookup
+pub struct UrlhausProvider {
+    client: Client,
+    auth_key: String,
+    base_url: String,
+}
+
+impl UrlhausProvider {
+    /// Create a new URLhaus provider
+    pub fn new(auth_key: String) -> Self {
+        Self {
+            client: client(),
+            auth_key,
+            base_url: URLHAUS_API_URL.to_string(),
+        }
+    }
+
+    /// Look up a URL; `None` when URLhaus does not know it
+    pub async fn lookup_url(&self, url: &str) -> Result<Option<Value>> {
+        let body = query(&self.client, "urlhaus", &format!("{}/url/", self.base_url), &self.auth_key, &[("url", url)]).await?;
+        let data: UrlhausUrl = serde_json::from_value(body).context("Failed to parse URLhaus response")?;
+
+        match data.query_status.as_str() {
+            "ok" => {}
+            "no_results" => return Ok(None),
+            status => anyhow::bail!("URLhaus query failed: {}", status),
+        }
+
+        let payloads: Vec<Value> = data
+            .payloads
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| json!({
+                "first_seen": p.firstseen,
+                "filename": p.filename,
+                "file_type": p.file_type,
+                "sha256": p.response_sha256,
+                "signature": p.signature,
+            }))
+            .collect();
+
+        Ok(Some(json!({
+            "url_status": data.url_status,
+            "threat": data.threat,
+            "host": data.host,
+            "date_added": data.date_added,
+            "last_online": data.last_online,
+            "tags": data.tags.unwrap_or_default(),
+            "blacklists": data.blacklists,
+            "payloads": payloads,
+            "reference": data.urlhaus_reference,
+        })))
+    }
+}
+
+#[async_trait]
+impl EnrichmentProvider for UrlhausProvider {
+    fn name(&self) -> &'static str {
+        "urlhaus"
+    }
+
+    fn enrichment_type(&self) -> &'static str {
+        "reputation"
+    }
+
+    fn supports(&self, ioc_type: &IocType) -> bool {
+        matches!(ioc_type, IocType::Url)
+    }
+
+    async fn enrich(&self, indicator: &Indicator) -> Result<Option<Value>> {
+        self.lookup_url(&indicator.value).await
+    }
+
+    fn ttl_hours(&self) -> i64 {
+        12 // URL status flips between online and offline quickly
+    }
+
+    fn rate_limit(&self) -> Option<Quota> {
+        Some(fair_use_quota())
+    }
+}
+
+// ==================== MalwareBazaar ====================
+
+#[derive(Debug, Deserialize)]
+struct BazaarInfoResponse {
+    query_status: String,
+    #[serde(default)]
+    data: Vec<BazaarInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BazaarInfo {
+    sha256_hash: Option<String>,
+    sha1_hash: Option<String>,
+    md5_hash: Option<String>,
+    sha3_384_hash: Option<String>,
+    first_seen: Option<String>,
+    last_seen: Option<String>,
+    file_name: Option<String>,
+    file_size: Option<i64>,
+    file_type: Option<String>,
+    file_type_mime: Option<String>,
+    signature: Option<String>,
+    imphash: Option<String>,
+    tlsh: Option<String>,
+    ssdeep: Option<String>,
+    delivery_method: Option<String>,
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+}
+
+/// MalwareBazaar sample lookup
+pub struct MalwareBazaarProvider {
+    client: Client,
+    auth_key: String,
+    base_url: String,
+}
+
+impl MalwareBazaarProvider {
+    /// Create a new MalwareBazaar provider
+    pub fn new(auth_key: String) -> Self {
+        Self {
+            client: client(),
+            auth_key,
+            base_url: MALWAREBAZAAR_API_URL.to_string(),
+        }
+    }
+
+    /// Look up an MD5, SHA1 or SHA256 hash; `None` when the sample is unknown
+    pub async fn lookup_hash(&self, hash: &str) -> Result<Option<Value>> {
+        let body = query(
+            &self.client,
+            "malwarebazaar",
+            &format!("{}/", self.base_url),
+            &self.auth_key,
+            &[("query", "get_info"), ("hash", hash)],
+        )
+        .await?;
+        let response: BazaarInfoResponse =
+            serde_json::from_value(body).context("Failed to parse MalwareBazaar response")?;
+
+        match response.query_status.as_str() {
+            "ok" => {}
+            "hash_not_found" | "no_results" => return Ok(None),
+            // Imphash, TLSH and other hashes are not accepted by get_info
+            "illegal_hash" => return Ok(None),
+            status => anyhow::bail!("MalwareBazaar query failed: {}", status),
+        }
+
+        let Some(sample) = response.data.into_iter().next() else {
+            return Ok(None);
+        };
+
+        Ok(Some(json!({
+            "signature": sample.signature,
+            "tags": sample.tags.unwrap_or_default(),
+            "first_seen": sample.first_seen,
+            "last_seen": sample.last_seen,
+            "file_name": sample.file_name,
+            "file_type_mime": sample.file_type_mime,
+            "delivery_method": sample.delivery_method,
+            "sha3_384": sample.sha3_384_hash,
+            "reference": sample.sha256_hash.as_ref().map(|h| format!("https://bazaar.abuse.ch/sample/{}/", h)),
+            // Every hash of the sample, so MD5/SHA1/SHA256 indicators can be linked
+            "file": {
+                "md5": sample.md5_hash,
+                "sha1": sample.sha1_hash,
+                "sha256": sample.sha256_hash,
+                "ssdeep": sample.ssdeep,
+                "tlsh": sample.tlsh,
+                "imphash": sample.imphash,
+                "size": sample.file_size,
+                "file_type": sample.file_type,
+                "names": sample.file_name.into_iter().collect::<Vec<_>>(),
+            },
+        })))
+    }
+}
+
+#[async_trait]
+impl EnrichmentProvider for MalwareBazaarProvider {
+    fn name(&self) -> &'static str {
+        "malwarebazaar"
+    }
+
+    fn enrichment_type(&self) -> &'static str {
+        "malware"
+    }
+
+    fn supports(&self, ioc_type: &IocType) -> bool {
+        matches!(ioc_type, IocType::Hash)
+    }
+
+    async fn enrich(&self, indicator: &Indicator) -> Result<Option<Value>> {
+        self.lookup_hash(&indicator.value).await
+    }
+
+    fn ttl_hours(&self) -> i64 {
+        168 // Sample metadata rarely changes
+    }
+
+    fn rate_limit(&self) -> Option<Quota> {
+        Some(fair_use_quota())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn urlhaus(server: &MockServer) -> UrlhausProvider {
+        let mut provider = UrlhausProvider::new("test-key".to_string());
+        provider.base_url = server.uri();
+        provider
+    }
+
+    fn bazaar(server: &MockServer) -> MalwareBazaarProvider {
+        let mut provider = MalwareBazaarProvider::new("test-key".to_string());
+        provider.base_url = server.uri();
+        provider
+    }
+
+    #[tokio::test]
+    async fn urlhaus_maps_url_and_payloads() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/url/"))
+            .and(header("Auth-Key", "test-key"))
+            .and(body_string_contains("url=http%3A%2F%2F198.51.100.7%3A44321%2FMozi.m"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "query_status": "ok",
+                "id": "3161234",
+                "urlhaus_reference": "https://urlhaus.abuse.ch/url/3161234/",
+                "url": "http://198.51.100.7:44321/Mozi.m",
+                "url_status": "online",
+                "host": "198.51.100.7",
+                "date_added": "2024-06-01 10:12:03 UTC",
+                "threat": "malware_download",
+                "blacklists": { "spamhaus_dbl": "not listed", "surbl": "not listed" },
+                "reporter": "lrz_urlhaus",
+                "larted": "true",
+                "takedown_time_seconds": null,
+                "tags": ["elf", "Mozi"],
+                "payloads": [{
+                    "firstseen": "2024-06-01",
+                    "filename": "Mozi.m",
+                    "file_type": "elf",
+                    "response_size": "307960",
+                    "response_md5": "0bd4e5b7e5b3d9a2e6a0b2d6c2f3f1a4",
+                    "response_sha256": "12b2a8f0a3b1e5a4c9d8e7f6a5b4c3d2e1f0a9b8c7d6e5f4a3b2c1d0e9f8a7b6",
+                    "signature": "Mozi",
+                    "virustotal": null,
+                    "imphash": null,
+                    "ssdeep": null,
+                    "tlsh": null
+                }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/url/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "query_status": "no_results" })))
+            .mount(&server)
+            .await;
+
+        let provider = urlhaus(&server);
+        let data = provider.lookup_url("http://198.51.100.7:44321/Mozi.m").await.unwrap().unwrap();
+        assert_eq!(data["url_status"], "online");
+        assert_eq!(data["tags"], json!(["elf", "Mozi"]));
+        assert_eq!(data["payloads"][0]["signature"], "Mozi");
+        assert_eq!(data["reference"], "https://urlhaus.abuse.ch/url/3161234/");
+
+        assert!(provider.lookup_url("http://unknown.example.test/").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn urlhaus_reports_failed_queries() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/url/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "query_status": "invalid_url" })))
+            .mount(&server)
+            .await;
+
+        assert!(urlhaus(&server).lookup_url("not a url").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn bazaar_maps_sample_hashes() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/"))
+            .and(header("Auth-Key", "test-key"))
+            .and(body_string_contains("query=get_info"))
+            .and(body_string_contains("hash=094fd325049b8a9cf6d3e5ef2a6d4cc6a567d7d49c35f8bb8dd9e3c6acf3d78d"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "query_status": "ok",
+                "data": [{
+                    "sha256_hash": "094fd325049b8a9cf6d3e5ef2a6d4cc6a567d7d49c35f8bb8dd9e3c6acf3d78d",
+                    "sha3_384_hash": "e5b8e0c8a4b5f0a3d2c1b0a9f8e7d6c5b4a3f2e1d0c9b8a7f6e5d4c3b2a1f0e9d8c7b6a5f4e3d2c1b0a9f8e7d6c5",
+                    "sha1_hash": "5ba5c8a6e3f0a1d2b3c4e5f6a7b8c9d0e1f2a3b4",
+                    "md5_hash": "8c5b9b4e1a3d2f0e6c7a8b9d0e1f2a3b",
+                    "first_seen": "2024-06-01 09:58:12",
+                    "last_seen": null,
+                    "file_name": "invoice.exe",
+                    "file_size": 245760,
+                    "file_type_mime": "application/x-dosexec",
+                    "file_type": "exe",
+                    "reporter": "abuse_ch",
+                    "signature": "AgentTesla",
+                    "imphash": "f34d5f2d4577ed6d9ceec516c1f5a744",
+                    "tlsh": "T1A5B4C3D2E1F0A9B8C7D6E5F4A3B2C1D0E9F8A7B6C5D4E3F2A1B0C9D8E7F6A5B4C3D2E1",
+                    "ssdeep": "6144:abcdefghijklmnopqrstuvwx:abcdefgh",
+                    "tags": ["AgentTesla", "exe"],
+                    "delivery_method": "email_attachment",
+                    "intelligence": { "downloads": "12", "uploads": "1" }
+                }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/"))
+            .and(body_string_contains("hash=f34d5f2d4577ed6d9ceec516c1f5a744"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "query_status": "illegal_hash" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "query_status": "hash_not_found" })))
+            .mount(&server)
+            .await;
+
+        let provider = bazaar(&server);
+        let data = provider
+            .lookup_hash("094fd325049b8a9cf6d3e5ef2a6d4cc6a567d7d49c35f8bb8dd9e3c6acf3d78d")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(data["signature"], "AgentTesla");
+        assert_eq!(data["file"]["md5"], "8c5b9b4e1a3d2f0e6c7a8b9d0e1f2a3b");
+        assert_eq!(data["file"]["names"], json!(["invoice.exe"]));
+        assert_eq!(
+            data["reference"],
+            "https://bazaar.abuse.ch/sample/094fd325049b8a9cf6d3e5ef2a6d4cc6a567d7d49c35f8bb8dd9e3c6acf3d78d/"
+        );
+
+        assert!(provider.lookup_hash("f34d5f2d4577ed6d9ceec516c1f5a744").await.unwrap().is_none());
+        assert!(provider.lookup_hash("8c5b9b4e1a3d2f0e6c7a8b9d0e1f2a3c").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn http_errors_surface() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("Unauthorized"))
+            .mount(&server)
+            .await;
+
+        assert!(bazaar(&server).lookup_hash("8c5b9b4e1a3d2f0e6c7a8b9d0e1f2a3b").await.is_err());
+        assert!(urlhaus(&server).lookup_url("http://evil.example.test/").await.is_err());
+    }
+}
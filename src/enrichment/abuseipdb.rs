@@ -2,11 +2,13 @@
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
+use governor::Quota;
 use reqwest::Client;
+use std::num::NonZeroU32;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
-use crate::enrichment::EnrichmentProvider;
+use crate::enrichment::{EnrichmentProvider, ProviderHttpError};
 use crate::models::{Indicator, IocType};
 
 const ABUSEIPDB_API_URL: &str = "https://api.abuseipdb.com/api/v2";
@@ -68,9 +70,7 @@ impl AbuseIpDbProvider {
             .context("Failed to send request to AbuseIPDB")?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("AbuseIPDB API error: {} - {}", status, body);
+            return Err(ProviderHttpError::from_response("AbuseIPDB", response).await.into());
         }
 
         let data: AbuseIpDbResponse = response
@@ -156,6 +156,12 @@ impl EnrichmentProvider for AbuseIpDbProvider {
     fn ttl_hours(&self) -> i64 {
         12 // Check reputation more frequently
     }
+
+    fn rate_limit(&self) -> Option<Quota> {
+        // Free tier: 1,000 checks per day, allowing short bursts
+        Quota::with_period(std::time::Duration::from_millis(86_400))
+            .map(|quota| quota.allow_burst(NonZeroU32::new(50).unwrap()))
+    }
 }
 
 /// AbuseIPDB attack categories
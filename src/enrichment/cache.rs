@@ -0,0 +1,82 @@
+//! Shared enrichment cache backed by Redis
+//!
+//! Results are keyed by provider and indicator value, so a value that is
+//! deleted and re-created (or enriched by several instances) does not cost
+//! another provider call while the previous result is still fresh. The cache is
+//! best-effort: Redis errors are logged and treated as a miss.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use redis::aio::MultiplexedConnection;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::IocType;
+
+const KEY_PREFIX: &str = "sentinelforge:enrichment";
+
+/// A provider result held in the cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedEnrichment {
+    pub data: Value,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Redis-backed enrichment cache
+#[derive(Clone)]
+pub struct EnrichmentCache {
+    conn: MultiplexedConnection,
+}
+
+impl EnrichmentCache {
+    /// Connect to Redis
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = redis::Client::open(url).context("Invalid Redis URL")?;
+        let conn = client
+            .get_multiplexed_tokio_connection()
+            .await
+            .context("Failed to connect to Redis")?;
+
+        Ok(Self { conn })
+    }
+
+    fn key(provider: &str, ioc_type: &IocType, value: &str) -> String {
+        format!("{}:{}:{}:{}", KEY_PREFIX, provider, ioc_type, value)
+    }
+
+    /// Fetch a fresh cached result
+    pub async fn get(&self, provider: &str, ioc_type: &IocType, value: &str) -> Option<CachedEnrichment> {
+        let mut conn = self.conn.clone();
+        let raw: Option<String> = match conn.get(Self::key(provider, ioc_type, value)).await {
+            Ok(raw) => raw,
+            Err(e) => {
+                tracing::warn!(provider, error = %e, "Enrichment cache read failed");
+                return None;
+            }
+        };
+
+        raw.and_then(|raw| serde_json::from_str::<CachedEnrichment>(&raw).ok())
+            .filter(|cached| cached.expires_at > Utc::now())
+    }
+
+    /// Store a result until it expires
+    pub async fn put(&self, provider: &str, ioc_type: &IocType, value: &str, cached: &CachedEnrichment) {
+        let seconds = (cached.expires_at - Utc::now()).num_seconds();
+        if seconds <= 0 {
+            return;
+        }
+
+        let Ok(raw) = serde_json::to_string(cached) else {
+            return;
+        };
+
+        let mut conn = self.conn.clone();
+        let result: redis::RedisResult<()> = conn
+            .set_ex(Self::key(provider, ioc_type, value), raw, seconds as u64)
+            .await;
+        if let Err(e) = result {
+            tracing::warn!(provider, error = %e, "Enrichment cache write failed");
+        }
+    }
+}
@@ -1,33 +1,122 @@
 //! DNS enrichment provider
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use async_trait::async_trait;
-use serde_json::{json, Value};
-use trust_dns_resolver::config::{ResolverConfig, ResolverOpts};
-use trust_dns_resolver::TokioAsyncResolver;
+use futures::future::join_all;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use trust_dns_resolver::config::{NameServerConfig, Protocol, ResolverConfig, ResolverOpts};
+use trust_dns_resolver::proto::op::{Edns, Message, MessageType, OpCode, Query, ResponseCode};
+use trust_dns_resolver::proto::rr::rdata::caa;
+use trust_dns_resolver::proto::rr::{RData, RecordType};
+use trust_dns_resolver::{Name, TokioAsyncResolver};
 
 use crate::enrichment::EnrichmentProvider;
-use crate::models::{DnsData, Indicator, IocType};
+use crate::models::{
+    CaaRecord, DmarcPolicy, DnsData, DnsObservation, DnssecStatus, DomainVerification, Indicator,
+    IocType, PdnsRecordType, SoaRecord, SpfPolicy, SrvRecord,
+};
+
+/// CNAME hops followed before giving up on a chain
+const MAX_CNAME_CHAIN: usize = 8;
+
+/// Services probed for SRV records under each domain
+const SRV_SERVICES: &[&str] = &[
+    "_sip._tcp",
+    "_sip._tls",
+    "_sipfederationtls._tcp",
+    "_xmpp-client._tcp",
+    "_xmpp-server._tcp",
+    "_ldap._tcp",
+    "_kerberos._tcp",
+    "_autodiscover._tcp",
+    "_submission._tcp",
+    "_imaps._tcp",
+    "_caldavs._tcp",
+];
+
+/// TXT prefixes that publish a domain ownership token for a service
+const VERIFICATION_PREFIXES: &[(&str, &str)] = &[
+    ("google-site-verification=", "google"),
+    ("ms=", "microsoft"),
+    ("facebook-domain-verification=", "facebook"),
+    ("apple-domain-verification=", "apple"),
+    ("atlassian-domain-verification=", "atlassian"),
+    ("adobe-idp-site-verification=", "adobe"),
+    ("docusign=", "docusign"),
+    ("globalsign-domain-verification=", "globalsign"),
+    ("stripe-verification=", "stripe"),
+    ("zoom_verify_", "zoom"),
+    ("amazonses:", "amazonses"),
+    ("yandex-verification:", "yandex"),
+];
+
+/// Timeout for the DNSSEC status query
+const DNSSEC_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Team Cymru zone answering `AS<n>` TXT queries with the AS registration
+const CYMRU_ASN_ZONE: &str = "asn.cymru.com";
 
 /// DNS enrichment provider
 pub struct DnsProvider {
     resolver: TokioAsyncResolver,
+    /// Resolver asked for the DNSSEC authenticated-data flag
+    upstream: Option<SocketAddr>,
 }
 
 impl DnsProvider {
-    /// Create a new DNS provider
+    /// Create a new DNS provider using the public default resolvers
     pub async fn new() -> Result<Self> {
-        let resolver = TokioAsyncResolver::tokio(
-            ResolverConfig::default(),
+        Ok(Self::from_config(ResolverConfig::default(), ResolverOpts::default()))
+    }
+
+    /// Resolve through the system configuration (`/etc/resolv.conf`)
+    pub fn from_system_conf() -> Result<Self> {
+        let (config, opts) = trust_dns_resolver::system_conf::read_system_conf()
+            .context("Failed to read system resolver configuration")?;
+        Ok(Self::from_config(config, opts))
+    }
+
+    /// Resolve through specific upstream servers, e.g. an internal resolver or a local stub
+    pub fn with_upstreams(upstreams: &[SocketAddr]) -> Self {
+        let name_servers: Vec<NameServerConfig> = upstreams
+            .iter()
+            .flat_map(|addr| {
+                [
+                    NameServerConfig::new(*addr, Protocol::Udp),
+                    NameServerConfig::new(*addr, Protocol::Tcp),
+                ]
+            })
+            .collect();
+
+        Self::from_config(
+            ResolverConfig::from_parts(None, vec![], name_servers),
             ResolverOpts::default(),
-        );
+        )
+    }
+
+    fn from_config(config: ResolverConfig, opts: ResolverOpts) -> Self {
+        let upstream = config
+            .name_servers()
+            .iter()
+            .find(|ns| ns.protocol == Protocol::Udp)
+            .map(|ns| ns.socket_addr);
 
-        Ok(Self { resolver })
+        Self {
+            resolver: TokioAsyncResolver::tokio(config, opts),
+            upstream,
+        }
     }
 
     /// Perform DNS lookups for a domain
     pub async fn lookup(&self, domain: &str) -> Result<DnsData> {
-        let mut data = DnsData::default();
+        let mut data = DnsData {
+            cname_records: self.follow_cnames(domain).await,
+            ..Default::default()
+        };
 
         // A records
         if let Ok(response) = self.resolver.lookup_ip(domain).await {
@@ -42,27 +131,74 @@ impl DnsProvider {
         // MX records
         if let Ok(response) = self.resolver.mx_lookup(domain).await {
             for record in response.iter() {
-                data.mx_records.push(record.exchange().to_string());
+                data.mx_records.push(name_str(record.exchange()));
             }
         }
 
-        // TXT records
-        if let Ok(response) = self.resolver.txt_lookup(domain).await {
+        // TXT records, with SPF and verification tokens picked out
+        data.txt_records = self.txt(domain).await;
+        data.spf = data.txt_records.iter().find_map(|txt| parse_spf(txt));
+        data.verifications = data.txt_records.iter().filter_map(|txt| parse_verification(txt)).collect();
+        data.dmarc = self
+            .txt(&format!("_dmarc.{}", domain))
+            .await
+            .iter()
+            .find_map(|txt| parse_dmarc(txt));
+
+        // NS records
+        if let Ok(response) = self.resolver.ns_lookup(domain).await {
             for record in response.iter() {
-                let txt: String = record.iter()
-                    .map(|d| String::from_utf8_lossy(d).to_string())
-                    .collect();
-                data.txt_records.push(txt);
+                data.ns_records.push(name_str(record));
             }
         }
 
-        // NS records
-        if let Ok(response) = self.resolver.ns_lookup(domain).await {
+        // SOA record
+        if let Ok(response) = self.resolver.soa_lookup(domain).await {
+            data.soa = response.iter().next().map(|soa| SoaRecord {
+                mname: name_str(soa.mname()),
+                rname: name_str(soa.rname()),
+                serial: soa.serial(),
+                refresh: soa.refresh(),
+                retry: soa.retry(),
+                expire: soa.expire(),
+                minimum: soa.minimum(),
+            });
+        }
+
+        // CAA records
+        if let Ok(response) = self.resolver.lookup(domain, RecordType::CAA).await {
             for record in response.iter() {
-                data.ns_records.push(record.to_string());
+                if let RData::CAA(caa) = record {
+                    data.caa_records.push(CaaRecord {
+                        critical: caa.issuer_critical(),
+                        tag: caa.tag().as_str().to_string(),
+                        value: caa_value(caa.value()),
+                    });
+                }
             }
         }
 
+        // SRV records for well-known services
+        let srv_lookups = SRV_SERVICES.iter().map(|service| async move {
+            let name = format!("{}.{}", service, domain);
+            match self.resolver.srv_lookup(name.as_str()).await {
+                Ok(response) => response
+                    .iter()
+                    .map(|srv| SrvRecord {
+                        service: service.to_string(),
+                        priority: srv.priority(),
+                        weight: srv.weight(),
+                        port: srv.port(),
+                        target: name_str(srv.target()),
+                    })
+                    .collect(),
+                Err(_) => vec![],
+            }
+        });
+        data.srv_records = join_all(srv_lookups).await.into_iter().flatten().collect();
+
+        data.dnssec = self.dnssec_status(domain).await;
+
         Ok(data)
     }
 
@@ -73,12 +209,297 @@ impl DnsProvider {
 
         if let Ok(response) = self.resolver.reverse_lookup(ip_addr).await {
             for name in response.iter() {
-                results.push(name.to_string());
+                results.push(name_str(name));
             }
         }
 
         Ok(results)
     }
+
+    /// Registration of an AS number from Team Cymru's IP to ASN service
+    pub async fn asn_lookup(&self, asn: &str) -> Option<Value> {
+        let number = asn.trim_start_matches("AS");
+        let records = self.txt(&format!("as{}.{}", number, CYMRU_ASN_ZONE)).await;
+        records.iter().find_map(|record| parse_cymru_asn(record))
+    }
+
+    async fn txt(&self, name: &str) -> Vec<String> {
+        match self.resolver.txt_lookup(name).await {
+            Ok(response) => response
+                .iter()
+                .map(|record| record.iter().map(|d| String::from_utf8_lossy(d).to_string()).collect())
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// CNAME targets from `domain` to the canonical name, stopping at loops
+    async fn follow_cnames(&self, domain: &str) -> Vec<String> {
+        let mut chain: Vec<String> = vec![];
+        let mut current = domain.to_string();
+
+        while chain.len() < MAX_CNAME_CHAIN {
+            let Ok(response) = self.resolver.lookup(current.as_str(), RecordType::CNAME).await else {
+                break;
+            };
+            let Some(target) = response.iter().find_map(|record| match record {
+                RData::CNAME(cname) => Some(name_str(cname)),
+                _ => None,
+            }) else {
+                break;
+            };
+            if target == domain || chain.contains(&target) {
+                break;
+            }
+            chain.push(target.clone());
+            current = target;
+        }
+
+        chain
+    }
+
+    /// Whether the upstream resolver validated the domain's answer
+    async fn dnssec_status(&self, domain: &str) -> DnssecStatus {
+        let Some(upstream) = self.upstream else {
+            return DnssecStatus::Indeterminate;
+        };
+
+        match tokio::time::timeout(DNSSEC_TIMEOUT, query_authenticated(upstream, domain)).await {
+            Ok(Ok(true)) => DnssecStatus::Secure,
+            Ok(Ok(false)) => DnssecStatus::Insecure,
+            _ => DnssecStatus::Indeterminate,
+        }
+    }
+}
+
+/// Parse a comma-separated list of `ip`, `ip:port` or `[ipv6]:port` resolvers
+pub fn parse_upstreams(spec: &str) -> Result<Vec<SocketAddr>> {
+    let upstreams = spec
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse::<SocketAddr>()
+                .or_else(|_| s.parse::<std::net::IpAddr>().map(|ip| SocketAddr::new(ip, 53)))
+                .with_context(|| format!("Invalid DNS resolver {}", s))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    anyhow::ensure!(!upstreams.is_empty(), "No DNS resolvers given");
+    Ok(upstreams)
+}
+
+/// Send an A query with the DO and AD bits set and report the AD bit of the answer
+async fn query_authenticated(upstream: SocketAddr, domain: &str) -> Result<bool> {
+    let mut edns = Edns::new();
+    edns.set_dnssec_ok(true).set_max_payload(1232);
+
+    let mut query = Message::new();
+    query
+        .set_id(uuid::Uuid::new_v4().as_u128() as u16)
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(true)
+        .set_authentic_data(true)
+        .add_query(Query::query(Name::from_ascii(domain)?, RecordType::A))
+        .set_edns(edns);
+
+    let bind = if upstream.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+    let socket = UdpSocket::bind(bind).await?;
+    socket.send_to(&query.to_vec()?, upstream).await?;
+
+    let mut buf = [0u8; 4096];
+    loop {
+        let (len, from) = socket.recv_from(&mut buf).await?;
+        let Ok(response) = Message::from_vec(&buf[..len]) else {
+            continue;
+        };
+        if from != upstream || response.id() != query.id() {
+            continue;
+        }
+        anyhow::ensure!(
+            matches!(response.response_code(), ResponseCode::NoError | ResponseCode::NXDomain),
+            "DNSSEC query failed: {}",
+            response.response_code()
+        );
+        return Ok(response.authentic_data());
+    }
+}
+
+/// CAA value without presentation quoting, e.g. `letsencrypt.org; validationmethods=dns-01`
+fn caa_value(value: &caa::Value) -> String {
+    match value {
+        caa::Value::Issuer(name, options) => name
+            .iter()
+            .map(name_str)
+            .chain(options.iter().map(|kv| format!("{}={}", kv.key(), kv.value())))
+            .collect::<Vec<_>>()
+            .join("; "),
+        other => other.to_string().trim_matches('"').to_string(),
+    }
+}
+
+fn name_str(name: &Name) -> String {
+    name.to_string().trim_end_matches('.').to_lowercase()
+}
+
+/// Parse a `v=spf1` TXT record
+pub fn parse_spf(record: &str) -> Option<SpfPolicy> {
+    let mut terms = record.split_whitespace();
+    if !terms.next()?.eq_ignore_ascii_case("v=spf1") {
+        return None;
+    }
+
+    let mut spf = SpfPolicy {
+        record: record.to_string(),
+        ..Default::default()
+    };
+
+    for term in terms {
+        let body = term.trim_start_matches(['+', '-', '~', '?']);
+        let mechanism = body.to_lowercase();
+        let value = |prefix: &str| body.get(prefix.len()..).unwrap_or_default().to_string();
+
+        if mechanism.starts_with("include:") {
+            spf.includes.push(value("include:"));
+        } else if mechanism.starts_with("ip4:") {
+            spf.ip4.push(value("ip4:"));
+        } else if mechanism.starts_with("ip6:") {
+            spf.ip6.push(value("ip6:"));
+        } else if mechanism.starts_with("redirect=") {
+            spf.redirect = Some(value("redirect="));
+        } else if mechanism == "all" {
+            spf.all = Some(term.to_string());
+        } else {
+            spf.mechanisms.push(term.to_string());
+        }
+    }
+
+    Some(spf)
+}
+
+/// Parse a `v=DMARC1` TXT record
+pub fn parse_dmarc(record: &str) -> Option<DmarcPolicy> {
+    let tags: HashMap<String, String> = record
+        .split(';')
+        .filter_map(|tag| tag.split_once('='))
+        .map(|(key, value)| (key.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    if !tags.get("v")?.eq_ignore_ascii_case("DMARC1") {
+        return None;
+    }
+
+    let uris = |key: &str| -> Vec<String> {
+        tags.get(key)
+            .map(|v| v.split(',').map(|u| u.trim().to_string()).filter(|u| !u.is_empty()).collect())
+            .unwrap_or_default()
+    };
+
+    Some(DmarcPolicy {
+        record: record.to_string(),
+        policy: tags.get("p").map(|p| p.to_lowercase()),
+        subdomain_policy: tags.get("sp").map(|p| p.to_lowercase()),
+        percentage: tags.get("pct").and_then(|p| p.parse().ok()),
+        rua: uris("rua"),
+        ruf: uris("ruf"),
+        adkim: tags.get("adkim").map(|a| a.to_lowercase()),
+        aspf: tags.get("aspf").map(|a| a.to_lowercase()),
+    })
+}
+
+/// Recognise a domain verification TXT record
+pub fn parse_verification(record: &str) -> Option<DomainVerification> {
+    let lower = record.to_lowercase();
+
+    if let Some((prefix, service)) = VERIFICATION_PREFIXES.iter().find(|(prefix, _)| lower.starts_with(prefix)) {
+        return Some(DomainVerification {
+            service: service.to_string(),
+            token: record.get(prefix.len()..).unwrap_or_default().trim().to_string(),
+        });
+    }
+
+    // Catch-all for the common `<service>-domain-verification=<token>` shape
+    let (key, token) = record.split_once('=')?;
+    let key = key.to_lowercase();
+    let service = key
+        .strip_suffix("-domain-verification")
+        .or_else(|| key.strip_suffix("-site-verification"))
+        .or_else(|| key.strip_suffix("-verification"))?;
+
+    Some(DomainVerification {
+        service: service.to_string(),
+        token: token.trim().to_string(),
+    })
+}
+
+/// Parse a Team Cymru AS record: `13335 | US | arin | 2010-07-14 | CLOUDFLARENET - Cloudflare, Inc., US`
+pub fn parse_cymru_asn(record: &str) -> Option<Value> {
+    let fields: Vec<&str> = record.split('|').map(str::trim).collect();
+    let [asn, country, registry, allocated, as_name] = fields.as_slice() else {
+        return None;
+    };
+    let asn: u32 = asn.parse().ok()?;
+    let field = |value: &str| (!value.is_empty()).then(|| value.to_string());
+
+    Some(serde_json::json!({
+        "asn": asn,
+        "country": field(country),
+        "registry": field(registry),
+        "allocated": field(allocated),
+        "as_name": field(as_name),
+    }))
+}
+
+/// Passive DNS observations in a stored DNS enrichment result
+pub fn passive_dns_observations(indicator: &Indicator, data: &Value) -> Vec<DnsObservation> {
+    let records = |field: &str| -> Vec<String> {
+        data.get(field)
+            .and_then(Value::as_array)
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(|v| v.trim_end_matches('.').to_lowercase())
+                    .filter(|v| !v.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let fields: &[(&str, PdnsRecordType)] = match indicator.ioc_type {
+        IocType::Domain => &[
+            ("a_records", PdnsRecordType::A),
+            ("aaaa_records", PdnsRecordType::Aaaa),
+            ("ns_records", PdnsRecordType::Ns),
+            ("mx_records", PdnsRecordType::Mx),
+        ],
+        IocType::Ip => &[("ptr_records", PdnsRecordType::Ptr)],
+        _ => &[],
+    };
+
+    let mut observations: Vec<DnsObservation> = fields
+        .iter()
+        .flat_map(|(field, rrtype)| {
+            records(field).into_iter().map(|rdata| DnsObservation {
+                rrname: indicator.value.clone(),
+                rrtype: *rrtype,
+                rdata,
+            })
+        })
+        .collect();
+
+    // Each CNAME in the chain aliases the name before it
+    let mut alias = indicator.value.clone();
+    for target in records("cname_records") {
+        observations.push(DnsObservation {
+            rrname: std::mem::replace(&mut alias, target.clone()),
+            rrtype: PdnsRecordType::Cname,
+            rdata: target,
+        });
+    }
+
+    observations
 }
 
 #[async_trait]
@@ -92,42 +513,39 @@ impl EnrichmentProvider for DnsProvider {
     }
 
     fn supports(&self, ioc_type: &IocType) -> bool {
-        matches!(ioc_type, IocType::Domain | IocType::Ip)
+        matches!(ioc_type, IocType::Domain | IocType::Ip | IocType::Asn)
     }
 
     async fn enrich(&self, indicator: &Indicator) -> Result<Option<Value>> {
         match indicator.ioc_type {
             IocType::Domain => {
                 let data = self.lookup(&indicator.value).await?;
-                
+
                 // Only return if we got some data
-                if data.a_records.is_empty() 
-                    && data.mx_records.is_empty() 
-                    && data.ns_records.is_empty() 
+                if data.a_records.is_empty()
+                    && data.aaaa_records.is_empty()
+                    && data.cname_records.is_empty()
+                    && data.mx_records.is_empty()
+                    && data.ns_records.is_empty()
+                    && data.soa.is_none()
                 {
                     return Ok(None);
                 }
 
-                Ok(Some(json!({
-                    "a_records": data.a_records,
-                    "aaaa_records": data.aaaa_records,
-                    "mx_records": data.mx_records,
-                    "txt_records": data.txt_records,
-                    "ns_records": data.ns_records,
-                    "cname_records": data.cname_records,
-                })))
+                Ok(Some(serde_json::to_value(&data)?))
             }
             IocType::Ip => {
                 let ptr_records = self.reverse_lookup(&indicator.value).await?;
-                
+
                 if ptr_records.is_empty() {
                     return Ok(None);
                 }
 
-                Ok(Some(json!({
+                Ok(Some(serde_json::json!({
                     "ptr_records": ptr_records,
                 })))
             }
+            IocType::Asn => Ok(self.asn_lookup(&indicator.value).await),
             _ => Ok(None),
         }
     }
@@ -136,3 +554,238 @@ impl EnrichmentProvider for DnsProvider {
         24 // DNS can change frequently
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use trust_dns_resolver::proto::rr::rdata::{A, CAA, CNAME, MX, NS, SOA, SRV, TXT};
+    use trust_dns_resolver::proto::rr::Record;
+
+    fn name(value: &str) -> Name {
+        Name::from_ascii(format!("{}.", value)).unwrap()
+    }
+
+    fn record(owner: &str, rdata: RData) -> Record {
+        Record::from_rdata(name(owner), 300, rdata)
+    }
+
+    /// Answer queries from a fixed record set, following CNAMEs like a recursive resolver
+    async fn start_stub(records: Vec<Record>, authenticated: bool) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let mut buf = [0u8; 4096];
+            while let Ok((len, from)) = socket.recv_from(&mut buf).await {
+                let Ok(request) = Message::from_vec(&buf[..len]) else {
+                    continue;
+                };
+
+                let mut response = Message::new();
+                response
+                    .set_id(request.id())
+                    .set_message_type(MessageType::Response)
+                    .set_op_code(OpCode::Query)
+                    .set_recursion_desired(request.recursion_desired())
+                    .set_recursion_available(true)
+                    .set_authentic_data(authenticated);
+
+                for query in request.queries() {
+                    response.add_query(query.clone());
+
+                    let mut owner = name_str(query.name());
+                    loop {
+                        let at_owner: Vec<&Record> = records.iter().filter(|r| name_str(r.name()) == owner).collect();
+                        if at_owner.is_empty() && owner == name_str(query.name()) {
+                            response.set_response_code(ResponseCode::NXDomain);
+                        }
+
+                        let answers: Vec<Record> = at_owner
+                            .iter()
+                            .filter(|r| r.record_type() == query.query_type())
+                            .map(|r| (*r).clone())
+                            .collect();
+                        if !answers.is_empty() {
+                            response.add_answers(answers);
+                            break;
+                        }
+
+                        match at_owner.iter().find(|r| r.record_type() == RecordType::CNAME) {
+                            Some(cname) => {
+                                response.add_answer((*cname).clone());
+                                owner = match cname.data() {
+                                    Some(RData::CNAME(target)) => name_str(target),
+                                    _ => break,
+                                };
+                            }
+                            None => break,
+                        }
+                    }
+                }
+
+                let _ = socket.send_to(&response.to_vec().unwrap(), from).await;
+            }
+        });
+
+        addr
+    }
+
+    #[tokio::test]
+    async fn lookup_collects_every_record_type_from_upstream() {
+        let upstream = start_stub(
+            vec![
+                record("www.example.test", RData::CNAME(CNAME(name("edge.example.test")))),
+                record("edge.example.test", RData::CNAME(CNAME(name("cdn.example.net")))),
+                record("cdn.example.net", RData::A(A(Ipv4Addr::new(192, 0, 2, 10)))),
+                record("example.test", RData::A(A(Ipv4Addr::new(192, 0, 2, 1)))),
+                record("example.test", RData::MX(MX::new(10, name("mail.example.test")))),
+                record("example.test", RData::NS(NS(name("ns1.example.test")))),
+                record(
+                    "example.test",
+                    RData::SOA(SOA::new(name("ns1.example.test"), name("hostmaster.example.test"), 2024010101, 7200, 3600, 1209600, 300)),
+                ),
+                record("example.test", RData::CAA(CAA::new_issue(false, Some(name("letsencrypt.org")), vec![]))),
+                record("example.test", RData::TXT(TXT::new(vec!["v=spf1 ip4:192.0.2.0/24 include:_spf.google.com ~all".into()]))),
+                record("example.test", RData::TXT(TXT::new(vec!["google-site-verification=abc123".into()]))),
+                record("_dmarc.example.test", RData::TXT(TXT::new(vec!["v=DMARC1; p=reject; rua=mailto:dmarc@example.test".into()]))),
+                record("_sip._tls.example.test", RData::SRV(SRV::new(100, 1, 443, name("sip.example.test")))),
+            ],
+            true,
+        )
+        .await;
+        let dns = DnsProvider::with_upstreams(&[upstream]);
+
+        let data = dns.lookup("example.test").await.unwrap();
+        assert_eq!(data.a_records, vec!["192.0.2.1"]);
+        assert_eq!(data.mx_records, vec!["mail.example.test"]);
+        assert_eq!(data.ns_records, vec!["ns1.example.test"]);
+        assert_eq!(data.soa.as_ref().unwrap().serial, 2024010101);
+        assert_eq!(data.caa_records[0].tag, "issue");
+        assert_eq!(data.caa_records[0].value, "letsencrypt.org");
+        assert_eq!(data.srv_records[0].service, "_sip._tls");
+        assert_eq!(data.srv_records[0].port, 443);
+        assert_eq!(data.spf.as_ref().unwrap().includes, vec!["_spf.google.com"]);
+        assert_eq!(data.dmarc.as_ref().unwrap().policy.as_deref(), Some("reject"));
+        assert_eq!(data.verifications[0].service, "google");
+        assert_eq!(data.dnssec, DnssecStatus::Secure);
+
+        let data = dns.lookup("www.example.test").await.unwrap();
+        assert_eq!(data.cname_records, vec!["edge.example.test", "cdn.example.net"]);
+        assert_eq!(data.a_records, vec!["192.0.2.10"]);
+    }
+
+    #[tokio::test]
+    async fn cname_chain_becomes_successive_passive_dns_pairs() {
+        let upstream = start_stub(
+            vec![
+                record("www.example.test", RData::CNAME(CNAME(name("cdn.example.net")))),
+                record("cdn.example.net", RData::A(A(Ipv4Addr::new(192, 0, 2, 10)))),
+            ],
+            false,
+        )
+        .await;
+        let dns = DnsProvider::with_upstreams(&[upstream]);
+
+        let now = chrono::Utc::now();
+        let indicator = Indicator {
+            id: uuid::Uuid::new_v4(),
+            ioc_type: IocType::Domain,
+            value: "www.example.test".to_string(),
+            severity: crate::models::Severity::Low,
+            confidence: 50,
+            threat_score: 0,
+            tlp: crate::models::Tlp::White,
+            first_seen: now,
+            last_seen: now,
+            expiration: None,
+            tags: vec![],
+            source_ids: vec![],
+            created_at: now,
+            updated_at: now,
+            hash_algorithm: None,
+            file_object_id: None,
+            network: None,
+            registrable_domain: None,
+        };
+
+        let data = dns.enrich(&indicator).await.unwrap().unwrap();
+        assert_eq!(data["dnssec"], "insecure");
+
+        let observations = passive_dns_observations(&indicator, &data);
+        assert!(observations.contains(&DnsObservation {
+            rrname: "www.example.test".to_string(),
+            rrtype: PdnsRecordType::Cname,
+            rdata: "cdn.example.net".to_string(),
+        }));
+        assert!(observations.contains(&DnsObservation {
+            rrname: "www.example.test".to_string(),
+            rrtype: PdnsRecordType::A,
+            rdata: "192.0.2.10".to_string(),
+        }));
+    }
+
+    #[tokio::test]
+    async fn asn_lookup_reads_team_cymru_record() {
+        let upstream = start_stub(
+            vec![record(
+                "as64500.asn.cymru.com",
+                RData::TXT(TXT::new(vec!["64500 | ZZ | ripencc | 2009-01-01 | EXAMPLE-AS Example Networks, ZZ".into()])),
+            )],
+            false,
+        )
+        .await;
+        let dns = DnsProvider::with_upstreams(&[upstream]);
+
+        let data = dns.asn_lookup("AS64500").await.unwrap();
+        assert_eq!(data["asn"], 64500);
+        assert_eq!(data["registry"], "ripencc");
+        assert_eq!(data["as_name"], "EXAMPLE-AS Example Networks, ZZ");
+        assert!(dns.asn_lookup("AS64501").await.is_none());
+
+        assert_eq!(parse_cymru_asn("23028 | US | arin | 2002-01-04 |").unwrap()["as_name"], Value::Null);
+        assert!(parse_cymru_asn("v=spf1 -all").is_none());
+    }
+
+    #[test]
+    fn parses_spf_dmarc_and_verification_records() {
+        let spf = parse_spf("v=spf1 +mx ip4:198.51.100.0/24 ip6:2001:db8::/32 include:spf.protection.outlook.com -all").unwrap();
+        assert_eq!(spf.ip4, vec!["198.51.100.0/24"]);
+        assert_eq!(spf.ip6, vec!["2001:db8::/32"]);
+        assert_eq!(spf.includes, vec!["spf.protection.outlook.com"]);
+        assert_eq!(spf.mechanisms, vec!["+mx"]);
+        assert_eq!(spf.all.as_deref(), Some("-all"));
+        assert!(parse_spf("v=spf10 -all").is_none());
+
+        let dmarc = parse_dmarc("v=DMARC1; p=Quarantine; sp=none; pct=50; rua=mailto:a@example.test,mailto:b@example.test; adkim=s").unwrap();
+        assert_eq!(dmarc.policy.as_deref(), Some("quarantine"));
+        assert_eq!(dmarc.subdomain_policy.as_deref(), Some("none"));
+        assert_eq!(dmarc.percentage, Some(50));
+        assert_eq!(dmarc.rua.len(), 2);
+        assert_eq!(dmarc.adkim.as_deref(), Some("s"));
+        assert!(parse_dmarc("v=spf1 -all").is_none());
+
+        let ms = parse_verification("MS=ms12345678").unwrap();
+        assert_eq!((ms.service.as_str(), ms.token.as_str()), ("microsoft", "ms12345678"));
+        let other = parse_verification("miro-verification=f00").unwrap();
+        assert_eq!((other.service.as_str(), other.token.as_str()), ("miro", "f00"));
+        assert!(parse_verification("v=spf1 -all").is_none());
+    }
+
+    #[test]
+    fn parses_resolver_lists_and_rejects_bad_ones() {
+        let upstreams = parse_upstreams("10.0.0.53, 10.0.0.54:5353,[2001:db8::53]:53").unwrap();
+        assert_eq!(
+            upstreams,
+            vec![
+                "10.0.0.53:53".parse::<SocketAddr>().unwrap(),
+                "10.0.0.54:5353".parse().unwrap(),
+                "[2001:db8::53]:53".parse().unwrap(),
+            ]
+        );
+
+        assert!(parse_upstreams("10.0.0.53,resolver.internal").is_err());
+        assert!(parse_upstreams("10.0.0.53:dns").is_err());
+        assert!(parse_upstreams(" , ").is_err());
+    }
+}
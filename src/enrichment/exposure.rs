@@ -0,0 +1,541 @@
+//! Host exposure enrichment from Shodan or Censys
+//!
+//! Both services are mapped onto [`HostExposure`]: open ports, service banners,
+//! TLS leaf certificates and reported CVEs. Certificate fingerprints and CVEs
+//! are linked back to the IP as indicators after the result is stored.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use governor::Quota;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::num::NonZeroU32;
+
+use crate::enrichment::{EnrichmentProvider, ProviderHttpError};
+use crate::models::{ExposedService, HostExposure, Indicator, IocType, TlsCertificate};
+
+const SHODAN_API_URL: &str = "https://api.shodan.io";
+const CENSYS_API_URL: &str = "https://search.censys.io/api";
+
+/// Longest banner kept per service
+const MAX_BANNER_CHARS: usize = 1024;
+
+/// Exposure data source and its credentials
+#[derive(Debug, Clone)]
+pub enum ExposureBackend {
+    Shodan { api_key: String },
+    Censys { api_id: String, api_secret: String },
+}
+
+// ==================== Shodan ====================
+
+#[derive(Debug, Deserialize)]
+struct ShodanHost {
+    ip_str: String,
+    #[serde(default)]
+    ports: Vec<u16>,
+    #[serde(default)]
+    hostnames: Vec<String>,
+    os: Option<String>,
+    org: Option<String>,
+    asn: Option<String>,
+    last_update: Option<String>,
+    #[serde(default)]
+    vulns: Vec<String>,
+    #[serde(default)]
+    data: Vec<ShodanService>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShodanService {
+    port: u16,
+    transport: Option<String>,
+    product: Option<String>,
+    version: Option<String>,
+    data: Option<String>,
+    ssl: Option<ShodanSsl>,
+    /// Keyed by CVE identifier
+    #[serde(default)]
+    vulns: HashMap<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShodanSsl {
+    cert: Option<ShodanCert>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShodanCert {
+    #[serde(default)]
+    subject: Map<String, Value>,
+    #[serde(default)]
+    issuer: Map<String, Value>,
+    expires: Option<String>,
+    fingerprint: Option<ShodanFingerprint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShodanFingerprint {
+    sha256: Option<String>,
+    sha1: Option<String>,
+}
+
+/// `CN=example.com, O=Example` from a Shodan name map
+fn distinguished_name(parts: &Map<String, Value>) -> Option<String> {
+    let dn = parts
+        .iter()
+        .filter_map(|(key, value)| value.as_str().map(|v| format!("{}={}", key, v)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    (!dn.is_empty()).then_some(dn)
+}
+
+/// Shodan certificate dates look like `20250101235959Z`
+fn parse_cert_time(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value, "%Y%m%d%H%M%SZ")
+        .ok()
+        .map(|t| t.and_utc())
+        .or_else(|| value.parse().ok())
+}
+
+impl From<ShodanHost> for HostExposure {
+    fn from(host: ShodanHost) -> Self {
+        let mut vulns: BTreeSet<String> = host.vulns.iter().map(|v| v.to_uppercase()).collect();
+
+        let services = host
+            .data
+            .into_iter()
+            .map(|service| {
+                vulns.extend(service.vulns.keys().map(|v| v.to_uppercase()));
+                ExposedService {
+                    port: service.port,
+                    transport: service.transport.unwrap_or_else(|| "tcp".to_string()),
+                    product: service.product,
+                    version: service.version,
+                    banner: service.data.map(truncate_banner),
+                    certificate: service.ssl.and_then(|ssl| ssl.cert).map(|cert| TlsCertificate {
+                        subject: distinguished_name(&cert.subject),
+                        issuer: distinguished_name(&cert.issuer),
+                        sha256: cert.fingerprint.as_ref().and_then(|f| f.sha256.as_ref()).map(|f| f.to_lowercase()),
+                        sha1: cert.fingerprint.as_ref().and_then(|f| f.sha1.as_ref()).map(|f| f.to_lowercase()),
+                        not_after: cert.expires.as_deref().and_then(parse_cert_time),
+                    }),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut ports: BTreeSet<u16> = host.ports.into_iter().collect();
+        ports.extend(services.iter().map(|s| s.port));
+
+        HostExposure {
+            ip: host.ip_str,
+            ports: ports.into_iter().collect(),
+            services,
+            vulns: vulns.into_iter().collect(),
+            hostnames: host.hostnames,
+            os: host.os,
+            org: host.org,
+            asn: host.asn,
+            last_update: host.last_update,
+        }
+    }
+}
+
+// ==================== Censys ====================
+
+#[derive(Debug, Deserialize)]
+struct CensysResponse {
+    result: CensysHost,
+}
+
+#[derive(Debug, Deserialize)]
+struct CensysHost {
+    ip: String,
+    #[serde(default)]
+    services: Vec<CensysService>,
+    autonomous_system: Option<CensysAutonomousSystem>,
+    operating_system: Option<CensysSoftware>,
+    dns: Option<CensysDns>,
+    last_updated_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CensysService {
+    port: u16,
+    transport_protocol: Option<String>,
+    service_name: Option<String>,
+    #[serde(default)]
+    software: Vec<CensysSoftware>,
+    banner: Option<String>,
+    tls: Option<CensysTls>,
+    #[serde(default)]
+    vulnerabilities: Vec<CensysVulnerability>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CensysSoftware {
+    product: Option<String>,
+    version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CensysTls {
+    certificates: Option<CensysCertificates>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CensysCertificates {
+    leaf_fp_sha_256: Option<String>,
+    leaf_data: Option<CensysLeaf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CensysLeaf {
+    subject_dn: Option<String>,
+    issuer_dn: Option<String>,
+    fingerprint_sha1: Option<String>,
+    validity_end: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CensysVulnerability {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CensysAutonomousSystem {
+    asn: Option<u32>,
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CensysDns {
+    #[serde(default)]
+    names: Vec<String>,
+}
+
+impl From<CensysHost> for HostExposure {
+    fn from(host: CensysHost) -> Self {
+        let mut vulns = BTreeSet::new();
+
+        let services = host
+            .services
+            .into_iter()
+            .map(|service| {
+                vulns.extend(service.vulnerabilities.iter().map(|v| v.id.to_uppercase()));
+                let software = service.software.into_iter().next();
+                ExposedService {
+                    port: service.port,
+                    transport: service
+                        .transport_protocol
+                        .map(|t| t.to_lowercase())
+                        .unwrap_or_else(|| "tcp".to_string()),
+                    product: software
+                        .as_ref()
+                        .and_then(|s| s.product.clone())
+                        .or(service.service_name),
+                    version: software.and_then(|s| s.version),
+                    banner: service.banner.map(truncate_banner),
+                    certificate: service.tls.and_then(|tls| tls.certificates).map(|certs| {
+                        let leaf = certs.leaf_data;
+                        TlsCertificate {
+                            subject: leaf.as_ref().and_then(|l| l.subject_dn.clone()),
+                            issuer: leaf.as_ref().and_then(|l| l.issuer_dn.clone()),
+                            sha256: certs.leaf_fp_sha_256.map(|f| f.to_lowercase()),
+                            sha1: leaf.as_ref().and_then(|l| l.fingerprint_sha1.as_ref()).map(|f| f.to_lowercase()),
+                            not_after: leaf.and_then(|l| l.validity_end),
+                        }
+                    }),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let ports: BTreeSet<u16> = services.iter().map(|s| s.port).collect();
+
+        HostExposure {
+            ip: host.ip,
+            ports: ports.into_iter().collect(),
+            services,
+            vulns: vulns.into_iter().collect(),
+            hostnames: host.dns.map(|d| d.names).unwrap_or_default(),
+            os: host.operating_system.and_then(|os| os.product),
+            org: host.autonomous_system.as_ref().and_then(|a| a.name.clone()),
+            asn: host.autonomous_system.and_then(|a| a.asn).map(|asn| format!("AS{}", asn)),
+            last_update: host.last_updated_at,
+        }
+    }
+}
+
+fn truncate_banner(banner: String) -> String {
+    match banner.char_indices().nth(MAX_BANNER_CHARS) {
+        Some((end, _)) => banner[..end].to_string(),
+        None => banner,
+    }
+}
+
+/// Host exposure enrichment provider
+pub struct HostExposureProvider {
+    client: Client,
+    backend: ExposureBackend,
+    base_url: String,
+}
+
+impl HostExposureProvider {
+    /// Create a provider for the given backend
+    pub fn new(backend: ExposureBackend) -> Self {
+        let client = Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        let base_url = match backend {
+            ExposureBackend::Shodan { .. } => SHODAN_API_URL,
+            ExposureBackend::Censys { .. } => CENSYS_API_URL,
+        };
+
+        Self {
+            client,
+            backend,
+            base_url: base_url.to_string(),
+        }
+    }
+
+    /// Use a different API root (proxies, on-premise mirrors)
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Exposure of an IP address; `None` when the service has never scanned it
+    pub async fn lookup(&self, ip: &str) -> Result<Option<HostExposure>> {
+        let request = match &self.backend {
+            ExposureBackend::Shodan { api_key } => self
+                .client
+                .get(format!("{}/shodan/host/{}", self.base_url, ip))
+                .query(&[("key", api_key.as_str())]),
+            ExposureBackend::Censys { api_id, api_secret } => self
+                .client
+                .get(format!("{}/v2/hosts/{}", self.base_url, ip))
+                .basic_auth(api_id, Some(api_secret)),
+        };
+
+        let response = request
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .with_context(|| format!("Failed to send request to {}", self.name()))?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            return Err(ProviderHttpError::from_response(self.name(), response).await.into());
+        }
+
+        let exposure = match self.backend {
+            ExposureBackend::Shodan { .. } => response
+                .json::<ShodanHost>()
+                .await
+                .context("Failed to parse Shodan response")?
+                .into(),
+            ExposureBackend::Censys { .. } => response
+                .json::<CensysResponse>()
+                .await
+                .context("Failed to parse Censys response")?
+                .result
+                .into(),
+        };
+
+        Ok(Some(exposure))
+    }
+}
+
+#[async_trait]
+impl EnrichmentProvider for HostExposureProvider {
+    fn name(&self) -> &'static str {
+        match self.backend {
+            ExposureBackend::Shodan { .. } => "shodan",
+            ExposureBackend::Censys { .. } => "censys",
+        }
+    }
+
+    fn enrichment_type(&self) -> &'static str {
+        "exposure"
+    }
+
+    fn supports(&self, ioc_type: &IocType) -> bool {
+        matches!(ioc_type, IocType::Ip)
+    }
+
+    async fn enrich(&self, indicator: &Indicator) -> Result<Option<Value>> {
+        match self.lookup(&indicator.value).await? {
+            Some(exposure) if !exposure.ports.is_empty() => Ok(Some(serde_json::to_value(&exposure)?)),
+            _ => Ok(None),
+        }
+    }
+
+    fn ttl_hours(&self) -> i64 {
+        72 // Scans revisit hosts every few days
+    }
+
+    fn rate_limit(&self) -> Option<Quota> {
+        match self.backend {
+            // 1 request/second on every paid Shodan plan
+            ExposureBackend::Shodan { .. } => Some(Quota::per_second(NonZeroU32::MIN)),
+            // 0.4 requests/second on the Censys free tier
+            ExposureBackend::Censys { .. } => NonZeroU32::new(24).map(Quota::per_minute),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn shodan(server: &MockServer) -> HostExposureProvider {
+        HostExposureProvider::new(ExposureBackend::Shodan { api_key: "test-key".to_string() })
+            .with_base_url(&server.uri())
+    }
+
+    fn censys(server: &MockServer) -> HostExposureProvider {
+        HostExposureProvider::new(ExposureBackend::Censys {
+            api_id: "id".to_string(),
+            api_secret: "secret".to_string(),
+        })
+        .with_base_url(&server.uri())
+    }
+
+    #[tokio::test]
+    async fn shodan_host_maps_ports_certificates_and_vulns() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/shodan/host/198.51.100.7"))
+            .and(query_param("key", "test-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "ip_str": "198.51.100.7",
+                "ports": [22, 443],
+                "hostnames": ["vpn.example.test"],
+                "org": "Example Hosting",
+                "asn": "AS64500",
+                "os": null,
+                "last_update": "2024-05-01T10:00:00.000000",
+                "vulns": ["CVE-2023-48795"],
+                "data": [
+                    {
+                        "port": 22,
+                        "transport": "tcp",
+                        "product": "OpenSSH",
+                        "version": "8.2p1",
+                        "data": "SSH-2.0-OpenSSH_8.2p1 Ubuntu-4ubuntu0.5\r\n"
+                    },
+                    {
+                        "port": 443,
+                        "transport": "tcp",
+                        "product": "nginx",
+                        "data": "HTTP/1.1 200 OK\r\nServer: nginx\r\n",
+                        "ssl": { "cert": {
+                            "subject": { "CN": "vpn.example.test" },
+                            "issuer": { "CN": "R3", "O": "Let's Encrypt" },
+                            "expires": "20250101235959Z",
+                            "fingerprint": { "sha256": "AB12CD", "sha1": "EF34" }
+                        }},
+                        "vulns": { "cve-2021-23017": { "cvss": 7.7 } }
+                    }
+                ]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let exposure = shodan(&server).lookup("198.51.100.7").await.unwrap().unwrap();
+
+        assert_eq!(exposure.ports, vec![22, 443]);
+        assert_eq!(exposure.vulns, vec!["CVE-2021-23017", "CVE-2023-48795"]);
+        assert_eq!(exposure.services[0].product.as_deref(), Some("OpenSSH"));
+        let cert = exposure.services[1].certificate.as_ref().unwrap();
+        assert_eq!(cert.subject.as_deref(), Some("CN=vpn.example.test"));
+        assert_eq!(cert.issuer.as_deref(), Some("CN=R3, O=Let's Encrypt"));
+        assert_eq!(cert.sha256.as_deref(), Some("ab12cd"));
+        assert_eq!(cert.not_after.unwrap().to_rfc3339(), "2025-01-01T23:59:59+00:00");
+    }
+
+    #[tokio::test]
+    async fn censys_host_maps_services() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v2/hosts/198.51.100.8"))
+            .and(header("authorization", "Basic aWQ6c2VjcmV0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "code": 200,
+                "status": "OK",
+                "result": {
+                    "ip": "198.51.100.8",
+                    "services": [
+                        {
+                            "port": 8443,
+                            "transport_protocol": "TCP",
+                            "service_name": "HTTP",
+                            "software": [{ "product": "cobalt_strike", "version": "4.x" }],
+                            "banner": "HTTP/1.1 404 Not Found",
+                            "tls": { "certificates": {
+                                "leaf_fp_sha_256": "87F2085C32B6A2CC709B365F55873E207A9CAA10BFFECF2FD16D3CF9D94D390C",
+                                "leaf_data": { "subject_dn": "C=, ST=, L=, O=, OU=, CN=", "issuer_dn": "C=, ST=, L=, O=, OU=, CN=" }
+                            }},
+                            "vulnerabilities": [{ "id": "CVE-2022-39197" }]
+                        },
+                        { "port": 53, "transport_protocol": "UDP", "service_name": "DNS" }
+                    ],
+                    "autonomous_system": { "asn": 64501, "name": "EXAMPLE-NET" },
+                    "dns": { "names": ["c2.example.test"] },
+                    "last_updated_at": "2024-05-02T08:00:00Z"
+                }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let exposure = censys(&server).lookup("198.51.100.8").await.unwrap().unwrap();
+
+        assert_eq!(exposure.ports, vec![53, 8443]);
+        assert_eq!(exposure.asn.as_deref(), Some("AS64501"));
+        assert_eq!(exposure.org.as_deref(), Some("EXAMPLE-NET"));
+        assert_eq!(exposure.hostnames, vec!["c2.example.test"]);
+        assert_eq!(exposure.vulns, vec!["CVE-2022-39197"]);
+        assert_eq!(exposure.services[0].product.as_deref(), Some("cobalt_strike"));
+        assert_eq!(exposure.services[1].transport, "udp");
+        assert_eq!(exposure.services[1].product.as_deref(), Some("DNS"));
+        assert_eq!(
+            exposure.services[0].certificate.as_ref().unwrap().sha256.as_deref(),
+            Some("87f2085c32b6a2cc709b365f55873e207a9caa10bffecf2fd16d3cf9d94d390c")
+        );
+    }
+
+    #[tokio::test]
+    async fn unscanned_hosts_return_nothing_and_auth_errors_surface() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/shodan/host/198.51.100.9"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(json!({ "error": "No information available for that IP." })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v2/hosts/198.51.100.9"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        assert!(shodan(&server).lookup("198.51.100.9").await.unwrap().is_none());
+
+        let error = censys(&server).lookup("198.51.100.9").await.unwrap_err();
+        let http = error.downcast_ref::<ProviderHttpError>().unwrap();
+        assert_eq!(http.provider, "censys");
+        assert_eq!(http.status, StatusCode::UNAUTHORIZED);
+    }
+}
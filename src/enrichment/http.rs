@@ -0,0 +1,582 @@
+//! Declarative HTTP enrichment providers
+//!
+//! Internal reputation services and small commercial APIs are described in a
+//! JSON file instead of code: a URL template per IOC type, headers, the JSONPath
+//! of each field to keep, and the usual TTL, timeout and rate limit settings.
+//!
+//! ```json
+//! {
+//!   "providers": [{
+//!     "name": "internal-reputation",
+//!     "enrichment_type": "reputation",
+//!     "urls": { "ip": "https://rep.internal/v1/ip/{value}" },
+//!     "auth": { "header": "X-Api-Key", "value": "${REPUTATION_API_KEY}" },
+//!     "fields": { "score": "$.result.score", "labels": "$.result.labels[*].name" },
+//!     "ttl_hours": 12
+//!   }]
+//! }
+//! ```
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use governor::Quota;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, Method, StatusCode};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroU32;
+use std::path::Path;
+
+use crate::enrichment::{EnrichmentProvider, ProviderHttpError};
+use crate::models::{Indicator, IocType};
+
+/// Provider file layout
+#[derive(Debug, Deserialize)]
+struct ProvidersFile {
+    providers: Vec<HttpProviderConfig>,
+}
+
+/// One declarative provider
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpProviderConfig {
+    pub name: String,
+    pub enrichment_type: String,
+    /// URL template per IOC type; `{value}` is percent-encoded, `{raw_value}` is not
+    pub urls: HashMap<IocType, String>,
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// JSON body for POST requests, with the same placeholders in string values
+    pub body: Option<Value>,
+    pub auth: Option<HttpAuth>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Output field name to JSONPath in the response
+    pub fields: HashMap<String, String>,
+    #[serde(default = "default_ttl_hours")]
+    pub ttl_hours: i64,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    pub requests_per_minute: Option<u32>,
+    /// Statuses meaning "nothing known about this indicator"
+    #[serde(default = "default_not_found")]
+    pub not_found_statuses: Vec<u16>,
+}
+
+/// Credential sent as a header; `${VAR}` is read from the environment
+#[derive(Debug, Deserialize)]
+pub struct HttpAuth {
+    pub header: String,
+    pub value: String,
+}
+
+/// Names of the built-in providers; stored enrichments are keyed by provider name
+const BUILTIN_PROVIDERS: &[&str] = &[
+    "maxmind",
+    "dns",
+    "whois",
+    "rdap",
+    "abuseipdb",
+    "virustotal",
+    "alienvault_otx",
+    "urlhaus",
+    "malwarebazaar",
+    "shodan",
+    "censys",
+];
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+fn default_ttl_hours() -> i64 {
+    24
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_max_retries() -> u32 {
+    2
+}
+
+fn default_not_found() -> Vec<u16> {
+    vec![404]
+}
+
+/// Step of a parsed JSONPath
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(i64),
+    Wildcard,
+}
+
+/// Parse the supported JSONPath subset: `$`, `.key`, `['key']`, `[n]`, `[-n]`, `[*]` and `.*`
+fn parse_path(path: &str) -> Result<Vec<Segment>> {
+    let rest = path
+        .trim()
+        .strip_prefix('$')
+        .with_context(|| format!("JSONPath {} must start with $", path))?;
+    let chars: Vec<char> = rest.chars().collect();
+    let mut segments = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '.' => {
+                anyhow::ensure!(chars.get(i + 1) != Some(&'.'), "Recursive descent is not supported in {}", path);
+                let start = i + 1;
+                let end = (start..chars.len()).find(|&j| chars[j] == '.' || chars[j] == '[').unwrap_or(chars.len());
+                let key: String = chars[start..end].iter().collect();
+                anyhow::ensure!(!key.is_empty(), "Empty key in JSONPath {}", path);
+                segments.push(if key == "*" { Segment::Wildcard } else { Segment::Key(key) });
+                i = end;
+            }
+            '[' => {
+                let end = (i..chars.len())
+                    .find(|&j| chars[j] == ']')
+                    .with_context(|| format!("Unclosed [ in JSONPath {}", path))?;
+                let inner: String = chars[i + 1..end].iter().collect();
+                let inner = inner.trim();
+                segments.push(if inner == "*" {
+                    Segment::Wildcard
+                } else if let Some(key) = inner
+                    .strip_prefix('\'')
+                    .and_then(|k| k.strip_suffix('\''))
+                    .or_else(|| inner.strip_prefix('"').and_then(|k| k.strip_suffix('"')))
+                {
+                    Segment::Key(key.to_string())
+                } else {
+                    Segment::Index(inner.parse().with_context(|| format!("Invalid index [{}] in JSONPath {}", inner, path))?)
+                });
+                i = end + 1;
+            }
+            c => anyhow::bail!("Unexpected '{}' in JSONPath {}", c, path),
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Select a value; paths with a wildcard yield an array of every match
+fn select(root: &Value, segments: &[Segment]) -> Value {
+    let mut current = vec![root];
+    for segment in segments {
+        current = current
+            .into_iter()
+            .flat_map(|value| -> Vec<&Value> {
+                match (segment, value) {
+                    (Segment::Key(key), Value::Object(map)) => map.get(key).into_iter().collect(),
+                    (Segment::Index(index), Value::Array(items)) => {
+                        let index = if *index < 0 { items.len() as i64 + index } else { *index };
+                        usize::try_from(index).ok().and_then(|i| items.get(i)).into_iter().collect()
+                    }
+                    (Segment::Wildcard, Value::Array(items)) => items.iter().collect(),
+                    (Segment::Wildcard, Value::Object(map)) => map.values().collect(),
+                    _ => vec![],
+                }
+            })
+            .collect();
+    }
+
+    if segments.contains(&Segment::Wildcard) {
+        Value::Array(current.into_iter().cloned().collect())
+    } else {
+        current.first().map(|v| (*v).clone()).unwrap_or(Value::Null)
+    }
+}
+
+/// Replace `${VAR}` with environment variables
+fn expand_env(value: &str) -> Result<String> {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..]
+            .find('}')
+            .with_context(|| format!("Unclosed ${{ in {}", value))?;
+        let var = &rest[start + 2..start + end];
+        out.push_str(&rest[..start]);
+        out.push_str(&std::env::var(var).with_context(|| format!("Environment variable {} is not set", var))?);
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Percent-encode everything except RFC 3986 unreserved characters
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Substitute `{value}`, `{raw_value}` and `{type}` in a single pass, so
+/// placeholders inside the indicator value are not expanded again
+fn substitute(template: &str, indicator: &Indicator, value: &str) -> String {
+    let ioc_type = indicator.ioc_type.to_string();
+    let placeholders = [("{value}", value), ("{raw_value}", indicator.value.as_str()), ("{type}", ioc_type.as_str())];
+
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match placeholders.iter().find(|(placeholder, _)| rest.starts_with(placeholder)) {
+            Some((placeholder, replacement)) => {
+                out.push_str(replacement);
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn render(template: &str, indicator: &Indicator) -> String {
+    substitute(template, indicator, &encode(&indicator.value))
+}
+
+fn render_body(body: &Value, indicator: &Indicator) -> Value {
+    match body {
+        Value::String(s) => Value::String(substitute(s, indicator, &indicator.value)),
+        Value::Array(items) => Value::Array(items.iter().map(|v| render_body(v, indicator)).collect()),
+        Value::Object(map) => Value::Object(map.iter().map(|(k, v)| (k.clone(), render_body(v, indicator))).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Enrichment provider described by configuration
+pub struct HttpProvider {
+    /// Leaked once at startup: the provider trait hands out `&'static str`
+    name: &'static str,
+    enrichment_type: &'static str,
+    client: Client,
+    method: Method,
+    urls: HashMap<IocType, String>,
+    body: Option<Value>,
+    headers: HeaderMap,
+    fields: Vec<(String, Vec<Segment>)>,
+    not_found: Vec<StatusCode>,
+    ttl_hours: i64,
+    timeout_secs: u64,
+    max_retries: u32,
+    requests_per_minute: Option<u32>,
+}
+
+impl HttpProvider {
+    /// Build a provider from its configuration, resolving `${VAR}` credentials
+    pub fn from_config(config: HttpProviderConfig) -> Result<Self> {
+        let context = || format!("Invalid enrichment provider {}", config.name);
+
+        anyhow::ensure!(
+            !BUILTIN_PROVIDERS.contains(&config.name.as_str()),
+            "Enrichment provider {} has the name of a built-in provider",
+            config.name
+        );
+        let method = Method::from_bytes(config.method.to_uppercase().as_bytes()).with_context(context)?;
+        anyhow::ensure!(!config.urls.is_empty(), "Enrichment provider {} has no urls", config.name);
+        anyhow::ensure!(!config.fields.is_empty(), "Enrichment provider {} has no fields", config.name);
+
+        let mut headers = HeaderMap::new();
+        let auth = config.auth.iter().map(|a| (&a.header, &a.value));
+        for (name, value) in config.headers.iter().chain(auth) {
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes()).with_context(context)?,
+                HeaderValue::from_str(&expand_env(value).with_context(context)?).with_context(context)?,
+            );
+        }
+
+        let mut fields = config
+            .fields
+            .iter()
+            .map(|(field, path)| Ok((field.clone(), parse_path(path)?)))
+            .collect::<Result<Vec<_>>>()
+            .with_context(context)?;
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let not_found = config
+            .not_found_statuses
+            .iter()
+            .map(|s| StatusCode::from_u16(*s))
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(context)?;
+
+        let client = Client::builder()
+            .timeout(std::time::Duration::from_secs(config.timeout_secs))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Ok(Self {
+            name: Box::leak(config.name.into_boxed_str()),
+            enrichment_type: Box::leak(config.enrichment_type.into_boxed_str()),
+            client,
+            method,
+            urls: config.urls,
+            body: config.body,
+            headers,
+            fields,
+            not_found,
+            ttl_hours: config.ttl_hours,
+            timeout_secs: config.timeout_secs,
+            max_retries: config.max_retries,
+            requests_per_minute: config.requests_per_minute,
+        })
+    }
+
+    /// Load every provider from a JSON file
+    pub fn load_file(path: &Path) -> Result<Vec<Self>> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read enrichment providers from {:?}", path))?;
+        let file: ProvidersFile = serde_json::from_str(&text)
+            .with_context(|| format!("Invalid enrichment providers file {:?}", path))?;
+
+        let mut names = HashSet::new();
+        for config in &file.providers {
+            anyhow::ensure!(names.insert(config.name.clone()), "Duplicate enrichment provider {}", config.name);
+        }
+
+        file.providers.into_iter().map(Self::from_config).collect()
+    }
+}
+
+#[async_trait]
+impl EnrichmentProvider for HttpProvider {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn enrichment_type(&self) -> &'static str {
+        self.enrichment_type
+    }
+
+    fn supports(&self, ioc_type: &IocType) -> bool {
+        self.urls.contains_key(ioc_type)
+    }
+
+    async fn enrich(&self, indicator: &Indicator) -> Result<Option<Value>> {
+        let Some(template) = self.urls.get(&indicator.ioc_type) else {
+            return Ok(None);
+        };
+
+        let mut request = self
+            .client
+            .request(self.method.clone(), render(template, indicator))
+            .headers(self.headers.clone());
+        if let Some(body) = &self.body {
+            request = request.json(&render_body(body, indicator));
+        }
+
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to send request to {}", self.name))?;
+
+        if self.not_found.contains(&response.status()) {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            return Err(ProviderHttpError::from_response(self.name, response).await.into());
+        }
+
+        let data: Value = response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse {} response", self.name))?;
+
+        let result: Map<String, Value> = self
+            .fields
+            .iter()
+            .map(|(field, path)| (field.clone(), select(&data, path)))
+            .collect();
+
+        if result.values().all(|v| v.is_null() || v.as_array().is_some_and(Vec::is_empty)) {
+            return Ok(None);
+        }
+
+        Ok(Some(Value::Object(result)))
+    }
+
+    fn ttl_hours(&self) -> i64 {
+        self.ttl_hours
+    }
+
+    fn rate_limit(&self) -> Option<Quota> {
+        self.requests_per_minute.and_then(NonZeroU32::new).map(Quota::per_minute)
+    }
+
+    fn timeout_secs(&self) -> u64 {
+        self.timeout_secs
+    }
+
+    fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn indicator(ioc_type: IocType, value: &str) -> Indicator {
+        Indicator {
+            id: uuid::Uuid::new_v4(),
+            ioc_type,
+            value: value.to_string(),
+            severity: crate::models::Severity::Medium,
+            confidence: 50,
+            threat_score: 50,
+            tlp: crate::models::Tlp::Amber,
+            first_seen: Utc::now(),
+            last_seen: Utc::now(),
+            expiration: None,
+            tags: vec![],
+            source_ids: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            hash_algorithm: None,
+            file_object_id: None,
+            network: None,
+            registrable_domain: None,
+        }
+    }
+
+    fn config(value: Value) -> HttpProviderConfig {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn parses_supported_jsonpath_subset() {
+        assert_eq!(
+            parse_path("$.result['last seen'][0].*").unwrap(),
+            vec![
+                Segment::Key("result".to_string()),
+                Segment::Key("last seen".to_string()),
+                Segment::Index(0),
+                Segment::Wildcard,
+            ]
+        );
+        assert_eq!(
+            parse_path(r#"$["data"][-1][*]"#).unwrap(),
+            vec![Segment::Key("data".to_string()), Segment::Index(-1), Segment::Wildcard]
+        );
+        assert!(parse_path("$").unwrap().is_empty());
+
+        for invalid in ["result.score", "$..score", "$.a.", "$[0", "$[x]", "$a"] {
+            assert!(parse_path(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn selects_values_and_wildcard_matches() {
+        let data = json!({
+            "result": {
+                "score": 87,
+                "labels": [{ "name": "c2" }, { "name": "botnet" }, { "id": 3 }],
+            }
+        });
+        let get = |path: &str| select(&data, &parse_path(path).unwrap());
+
+        assert_eq!(get("$.result.score"), json!(87));
+        assert_eq!(get("$.result.labels[-1].id"), json!(3));
+        assert_eq!(get("$.result.labels[*].name"), json!(["c2", "botnet"]));
+        assert_eq!(get("$.result.missing"), Value::Null);
+        assert_eq!(get("$.result.labels[7]"), Value::Null);
+        assert_eq!(get("$.result.score[*]"), json!([]));
+    }
+
+    #[test]
+    fn expands_environment_variables() {
+        let name = env!("CARGO_PKG_NAME");
+        assert_eq!(expand_env("Bearer ${CARGO_PKG_NAME}!").unwrap(), format!("Bearer {}!", name));
+        assert_eq!(expand_env("no variables").unwrap(), "no variables");
+        assert!(expand_env("${SENTINELFORGE_TEST_UNSET_VARIABLE}").is_err());
+        assert!(expand_env("${CARGO_PKG_NAME").is_err());
+    }
+
+    #[test]
+    fn renders_placeholders_once() {
+        let url = indicator(IocType::Url, "http://evil.test/{type}?q={raw_value}");
+
+        assert_eq!(
+            render("https://rep.test/v1/{type}/{value}", &url),
+            "https://rep.test/v1/url/http%3A%2F%2Fevil.test%2F%7Btype%7D%3Fq%3D%7Braw_value%7D"
+        );
+        assert_eq!(
+            render_body(&json!({ "query": "{raw_value}", "kind": "{type}", "ids": ["{value}", 3], "x": "{other}" }), &url),
+            json!({
+                "query": "http://evil.test/{type}?q={raw_value}",
+                "kind": "url",
+                "ids": ["http://evil.test/{type}?q={raw_value}", 3],
+                "x": "{other}",
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_builtin_provider_names() {
+        let result = HttpProvider::from_config(config(json!({
+            "name": "virustotal",
+            "enrichment_type": "reputation",
+            "urls": { "ip": "https://rep.test/{value}" },
+            "fields": { "score": "$.score" },
+        })));
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn round_trip_posts_body_and_maps_fields() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/lookup/ip"))
+            .and(header("X-Api-Key", "secret"))
+            .and(body_json(json!({ "indicator": "198.51.100.7", "type": "ip" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "result": { "score": 91, "labels": [{ "name": "scanner" }] }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/lookup/ip"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let provider = HttpProvider::from_config(config(json!({
+            "name": "internal-reputation",
+            "enrichment_type": "reputation",
+            "method": "post",
+            "urls": { "ip": format!("{}/v1/lookup/{{type}}", server.uri()) },
+            "body": { "indicator": "{raw_value}", "type": "{type}" },
+            "headers": { "X-Api-Key": "secret" },
+            "fields": { "score": "$.result.score", "labels": "$.result.labels[*].name", "asn": "$.result.asn" },
+        })))
+        .unwrap();
+
+        assert!(provider.supports(&IocType::Ip));
+        assert!(!provider.supports(&IocType::Domain));
+
+        let data = provider.enrich(&indicator(IocType::Ip, "198.51.100.7")).await.unwrap().unwrap();
+        assert_eq!(data, json!({ "asn": null, "labels": ["scanner"], "score": 91 }));
+
+        assert!(provider.enrich(&indicator(IocType::Ip, "198.51.100.8")).await.unwrap().is_none());
+    }
+}
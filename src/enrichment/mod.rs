@@ -5,12 +5,26 @@ pub mod whois;
 pub mod dns;
 pub mod abuseipdb;
 pub mod virustotal;
+pub mod abusech;
+pub mod otx;
+pub mod rdap;
+pub mod http;
+pub mod exposure;
+pub mod cache;
+pub mod queue;
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use futures::future::join_all;
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use serde::Serialize;
 use serde_json::Value;
+use uuid::Uuid;
 
-use crate::models::{Indicator, IocType};
+use crate::models::{AuditContext, EnrichMode, Enrichment, FileHashes, HostExposure, Indicator, IocType};
+use crate::storage::ThreatIntelRepo;
+use cache::{CachedEnrichment, EnrichmentCache};
 
 /// Trait for enrichment providers
 #[async_trait]
@@ -31,59 +45,437 @@ pub trait EnrichmentProvider: Send + Sync {
     fn ttl_hours(&self) -> i64 {
         24
     }
+
+    /// Request budget enforced before each call (token bucket); `None` is unlimited
+    fn rate_limit(&self) -> Option<Quota> {
+        None
+    }
+
+    /// Maximum time for a single call in seconds
+    fn timeout_secs(&self) -> u64 {
+        30
+    }
+
+    /// Attempts after the first on timeouts, 429 and 5xx responses
+    fn max_retries(&self) -> u32 {
+        2
+    }
+}
+
+/// Non-success HTTP response from a provider API
+#[derive(Debug, thiserror::Error)]
+#[error("{provider} API error: {status} - {body}")]
+pub struct ProviderHttpError {
+    pub provider: &'static str,
+    pub status: reqwest::StatusCode,
+    pub body: String,
+    /// Delay requested by a `Retry-After` header
+    pub retry_after: Option<std::time::Duration>,
+}
+
+impl ProviderHttpError {
+    /// Build the error from a failed response, consuming its body
+    pub async fn from_response(provider: &'static str, response: reqwest::Response) -> Self {
+        let status = response.status();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(std::time::Duration::from_secs);
+        let body = response.text().await.unwrap_or_default();
+
+        Self { provider, status, body, retry_after }
+    }
+}
+
+/// A provider call that exceeded its `timeout_secs`
+#[derive(Debug, thiserror::Error)]
+#[error("{provider} timed out after {seconds}s")]
+pub struct ProviderTimeout {
+    pub provider: &'static str,
+    pub seconds: u64,
+}
+
+/// Whether a failed call is worth retrying, and after how long if the provider said
+fn retry_hint(error: &anyhow::Error) -> Option<Option<std::time::Duration>> {
+    for cause in error.chain() {
+        if cause.is::<ProviderTimeout>() {
+            return Some(None);
+        }
+        if let Some(http) = cause.downcast_ref::<ProviderHttpError>() {
+            let retryable = http.status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                || http.status.is_server_error();
+            return retryable.then_some(http.retry_after);
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return (e.is_timeout() || e.is_connect()).then_some(None);
+        }
+    }
+    None
+}
+
+/// Base delay between retries, doubled on every attempt
+const RETRY_BACKOFF_MS: u64 = 500;
+
+/// A provider with its rate limiter
+struct RegisteredProvider {
+    provider: Box<dyn EnrichmentProvider>,
+    limiter: Option<DefaultDirectRateLimiter>,
+}
+
+impl RegisteredProvider {
+    /// Call the provider, honouring its rate limit, timeout and retry policy
+    async fn call(&self, indicator: &Indicator) -> Result<Option<Value>> {
+        let name = self.provider.name();
+        let seconds = self.provider.timeout_secs();
+        let mut attempt = 0;
+
+        loop {
+            if let Some(limiter) = &self.limiter
+                && limiter.check().is_err()
+            {
+                metrics::counter!("enrichment_rate_limited_total", "provider" => name).increment(1);
+                limiter.until_ready().await;
+            }
+
+            let started = std::time::Instant::now();
+            let timeout = std::time::Duration::from_secs(seconds);
+            let result = match tokio::time::timeout(timeout, self.provider.enrich(indicator)).await {
+                Ok(result) => result,
+                Err(_) => Err(ProviderTimeout { provider: name, seconds }.into()),
+            };
+            metrics::histogram!("enrichment_duration_seconds", "provider" => name)
+                .record(started.elapsed().as_secs_f64());
+
+            let error = match result {
+                Ok(data) => return Ok(data),
+                Err(e) => e,
+            };
+
+            match retry_hint(&error) {
+                Some(retry_after) if attempt < self.provider.max_retries() => {
+                    let delay = retry_after
+                        .unwrap_or(std::time::Duration::from_millis(RETRY_BACKOFF_MS << attempt));
+                    tracing::debug!(
+                        provider = name,
+                        attempt = attempt + 1,
+                        error = %error,
+                        "Retrying enrichment in {:?}",
+                        delay
+                    );
+                    metrics::counter!("enrichment_retries_total", "provider" => name).increment(1);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                _ => return Err(error),
+            }
+        }
+    }
+}
+
+/// A provider result ready to be stored
+#[derive(Debug, Clone)]
+pub struct EnrichmentResult {
+    pub enrichment_type: String,
+    pub provider: String,
+    pub data: Value,
+    pub expires_at: DateTime<Utc>,
+    /// Served from the shared cache rather than the provider
+    pub cached: bool,
+}
+
+/// Outcome of enriching one indicator
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EnrichmentSummary {
+    /// Results fetched from providers and stored
+    pub fetched: usize,
+    /// Results reused from the database or the shared cache
+    pub cached: usize,
+    /// Stored results that differ materially from the previous snapshot
+    pub changed: usize,
+    /// Providers that failed, as `provider: error`
+    pub errors: Vec<String>,
+}
+
+/// Provider results of one run, before they are stored
+#[derive(Debug, Default)]
+pub struct EnrichmentRun {
+    pub results: Vec<EnrichmentResult>,
+    /// Providers skipped because their stored result is still fresh
+    pub fresh: usize,
+    /// Providers that failed, as `provider: error`
+    pub errors: Vec<String>,
+    /// Expired results that could not be refreshed
+    pub retries: Vec<RefreshRetry>,
+}
+
+/// Expired stored result whose provider failed or returned no data; its
+/// refresh is retried later rather than on every refresh run
+#[derive(Debug, Clone)]
+pub struct RefreshRetry {
+    pub enrichment_id: Uuid,
+    /// Longest the retry is put off: the provider's TTL
+    pub max_hours: i64,
 }
 
+/// What a single provider contributed to an enrichment run
+enum ProviderOutcome {
+    /// The stored result is still fresh
+    Fresh,
+    /// Nothing to store: not due or no data
+    Skipped,
+    Failed(String),
+    Result(EnrichmentResult),
+}
+
+/// Provider outcome plus the stored result to retry later, if any
+type ProviderRun = (ProviderOutcome, Option<RefreshRetry>);
+
 /// Enrichment engine that coordinates multiple providers
 pub struct EnrichmentEngine {
-    providers: Vec<Box<dyn EnrichmentProvider>>,
+    providers: Vec<RegisteredProvider>,
+    cache: Option<EnrichmentCache>,
 }
 
 impl EnrichmentEngine {
     pub fn new() -> Self {
-        Self { providers: vec![] }
+        Self { providers: vec![], cache: None }
     }
 
     pub fn add_provider(&mut self, provider: Box<dyn EnrichmentProvider>) {
-        self.providers.push(provider);
+        let limiter = provider.rate_limit().map(RateLimiter::direct);
+        self.providers.push(RegisteredProvider { provider, limiter });
+    }
+
+    /// Names of the registered providers
+    pub fn provider_names(&self) -> Vec<&'static str> {
+        self.providers.iter().map(|registered| registered.provider.name()).collect()
+    }
+
+    /// Share provider results through Redis
+    pub fn set_cache(&mut self, cache: EnrichmentCache) {
+        self.cache = Some(cache);
     }
 
-    /// Enrich an indicator with all applicable providers
-    pub async fn enrich_all(&self, indicator: &Indicator) -> Vec<(String, String, Value, i64)> {
-        let mut results = vec![];
+    /// Enrich an indicator with all applicable providers, running them concurrently.
+    ///
+    /// `stored` holds the indicator's current enrichments; a provider whose
+    /// stored result has not reached `expires_at` is skipped unless `mode` is
+    /// [`EnrichMode::Force`].
+    pub async fn enrich_all(
+        &self,
+        indicator: &Indicator,
+        stored: &[Enrichment],
+        mode: EnrichMode,
+    ) -> EnrichmentRun {
+        let outcomes = join_all(
+            self.providers
+                .iter()
+                .filter(|registered| registered.provider.supports(&indicator.ioc_type))
+                .map(|registered| self.run_provider(registered, indicator, stored, mode)),
+        )
+        .await;
 
-        for provider in &self.providers {
-            if !provider.supports(&indicator.ioc_type) {
-                continue;
+        let mut run = EnrichmentRun::default();
+        for (outcome, retry) in outcomes {
+            run.retries.extend(retry);
+            match outcome {
+                ProviderOutcome::Fresh => run.fresh += 1,
+                ProviderOutcome::Skipped => {}
+                ProviderOutcome::Failed(error) => run.errors.push(error),
+                ProviderOutcome::Result(result) => run.results.push(result),
             }
+        }
 
-            match provider.enrich(indicator).await {
-                Ok(Some(data)) => {
-                    results.push((
-                        provider.enrichment_type().to_string(),
-                        provider.name().to_string(),
-                        data,
-                        provider.ttl_hours(),
-                    ));
+        run
+    }
+
+    async fn run_provider(
+        &self,
+        registered: &RegisteredProvider,
+        indicator: &Indicator,
+        stored: &[Enrichment],
+        mode: EnrichMode,
+    ) -> ProviderRun {
+        let provider = registered.provider.as_ref();
+        let name = provider.name();
+
+        let existing = stored
+            .iter()
+            .find(|e| e.provider == name && e.enrichment_type == provider.enrichment_type());
+        let is_fresh = existing.is_some_and(|e| e.expires_at.is_none_or(|at| at > Utc::now()));
+        // A failed refresh of an expired result is retried with backoff
+        let retry = existing.filter(|_| !is_fresh).map(|e| RefreshRetry {
+            enrichment_id: e.id,
+            max_hours: provider.ttl_hours(),
+        });
+
+        match mode {
+            EnrichMode::Force => {}
+            EnrichMode::Cached if is_fresh => {
+                metrics::counter!("enrichment_requests_total", "provider" => name, "outcome" => "fresh")
+                    .increment(1);
+                return (ProviderOutcome::Fresh, None);
+            }
+            EnrichMode::Cached => {}
+            EnrichMode::Expired if existing.is_none() || is_fresh => return (ProviderOutcome::Skipped, None),
+            EnrichMode::Expired => {}
+        }
+
+        if mode != EnrichMode::Force
+            && let Some(cache) = &self.cache
+            && let Some(hit) = cache.get(name, &indicator.ioc_type, &indicator.value).await
+        {
+            metrics::counter!("enrichment_requests_total", "provider" => name, "outcome" => "cached")
+                .increment(1);
+            return (
+                ProviderOutcome::Result(EnrichmentResult {
+                    enrichment_type: provider.enrichment_type().to_string(),
+                    provider: name.to_string(),
+                    data: hit.data,
+                    expires_at: hit.expires_at,
+                    cached: true,
+                }),
+                None,
+            );
+        }
+
+        match registered.call(indicator).await {
+            Ok(Some(data)) => {
+                metrics::counter!("enrichment_requests_total", "provider" => name, "outcome" => "success")
+                    .increment(1);
+                let expires_at = Utc::now() + Duration::hours(provider.ttl_hours());
+                if let Some(cache) = &self.cache {
+                    let entry = CachedEnrichment { data: data.clone(), expires_at };
+                    cache.put(name, &indicator.ioc_type, &indicator.value, &entry).await;
                 }
-                Ok(None) => {
-                    tracing::debug!(
-                        provider = provider.name(),
-                        indicator = %indicator.value,
-                        "No enrichment data returned"
-                    );
+
+                (
+                    ProviderOutcome::Result(EnrichmentResult {
+                        enrichment_type: provider.enrichment_type().to_string(),
+                        provider: name.to_string(),
+                        data,
+                        expires_at,
+                        cached: false,
+                    }),
+                    None,
+                )
+            }
+            Ok(None) => {
+                metrics::counter!("enrichment_requests_total", "provider" => name, "outcome" => "empty")
+                    .increment(1);
+                tracing::debug!(
+                    provider = name,
+                    indicator = %indicator.value,
+                    "No enrichment data returned"
+                );
+                (ProviderOutcome::Skipped, retry)
+            }
+            Err(e) => {
+                let outcome = if e.is::<ProviderTimeout>() { "timeout" } else { "error" };
+                metrics::counter!("enrichment_requests_total", "provider" => name, "outcome" => outcome)
+                    .increment(1);
+                tracing::warn!(
+                    provider = name,
+                    indicator = %indicator.value,
+                    error = %e,
+                    "Enrichment failed"
+                );
+                (ProviderOutcome::Failed(format!("{}: {}", name, e)), retry)
+            }
+        }
+    }
+
+    /// Enrich an indicator and persist every new result.
+    ///
+    /// When a provider reports the other hashes of a file, the MD5/SHA1/SHA256
+    /// indicators are linked to a shared file object.
+    pub async fn enrich_and_store(
+        &self,
+        repo: &ThreatIntelRepo,
+        indicator: &Indicator,
+        mode: EnrichMode,
+        ctx: &AuditContext,
+    ) -> EnrichmentSummary {
+        let stored = match mode {
+            EnrichMode::Force => vec![],
+            _ => repo.get_enrichments(indicator.id).await.unwrap_or_else(|e| {
+                tracing::warn!(indicator = %indicator.id, error = %e, "Failed to load stored enrichments");
+                vec![]
+            }),
+        };
+
+        let run = self.enrich_all(indicator, &stored, mode).await;
+        for retry in &run.retries {
+            if let Err(e) = repo.defer_enrichment_refresh(retry.enrichment_id, retry.max_hours).await {
+                tracing::warn!(enrichment = %retry.enrichment_id, error = %e, "Failed to defer enrichment refresh");
+            }
+        }
+        let mut summary = EnrichmentSummary { fetched: 0, cached: run.fresh, changed: 0, errors: run.errors };
+
+        for result in run.results {
+            let file_hashes = result
+                .data
+                .get("file")
+                .and_then(|f| serde_json::from_value::<FileHashes>(f.clone()).ok());
+            let exposure = match result.enrichment_type.as_str() {
+                "exposure" => serde_json::from_value::<HostExposure>(result.data.clone()).ok(),
+                _ => None,
+            };
+            let observations = match result.enrichment_type.as_str() {
+                "dns" if !result.cached => dns::passive_dns_observations(indicator, &result.data),
+                _ => vec![],
+            };
+
+            match repo
+                .add_enrichment(
+                    indicator.id,
+                    &result.enrichment_type,
+                    &result.provider,
+                    result.data,
+                    Some(result.expires_at),
+                    ctx,
+                )
+                .await
+            {
+                Ok(stored) => {
+                    if stored.changes.is_some() {
+                        summary.changed += 1;
+                        metrics::counter!("enrichment_changes_total", "provider" => result.provider.clone()).increment(1);
+                        tracing::info!(indicator = %indicator.id, provider = %result.provider, "Enrichment changed materially");
+                    }
+                    if result.cached {
+                        summary.cached += 1;
+                    } else {
+                        summary.fetched += 1;
+                    }
                 }
                 Err(e) => {
-                    tracing::warn!(
-                        provider = provider.name(),
-                        indicator = %indicator.value,
-                        error = %e,
-                        "Enrichment failed"
-                    );
+                    tracing::warn!(provider = %result.provider, error = %e, "Failed to save enrichment");
+                    summary.errors.push(format!("{}: {}", result.provider, e));
+                    continue;
                 }
             }
+
+            if let (IocType::Hash, Some(hashes)) = (&indicator.ioc_type, file_hashes)
+                && let Err(e) = repo.link_file_hashes(indicator, &hashes, ctx).await
+            {
+                tracing::warn!(provider = %result.provider, error = %e, "Failed to link file hashes");
+            }
+
+            if let Some(exposure) = exposure
+                && let Err(e) = repo.link_exposure(indicator, &exposure, ctx).await
+            {
+                tracing::warn!(provider = %result.provider, error = %e, "Failed to link exposure indicators");
+            }
+
+            if let Err(e) = repo.record_passive_dns(&observations).await {
+                tracing::warn!(provider = %result.provider, error = %e, "Failed to record passive DNS");
+            }
         }
 
-        results
+        summary
     }
 }
 
@@ -92,3 +484,119 @@ impl Default for EnrichmentEngine {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Severity, Tlp};
+
+    /// Provider returning a fixed outcome
+    struct Stub {
+        name: &'static str,
+        outcome: fn() -> Result<Option<Value>>,
+    }
+
+    #[async_trait]
+    impl EnrichmentProvider for Stub {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn enrichment_type(&self) -> &'static str {
+            "reputation"
+        }
+
+        fn supports(&self, _ioc_type: &IocType) -> bool {
+            true
+        }
+
+        async fn enrich(&self, _indicator: &Indicator) -> Result<Option<Value>> {
+            (self.outcome)()
+        }
+
+        fn ttl_hours(&self) -> i64 {
+            48
+        }
+
+        fn max_retries(&self) -> u32 {
+            0
+        }
+    }
+
+    fn indicator() -> Indicator {
+        let now = Utc::now();
+        Indicator {
+            id: Uuid::new_v4(),
+            ioc_type: IocType::Ip,
+            value: "198.51.100.7".to_string(),
+            severity: Severity::Medium,
+            confidence: 50,
+            threat_score: 0,
+            tlp: Tlp::Green,
+            first_seen: now,
+            last_seen: now,
+            expiration: None,
+            tags: vec![],
+            source_ids: vec![],
+            created_at: now,
+            updated_at: now,
+            hash_algorithm: None,
+            file_object_id: None,
+            network: None,
+            registrable_domain: None,
+        }
+    }
+
+    fn stored(indicator: &Indicator, provider: &str, expires_in: Duration) -> Enrichment {
+        Enrichment {
+            id: Uuid::new_v4(),
+            indicator_id: indicator.id,
+            enrichment_type: "reputation".to_string(),
+            data: serde_json::json!({ "score": 10 }),
+            provider: provider.to_string(),
+            fetched_at: Utc::now() - Duration::days(3),
+            expires_at: Some(Utc::now() + expires_in),
+            is_latest: true,
+            changes: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_refreshes_of_expired_results_are_retried_later() {
+        let mut engine = EnrichmentEngine::new();
+        engine.add_provider(Box::new(Stub { name: "failing", outcome: || anyhow::bail!("upstream down") }));
+        engine.add_provider(Box::new(Stub { name: "empty", outcome: || Ok(None) }));
+        engine.add_provider(Box::new(Stub { name: "working", outcome: || Ok(Some(serde_json::json!({ "score": 20 }))) }));
+        let indicator = indicator();
+        let stored: Vec<_> = ["failing", "empty", "working"]
+            .into_iter()
+            .map(|name| stored(&indicator, name, Duration::hours(-1)))
+            .collect();
+
+        let run = engine.enrich_all(&indicator, &stored, EnrichMode::Expired).await;
+
+        assert_eq!(run.errors, vec!["failing: upstream down"]);
+        assert_eq!(run.results.len(), 1);
+        let retried: Vec<_> = run.retries.iter().map(|r| (r.enrichment_id, r.max_hours)).collect();
+        assert_eq!(retried, vec![(stored[0].id, 48), (stored[1].id, 48)]);
+    }
+
+    #[tokio::test]
+    async fn failures_without_an_expired_result_are_not_retried() {
+        let mut engine = EnrichmentEngine::new();
+        engine.add_provider(Box::new(Stub { name: "failing", outcome: || anyhow::bail!("upstream down") }));
+        let indicator = indicator();
+
+        // Never enriched
+        let run = engine.enrich_all(&indicator, &[], EnrichMode::Cached).await;
+        assert_eq!(run.errors.len(), 1);
+        assert!(run.retries.is_empty());
+
+        // Still fresh, forced
+        let fresh = [stored(&indicator, "failing", Duration::hours(1))];
+        let run = engine.enrich_all(&indicator, &fresh, EnrichMode::Force).await;
+        assert_eq!(run.errors.len(), 1);
+        assert!(run.retries.is_empty());
+        assert_eq!(engine.provider_names(), vec!["failing"]);
+    }
+}
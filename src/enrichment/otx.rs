@@ -0,0 +1,206 @@
+//! AlienVault OTX enrichment provider
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+
+use crate::enrichment::{EnrichmentProvider, ProviderHttpError};
+use crate::models::{HashAlgorithm, Indicator, IocType};
+
+const OTX_API_URL: &str = "https://otx.alienvault.com/api/v1";
+
+/// Pulses listed in a result (the count covers all of them)
+const MAX_PULSES: usize = 10;
+
+#[derive(Debug, Deserialize)]
+struct OtxGeneral {
+    pulse_info: Option<OtxPulseInfo>,
+    reputation: Option<i64>,
+    #[serde(default)]
+    validation: Vec<OtxValidation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OtxPulseInfo {
+    count: i64,
+    #[serde(default)]
+    pulses: Vec<OtxPulseSummary>,
+    #[serde(default)]
+    references: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OtxPulseSummary {
+    id: String,
+    name: String,
+    modified: Option<String>,
+    adversary: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    malware_families: Vec<OtxNamed>,
+    #[serde(default)]
+    attack_ids: Vec<OtxNamed>,
+}
+
+/// Malware family or ATT&CK technique, a bare string or an object
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OtxNamed {
+    Name(String),
+    Object { id: String, display_name: Option<String> },
+}
+
+impl OtxNamed {
+    fn id(&self) -> &str {
+        match self {
+            OtxNamed::Name(name) => name,
+            OtxNamed::Object { id, .. } => id,
+        }
+    }
+
+    fn display_name(&self) -> &str {
+        match self {
+            OtxNamed::Name(name) => name,
+            OtxNamed::Object { id, display_name } => display_name.as_deref().unwrap_or(id),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OtxValidation {
+    source: Option<String>,
+    message: Option<String>,
+}
+
+/// AlienVault OTX enrichment provider
+pub struct OtxProvider {
+    client: Client,
+    api_key: String,
+}
+
+impl OtxProvider {
+    /// Create a new OTX provider
+    pub fn new(api_key: String) -> Self {
+        let client = Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self { client, api_key }
+    }
+
+    /// OTX indicator section for an indicator, if OTX tracks its type
+    fn section(indicator: &Indicator) -> Option<&'static str> {
+        match indicator.ioc_type {
+            IocType::Ip if indicator.value.contains(':') => Some("IPv6"),
+            IocType::Ip => Some("IPv4"),
+            IocType::Domain => Some("domain"),
+            IocType::Url => Some("url"),
+            IocType::Cve => Some("cve"),
+            // Fuzzy hashes and imphashes are not file identifiers in OTX
+            IocType::Hash => matches!(
+                indicator.hash_algorithm,
+                None | Some(HashAlgorithm::Md5) | Some(HashAlgorithm::Sha1) | Some(HashAlgorithm::Sha256)
+            )
+            .then_some("file"),
+            _ => None,
+        }
+    }
+
+    /// General OTX information for an indicator; `None` when OTX has nothing on it
+    pub async fn lookup(&self, section: &str, value: &str) -> Result<Option<Value>> {
+        // URLs go into the path as-is apart from characters that would end it
+        let value = value.replace('%', "%25").replace('?', "%3F").replace('#', "%23");
+        let response = self
+            .client
+            .get(format!("{}/indicators/{}/{}/general", OTX_API_URL, section, value))
+            .header("X-OTX-API-KEY", &self.api_key)
+            .send()
+            .await
+            .context("Failed to send request to OTX")?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            return Err(ProviderHttpError::from_response("OTX", response).await.into());
+        }
+
+        let general: OtxGeneral = response
+            .json()
+            .await
+            .context("Failed to parse OTX response")?;
+
+        let Some(info) = general.pulse_info.filter(|info| info.count > 0) else {
+            return Ok(None);
+        };
+
+        let mut malware_families = BTreeSet::new();
+        let mut adversaries = BTreeSet::new();
+        let mut attack_ids = BTreeSet::new();
+        for pulse in &info.pulses {
+            malware_families.extend(pulse.malware_families.iter().map(|m| m.display_name().to_string()));
+            adversaries.extend(pulse.adversary.iter().filter(|a| !a.is_empty()).cloned());
+            attack_ids.extend(pulse.attack_ids.iter().map(|a| a.id().to_string()));
+        }
+
+        let pulses: Vec<Value> = info
+            .pulses
+            .iter()
+            .take(MAX_PULSES)
+            .map(|p| json!({
+                "id": p.id,
+                "name": p.name,
+                "modified": p.modified,
+                "adversary": p.adversary,
+                "tags": p.tags,
+            }))
+            .collect();
+
+        Ok(Some(json!({
+            "pulse_count": info.count,
+            "pulses": pulses,
+            "malware_families": malware_families,
+            "adversaries": adversaries,
+            "attack_ids": attack_ids,
+            "references": info.references,
+            "reputation": general.reputation,
+            // Whitelist hits (Alexa, Majestic, false positive lists)
+            "validation": general
+                .validation
+                .iter()
+                .map(|v| json!({ "source": v.source, "message": v.message }))
+                .collect::<Vec<_>>(),
+        })))
+    }
+}
+
+#[async_trait]
+impl EnrichmentProvider for OtxProvider {
+    fn name(&self) -> &'static str {
+        "alienvault_otx"
+    }
+
+    fn enrichment_type(&self) -> &'static str {
+        "reputation"
+    }
+
+    fn supports(&self, ioc_type: &IocType) -> bool {
+        matches!(
+            ioc_type,
+            IocType::Ip | IocType::Domain | IocType::Url | IocType::Hash | IocType::Cve
+        )
+    }
+
+    async fn enrich(&self, indicator: &Indicator) -> Result<Option<Value>> {
+        match Self::section(indicator) {
+            Some(section) => self.lookup(section, &indicator.value).await,
+            None => Ok(None),
+        }
+    }
+}
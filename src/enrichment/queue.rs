@@ -0,0 +1,137 @@
+//! Durable enrichment queue workers
+//!
+//! Jobs live in the `enrichment_jobs` table, so pending work survives a
+//! restart. Each worker claims the most urgent ready job with
+//! `FOR UPDATE SKIP LOCKED`, runs the engine, and deletes the job on success.
+//! Failed jobs are retried with backoff and dead-lettered after `max_attempts`;
+//! jobs whose worker stopped responding are released and count as failures.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::enrichment::EnrichmentEngine;
+use crate::models::{AuditContext, EnrichMode, EnrichmentJob, EnrichmentJobStatus, EnrichmentPriority};
+use crate::storage::ThreatIntelRepo;
+
+/// How often jobs abandoned by workers are looked for
+const RELEASE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Worker pool settings
+#[derive(Debug, Clone)]
+pub struct QueueConfig {
+    pub workers: usize,
+    pub max_attempts: i32,
+    /// Sleep between polls when the queue is empty
+    pub poll_interval: Duration,
+    /// Running jobs locked for longer are assumed abandoned
+    pub stale_after: chrono::Duration,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            workers: 4,
+            max_attempts: 5,
+            poll_interval: Duration::from_secs(2),
+            stale_after: chrono::Duration::minutes(15),
+        }
+    }
+}
+
+/// Start the worker pool
+pub fn spawn_workers(engine: Arc<EnrichmentEngine>, repo: ThreatIntelRepo, config: QueueConfig) {
+    for worker in 0..config.workers {
+        let engine = engine.clone();
+        let repo = repo.clone();
+        let config = config.clone();
+        tokio::spawn(async move { run_worker(worker, &engine, &repo, &config).await });
+    }
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(RELEASE_INTERVAL).await;
+            match repo.release_stale_enrichment_jobs(config.stale_after, config.max_attempts).await {
+                Ok((0, _)) => {}
+                Ok((released, dead)) => {
+                    tracing::info!(released, dead, "Released stale enrichment jobs");
+                    metrics::counter!("enrichment_jobs_total", "outcome" => "released").increment(released);
+                }
+                Err(e) => tracing::warn!(error = %e, "Failed to release stale enrichment jobs"),
+            }
+        }
+    });
+}
+
+async fn run_worker(worker: usize, engine: &EnrichmentEngine, repo: &ThreatIntelRepo, config: &QueueConfig) {
+    loop {
+        let job = match repo.claim_enrichment_job().await {
+            Ok(Some(job)) => job,
+            Ok(None) => {
+                tokio::time::sleep(config.poll_interval).await;
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!(worker, error = %e, "Failed to claim enrichment job");
+                tokio::time::sleep(config.poll_interval).await;
+                continue;
+            }
+        };
+
+        let outcome = match process(engine, repo, &job).await {
+            Ok(()) => repo.complete_enrichment_job(job.id).await.map(|_| "done"),
+            Err(e) => {
+                let error = format!("{:#}", e);
+                repo.fail_enrichment_job(job.id, &error, config.max_attempts)
+                    .await
+                    .map(|status| match status {
+                        EnrichmentJobStatus::Dead => {
+                            tracing::warn!(job = %job.id, indicator = %job.indicator_id, error, "Enrichment job dead-lettered");
+                            "dead"
+                        }
+                        _ => "retry",
+                    })
+            }
+        };
+
+        match outcome {
+            Ok(outcome) => metrics::counter!("enrichment_jobs_total", "outcome" => outcome).increment(1),
+            Err(e) => tracing::warn!(worker, job = %job.id, error = %e, "Failed to update enrichment job"),
+        }
+    }
+}
+
+/// Run one job; any provider failure fails the job so it is retried
+async fn process(engine: &EnrichmentEngine, repo: &ThreatIntelRepo, job: &EnrichmentJob) -> Result<()> {
+    let Some(indicator) = repo.get_indicator(job.indicator_id).await? else {
+        return Ok(());
+    };
+
+    // Jobs may be requested through the API, so the requester is not trusted
+    let ctx = AuditContext {
+        actor: job.requested_by.clone(),
+        actor_verified: false,
+        ..AuditContext::scheduler("enrichment-queue")
+    };
+    let summary = engine.enrich_and_store(repo, &indicator, job.mode, &ctx).await;
+
+    anyhow::ensure!(summary.errors.is_empty(), summary.errors.join("; "));
+    Ok(())
+}
+
+/// Queue a refresh of up to `limit` indicators whose enrichments from the
+/// engine's providers have expired
+pub async fn enqueue_expired(engine: &EnrichmentEngine, repo: &ThreatIntelRepo, limit: i64) -> Result<usize> {
+    let indicators = repo
+        .get_indicators_with_expired_enrichments(&engine.provider_names(), limit)
+        .await
+        .context("Failed to find expired enrichments")?;
+
+    for indicator in &indicators {
+        repo.enqueue_enrichment(indicator.id, EnrichmentPriority::Refresh, EnrichMode::Expired, "enrichment-refresh")
+            .await?;
+    }
+
+    Ok(indicators.len())
+}
@@ -0,0 +1,828 @@
+//! RDAP enrichment provider
+//!
+//! RDAP returns the registration data WHOIS does, as structured JSON. The
+//! authoritative server for a TLD, address block or AS number comes from the
+//! IANA bootstrap registries: a copy is bundled with the binary, and
+//! [`RdapBootstrap::refresh`] replaces it with the current IANA files.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use governor::Quota;
+use ipnetwork::IpNetwork;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use crate::enrichment::whois::is_privacy_protected;
+use crate::enrichment::{EnrichmentProvider, ProviderHttpError};
+use crate::models::ioc_utils::{indicator_network, registrable_domain};
+use crate::models::{Indicator, IocType, WhoisData};
+
+/// Where [`RdapBootstrap::refresh`] downloads the bootstrap registries from
+pub const IANA_BOOTSTRAP_URL: &str = "https://data.iana.org/rdap/";
+
+/// Redirecting RDAP service used when the bootstrap has no server
+pub const DEFAULT_FALLBACK_URL: &str = "https://rdap.org/";
+
+/// An IANA bootstrap registry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Registry {
+    Dns,
+    Ipv4,
+    Ipv6,
+    Asn,
+}
+
+impl Registry {
+    const ALL: [Registry; 4] = [Registry::Dns, Registry::Ipv4, Registry::Ipv6, Registry::Asn];
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            Registry::Dns => "dns.json",
+            Registry::Ipv4 => "ipv4.json",
+            Registry::Ipv6 => "ipv6.json",
+            Registry::Asn => "asn.json",
+        }
+    }
+
+    fn bundled(&self) -> &'static str {
+        match self {
+            Registry::Dns => include_str!("../../data/rdap/dns.json"),
+            Registry::Ipv4 => include_str!("../../data/rdap/ipv4.json"),
+            Registry::Ipv6 => include_str!("../../data/rdap/ipv6.json"),
+            Registry::Asn => include_str!("../../data/rdap/asn.json"),
+        }
+    }
+}
+
+/// Bootstrap file layout (RFC 9224): `services` is a list of `[[entries], [urls]]`
+#[derive(Debug, Deserialize)]
+struct BootstrapFile {
+    services: Vec<(Vec<String>, Vec<String>)>,
+}
+
+impl BootstrapFile {
+    /// Each entry paired with the service's preferred (HTTPS) base URL
+    fn entries(&self) -> impl Iterator<Item = (&str, String)> {
+        self.services.iter().filter_map(|(entries, urls)| {
+            let url = urls.iter().find(|u| u.starts_with("https://")).or(urls.first())?;
+            let url = if url.ends_with('/') { url.clone() } else { format!("{}/", url) };
+            Some(entries.iter().map(move |entry| (entry.as_str(), url.clone())))
+        })
+        .flatten()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Services {
+    dns: HashMap<String, String>,
+    networks: Vec<(IpNetwork, String)>,
+    asns: Vec<(u32, u32, String)>,
+}
+
+impl Services {
+    fn replace(&mut self, registry: Registry, file: &BootstrapFile) -> Result<()> {
+        match registry {
+            Registry::Dns => {
+                self.dns = file
+                    .entries()
+                    .map(|(tld, url)| (tld.to_lowercase(), url))
+                    .collect();
+            }
+            Registry::Ipv4 | Registry::Ipv6 => {
+                let v4 = registry == Registry::Ipv4;
+                let mut networks = file
+                    .entries()
+                    .map(|(network, url)| {
+                        network
+                            .parse::<IpNetwork>()
+                            .map(|n| (n, url))
+                            .with_context(|| format!("Invalid bootstrap network {}", network))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                self.networks.retain(|(n, _)| n.is_ipv4() != v4);
+                self.networks.append(&mut networks);
+            }
+            Registry::Asn => {
+                self.asns = file
+                    .entries()
+                    .map(|(range, url)| {
+                        let (start, end) = range.split_once('-').unwrap_or((range, range));
+                        Ok((
+                            start.parse().with_context(|| format!("Invalid bootstrap ASN range {}", range))?,
+                            end.parse().with_context(|| format!("Invalid bootstrap ASN range {}", range))?,
+                            url,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// RDAP server lookup from the IANA bootstrap registries
+#[derive(Debug)]
+pub struct RdapBootstrap {
+    services: RwLock<Services>,
+    /// Refreshed files are saved here and preferred over the bundled copy on startup
+    dir: Option<PathBuf>,
+}
+
+impl RdapBootstrap {
+    /// Load bootstrap data from `dir` where present, falling back to the bundled files
+    pub fn load(dir: Option<&Path>) -> Self {
+        let bootstrap = Self {
+            services: RwLock::new(Services::default()),
+            dir: dir.map(Path::to_path_buf),
+        };
+
+        for registry in Registry::ALL {
+            let saved = dir
+                .map(|dir| dir.join(registry.file_name()))
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .filter(|text| bootstrap.replace(registry, text).is_ok());
+
+            if saved.is_none() {
+                bootstrap
+                    .replace(registry, registry.bundled())
+                    .expect("bundled RDAP bootstrap is valid");
+            }
+        }
+
+        bootstrap
+    }
+
+    /// Replace one registry from bootstrap JSON
+    pub fn replace(&self, registry: Registry, text: &str) -> Result<()> {
+        let file: BootstrapFile = serde_json::from_str(text)
+            .with_context(|| format!("Invalid RDAP bootstrap {}", registry.file_name()))?;
+        self.services.write().unwrap().replace(registry, &file)
+    }
+
+    /// Download every registry from `base_url` (normally [`IANA_BOOTSTRAP_URL`]),
+    /// saving them to the bootstrap directory if one is configured
+    pub async fn refresh(&self, client: &Client, base_url: &str) -> Result<()> {
+        for registry in Registry::ALL {
+            let url = format!("{}/{}", base_url.trim_end_matches('/'), registry.file_name());
+            let text = client
+                .get(&url)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .with_context(|| format!("Failed to fetch {}", url))?
+                .text()
+                .await
+                .with_context(|| format!("Failed to read {}", url))?;
+
+            self.replace(registry, &text)?;
+
+            if let Some(dir) = &self.dir {
+                std::fs::create_dir_all(dir)
+                    .and_then(|_| std::fs::write(dir.join(registry.file_name()), &text))
+                    .with_context(|| format!("Failed to save {} to {:?}", registry.file_name(), dir))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Server for the longest matching suffix of a domain
+    fn domain_server(&self, domain: &str) -> Option<String> {
+        let services = self.services.read().unwrap();
+        let labels: Vec<&str> = domain.split('.').collect();
+        (0..labels.len()).find_map(|start| services.dns.get(&labels[start..].join(".")).cloned())
+    }
+
+    /// Server for the most specific block containing a network
+    fn network_server(&self, network: IpNetwork) -> Option<String> {
+        let services = self.services.read().unwrap();
+        services
+            .networks
+            .iter()
+            .filter(|(block, _)| block.contains(network.network()) && block.prefix() <= network.prefix())
+            .max_by_key(|(block, _)| block.prefix())
+            .map(|(_, url)| url.clone())
+    }
+
+    fn asn_server(&self, asn: u32) -> Option<String> {
+        let services = self.services.read().unwrap();
+        services
+            .asns
+            .iter()
+            .find(|(start, end, _)| (*start..=*end).contains(&asn))
+            .map(|(_, _, url)| url.clone())
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RdapObject {
+    handle: Option<String>,
+    name: Option<String>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    country: Option<String>,
+    parent_handle: Option<String>,
+    start_address: Option<String>,
+    end_address: Option<String>,
+    ip_version: Option<String>,
+    start_autnum: Option<u32>,
+    end_autnum: Option<u32>,
+    port43: Option<String>,
+    #[serde(default, rename = "cidr0_cidrs")]
+    cidrs: Vec<RdapCidr>,
+    #[serde(default)]
+    events: Vec<RdapEvent>,
+    #[serde(default)]
+    entities: Vec<RdapEntity>,
+    #[serde(default)]
+    nameservers: Vec<RdapNameserver>,
+    #[serde(default)]
+    status: Vec<String>,
+    /// RFC 9537 redaction markers
+    #[serde(default)]
+    redacted: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RdapEvent {
+    event_action: String,
+    event_date: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RdapEntity {
+    #[serde(default)]
+    roles: Vec<String>,
+    vcard_array: Option<Value>,
+    #[serde(default)]
+    entities: Vec<RdapEntity>,
+}
+
+impl RdapEntity {
+    /// Text value of a jCard property (`fn`, `org`, ...)
+    fn vcard(&self, property: &str) -> Option<String> {
+        self.vcard_property(property)
+            .and_then(|p| p.get(3))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .filter(|v| !v.is_empty())
+    }
+
+    /// Country from the jCard address: the `cc` parameter, else the last address component
+    fn country(&self) -> Option<String> {
+        let adr = self.vcard_property("adr")?;
+        adr.get(1)
+            .and_then(|params| params.get("cc"))
+            .and_then(Value::as_str)
+            .or_else(|| adr.get(3)?.as_array()?.last()?.as_str())
+            .map(str::to_string)
+            .filter(|v| !v.is_empty())
+    }
+
+    fn vcard_property(&self, property: &str) -> Option<&Vec<Value>> {
+        self.vcard_array
+            .as_ref()?
+            .get(1)?
+            .as_array()?
+            .iter()
+            .filter_map(Value::as_array)
+            .find(|p| p.first().and_then(Value::as_str) == Some(property))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RdapNameserver {
+    ldh_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RdapCidr {
+    v4prefix: Option<String>,
+    v6prefix: Option<String>,
+    length: u8,
+}
+
+/// First entity with `role`, searching nested entities too
+fn find_entity<'a>(entities: &'a [RdapEntity], role: &str) -> Option<&'a RdapEntity> {
+    entities.iter().find(|e| e.roles.iter().any(|r| r == role)).or_else(|| {
+        entities.iter().find_map(|e| find_entity(&e.entities, role))
+    })
+}
+
+impl RdapObject {
+    fn event(&self, action: &str) -> Option<DateTime<Utc>> {
+        self.events
+            .iter()
+            .find(|e| e.event_action.eq_ignore_ascii_case(action))
+            .map(|e| e.event_date)
+    }
+
+    fn to_whois_data(&self) -> WhoisData {
+        let registrar = find_entity(&self.entities, "registrar");
+        let registrant = find_entity(&self.entities, "registrant");
+
+        let mut data = WhoisData {
+            registrar: registrar.and_then(|e| e.vcard("fn")),
+            registrant: registrant.and_then(|e| e.vcard("fn")),
+            registrant_org: registrant.and_then(|e| e.vcard("org")),
+            registrant_country: registrant.and_then(RdapEntity::country).or_else(|| self.country.clone()),
+            creation_date: self.event("registration"),
+            expiration_date: self.event("expiration"),
+            updated_date: self.event("last changed"),
+            name_servers: self
+                .nameservers
+                .iter()
+                .filter_map(|ns| ns.ldh_name.as_deref())
+                .map(|ns| ns.trim_end_matches('.').to_lowercase())
+                .collect(),
+            status: self.status.clone(),
+            whois_server: self.port43.clone(),
+            ..Default::default()
+        };
+        data.privacy_protected = is_privacy_protected(&data) || !self.redacted.is_empty();
+        data
+    }
+
+    /// Allocated blocks from the cidr0 extension
+    fn cidrs(&self) -> Vec<String> {
+        self.cidrs
+            .iter()
+            .filter_map(|c| {
+                let prefix = c.v4prefix.as_deref().or(c.v6prefix.as_deref())?;
+                Some(format!("{}/{}", prefix, c.length))
+            })
+            .collect()
+    }
+}
+
+/// WHOIS-compatible fields shared by every RDAP result
+fn whois_fields(data: &WhoisData, server: &str) -> Map<String, Value> {
+    let value = json!({
+        "registrar": data.registrar,
+        "registrant": data.registrant,
+        "registrant_org": data.registrant_org,
+        "registrant_country": data.registrant_country,
+        "creation_date": data.creation_date,
+        "expiration_date": data.expiration_date,
+        "updated_date": data.updated_date,
+        "domain_age_days": data.domain_age_days(),
+        "privacy_protected": data.privacy_protected,
+        "name_servers": data.name_servers,
+        "status": data.status,
+        "whois_server": data.whois_server,
+        "rdap_server": server,
+    });
+    match value {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    }
+}
+
+/// RDAP enrichment provider for domains, IP networks and ASNs
+pub struct RdapProvider {
+    client: Client,
+    bootstrap: Arc<RdapBootstrap>,
+    fallback: Option<String>,
+}
+
+impl RdapProvider {
+    /// Create a new RDAP provider
+    pub fn new(bootstrap: Arc<RdapBootstrap>) -> Self {
+        let client = Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            client,
+            bootstrap,
+            fallback: Some(DEFAULT_FALLBACK_URL.to_string()),
+        }
+    }
+
+    /// Server used when the bootstrap has no entry; `None` skips such lookups
+    pub fn with_fallback(mut self, fallback: Option<String>) -> Self {
+        self.fallback = fallback
+            .filter(|url| !url.is_empty())
+            .map(|url| if url.ends_with('/') { url } else { format!("{}/", url) });
+        self
+    }
+
+    /// Registration data for a domain
+    pub async fn lookup_domain(&self, domain: &str) -> Result<Option<Value>> {
+        let domain = registrable_domain(domain).unwrap_or_else(|| domain.to_string());
+        let Some(server) = self.bootstrap.domain_server(&domain).or_else(|| self.fallback.clone()) else {
+            return Ok(None);
+        };
+
+        let Some(object) = self.fetch(&server, &format!("domain/{}", domain)).await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(Value::Object(whois_fields(&object.to_whois_data(), &server))))
+    }
+
+    /// Allocation details for an address or block
+    pub async fn lookup_network(&self, network: IpNetwork) -> Result<Option<Value>> {
+        let Some(server) = self.bootstrap.network_server(network).or_else(|| self.fallback.clone()) else {
+            return Ok(None);
+        };
+
+        let host_prefix = if network.is_ipv4() { 32 } else { 128 };
+        let path = if network.prefix() == host_prefix {
+            format!("ip/{}", network.ip())
+        } else {
+            format!("ip/{}/{}", network.network(), network.prefix())
+        };
+
+        let Some(object) = self.fetch(&server, &path).await? else {
+            return Ok(None);
+        };
+
+        let mut result = whois_fields(&object.to_whois_data(), &server);
+        result.insert(
+            "network".to_string(),
+            json!({
+                "handle": object.handle,
+                "name": object.name,
+                "type": object.kind,
+                "start_address": object.start_address,
+                "end_address": object.end_address,
+                "ip_version": object.ip_version,
+                "cidrs": object.cidrs(),
+                "country": object.country,
+                "parent_handle": object.parent_handle,
+            }),
+        );
+        Ok(Some(Value::Object(result)))
+    }
+
+    /// Allocation details for an AS number
+    pub async fn lookup_asn(&self, asn: u32) -> Result<Option<Value>> {
+        let Some(server) = self.bootstrap.asn_server(asn).or_else(|| self.fallback.clone()) else {
+            return Ok(None);
+        };
+
+        let Some(object) = self.fetch(&server, &format!("autnum/{}", asn)).await? else {
+            return Ok(None);
+        };
+
+        let mut result = whois_fields(&object.to_whois_data(), &server);
+        result.insert(
+            "autnum".to_string(),
+            json!({
+                "handle": object.handle,
+                "name": object.name,
+                "type": object.kind,
+                "start_autnum": object.start_autnum,
+                "end_autnum": object.end_autnum,
+                "country": object.country,
+            }),
+        );
+        Ok(Some(Value::Object(result)))
+    }
+
+    async fn fetch(&self, server: &str, path: &str) -> Result<Option<RdapObject>> {
+        let url = format!("{}{}", server, path);
+        let response = self.client
+            .get(&url)
+            .header("Accept", "application/rdap+json")
+            .send()
+            .await
+            .with_context(|| format!("Failed to send request to {}", url))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            return Err(ProviderHttpError::from_response("RDAP", response).await.into());
+        }
+
+        let object = response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse RDAP response from {}", url))?;
+
+        Ok(Some(object))
+    }
+}
+
+#[async_trait]
+impl EnrichmentProvider for RdapProvider {
+    fn name(&self) -> &'static str {
+        "rdap"
+    }
+
+    fn enrichment_type(&self) -> &'static str {
+        "rdap"
+    }
+
+    fn supports(&self, ioc_type: &IocType) -> bool {
+        matches!(ioc_type, IocType::Domain | IocType::Ip | IocType::Cidr | IocType::Asn)
+    }
+
+    async fn enrich(&self, indicator: &Indicator) -> Result<Option<Value>> {
+        match indicator.ioc_type {
+            IocType::Domain => self.lookup_domain(&indicator.value).await,
+            IocType::Ip | IocType::Cidr => match indicator_network(&indicator.value, &indicator.ioc_type) {
+                Some(network) => self.lookup_network(network).await,
+                None => Ok(None),
+            },
+            IocType::Asn => match indicator.value.trim_start_matches("AS").parse() {
+                Ok(asn) => self.lookup_asn(asn).await,
+                Err(_) => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn ttl_hours(&self) -> i64 {
+        168 // 1 week - registration data changes infrequently
+    }
+
+    fn rate_limit(&self) -> Option<Quota> {
+        // RIRs and rdap.org (10 requests per 10 seconds) throttle bulk clients
+        Some(Quota::per_minute(NonZeroU32::new(60).unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn bootstrap_for(server: &MockServer) -> Arc<RdapBootstrap> {
+        let bootstrap = RdapBootstrap::load(None);
+        let registry = format!("{}/registry/", server.uri());
+        let rir = format!("{}/rir/", server.uri());
+
+        bootstrap
+            .replace(Registry::Dns, &json!({ "services": [[["com"], [registry]]] }).to_string())
+            .unwrap();
+        bootstrap
+            .replace(
+                Registry::Ipv4,
+                &json!({ "services": [
+                    [["198.0.0.0/8"], [format!("{}/wrong/", server.uri())]],
+                    [["198.51.0.0/16"], [rir.clone()]]
+                ] })
+                .to_string(),
+            )
+            .unwrap();
+        bootstrap
+            .replace(Registry::Asn, &json!({ "services": [[["64496-64511"], [rir]]] }).to_string())
+            .unwrap();
+        Arc::new(bootstrap)
+    }
+
+    fn provider_for(server: &MockServer) -> RdapProvider {
+        RdapProvider::new(bootstrap_for(server)).with_fallback(None)
+    }
+
+    fn indicator(ioc_type: IocType, value: &str) -> Indicator {
+        Indicator {
+            id: uuid::Uuid::new_v4(),
+            ioc_type,
+            value: value.to_string(),
+            severity: crate::models::Severity::Medium,
+            confidence: 50,
+            threat_score: 50,
+            tlp: crate::models::Tlp::Amber,
+            first_seen: Utc::now(),
+            last_seen: Utc::now(),
+            expiration: None,
+            tags: vec![],
+            source_ids: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            hash_algorithm: None,
+            file_object_id: None,
+            network: None,
+            registrable_domain: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn domain_lookup_maps_registration_data() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/registry/domain/example.com"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "objectClassName": "domain",
+                "ldhName": "EXAMPLE.COM",
+                "status": ["client transfer prohibited"],
+                "port43": "whois.example-registrar.test",
+                "events": [
+                    { "eventAction": "registration", "eventDate": "1995-08-14T04:00:00Z" },
+                    { "eventAction": "expiration", "eventDate": "2030-08-13T04:00:00Z" },
+                    { "eventAction": "last changed", "eventDate": "2024-08-14T07:01:34Z" }
+                ],
+                "entities": [
+                    {
+                        "roles": ["registrar"],
+                        "vcardArray": ["vcard", [["version", {}, "text", "4.0"], ["fn", {}, "text", "Example Registrar, Inc."]]]
+                    },
+                    {
+                        "roles": ["registrant"],
+                        "vcardArray": ["vcard", [
+                            ["fn", {}, "text", "REDACTED FOR PRIVACY"],
+                            ["adr", { "cc": "US" }, "text", ["", "", "", "", "", "", ""]]
+                        ]]
+                    }
+                ],
+                "nameservers": [{ "ldhName": "A.IANA-SERVERS.NET" }, { "ldhName": "B.IANA-SERVERS.NET." }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let result = provider_for(&server)
+            .enrich(&indicator(IocType::Domain, "www.example.com"))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(result["registrar"], "Example Registrar, Inc.");
+        assert_eq!(result["registrant_country"], "US");
+        assert_eq!(result["creation_date"], "1995-08-14T04:00:00Z");
+        assert_eq!(result["expiration_date"], "2030-08-13T04:00:00Z");
+        assert_eq!(result["name_servers"], json!(["a.iana-servers.net", "b.iana-servers.net"]));
+        assert_eq!(result["privacy_protected"], true);
+        assert_eq!(result["whois_server"], "whois.example-registrar.test");
+        assert!(result["domain_age_days"].as_i64().unwrap() > 10_000);
+    }
+
+    #[tokio::test]
+    async fn ip_lookup_uses_most_specific_block_and_maps_allocation() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/rir/ip/198.51.100.7"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "objectClassName": "ip network",
+                "handle": "NET-198-51-100-0-1",
+                "name": "TEST-NET-2",
+                "type": "ASSIGNED",
+                "startAddress": "198.51.100.0",
+                "endAddress": "198.51.100.255",
+                "ipVersion": "v4",
+                "country": "NL",
+                "parentHandle": "NET-198-0-0-0-0",
+                "cidr0_cidrs": [{ "v4prefix": "198.51.100.0", "length": 24 }],
+                "entities": [{
+                    "roles": ["registrant"],
+                    "vcardArray": ["vcard", [["fn", {}, "text", "Example Hosting"], ["org", {}, "text", "Example Hosting B.V."]]]
+                }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let result = provider_for(&server)
+            .enrich(&indicator(IocType::Ip, "198.51.100.7"))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(result["network"]["handle"], "NET-198-51-100-0-1");
+        assert_eq!(result["network"]["cidrs"], json!(["198.51.100.0/24"]));
+        assert_eq!(result["network"]["country"], "NL");
+        assert_eq!(result["registrant_org"], "Example Hosting B.V.");
+        assert_eq!(result["registrant_country"], "NL");
+        assert_eq!(result["privacy_protected"], false);
+    }
+
+    #[tokio::test]
+    async fn cidr_and_asn_lookups_use_their_rdap_paths() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/rir/ip/198.51.100.0/24"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "handle": "NET-1", "startAddress": "198.51.100.0" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/rir/autnum/64500"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "handle": "AS64500",
+                "name": "EXAMPLE-AS",
+                "startAutnum": 64500,
+                "endAutnum": 64500
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = provider_for(&server);
+        let network = provider.enrich(&indicator(IocType::Cidr, "198.51.100.0/24")).await.unwrap().unwrap();
+        assert_eq!(network["network"]["handle"], "NET-1");
+
+        let asn = provider.enrich(&indicator(IocType::Asn, "AS64500")).await.unwrap().unwrap();
+        assert_eq!(asn["autnum"]["name"], "EXAMPLE-AS");
+        assert_eq!(asn["autnum"]["start_autnum"], 64500);
+    }
+
+    #[tokio::test]
+    async fn unknown_objects_and_unbootstrapped_tlds_return_nothing() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/registry/domain/missing.com"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let provider = provider_for(&server);
+        assert!(provider.enrich(&indicator(IocType::Domain, "missing.com")).await.unwrap().is_none());
+        assert!(provider.enrich(&indicator(IocType::Domain, "example.invalidtld")).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn fallback_server_is_used_without_bootstrap_entry() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/fallback/domain/example.org"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "events": [{ "eventAction": "registration", "eventDate": "1985-01-01T05:00:00Z" }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = RdapProvider::new(Arc::new(RdapBootstrap::load(None)))
+            .with_fallback(Some(format!("{}/fallback", server.uri())));
+        provider.bootstrap.replace(Registry::Dns, r#"{"services": []}"#).unwrap();
+
+        let result = provider.enrich(&indicator(IocType::Domain, "example.org")).await.unwrap().unwrap();
+        assert_eq!(result["creation_date"], "1985-01-01T05:00:00Z");
+    }
+
+    #[tokio::test]
+    async fn server_errors_surface_as_retryable_http_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/registry/domain/example.com"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let error = provider_for(&server)
+            .enrich(&indicator(IocType::Domain, "example.com"))
+            .await
+            .unwrap_err();
+        let http = error.downcast_ref::<ProviderHttpError>().unwrap();
+        assert_eq!(http.status, reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn refresh_replaces_bootstrap_from_iana() {
+        let server = MockServer::start().await;
+        let services = [
+            ("dns.json", json!({ "services": [[["test"], ["https://rdap.nic.test/"]]] })),
+            ("ipv4.json", json!({ "services": [[["203.0.113.0/24"], ["https://rdap.rir.test"]]] })),
+            ("ipv6.json", json!({ "services": [] })),
+            ("asn.json", json!({ "services": [[["64512-65534"], ["http://rdap.rir.test/", "https://rdap.rir.test/"]]] })),
+        ];
+        for (file, body) in services {
+            Mock::given(method("GET"))
+                .and(path(format!("/rdap/{}", file)))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .mount(&server)
+                .await;
+        }
+
+        let dir = std::env::temp_dir().join(format!("rdap-bootstrap-{}", uuid::Uuid::new_v4()));
+        let bootstrap = RdapBootstrap::load(Some(&dir));
+        assert_eq!(bootstrap.domain_server("example.com").as_deref(), Some("https://rdap.verisign.com/com/v1/"));
+        assert_eq!(bootstrap.asn_server(3333).as_deref(), Some("https://rdap.db.ripe.net/"));
+        assert_eq!(bootstrap.asn_server(15169).as_deref(), Some("https://rdap.arin.net/registry/"));
+        assert_eq!(bootstrap.asn_server(64600), None);
+
+        bootstrap
+            .refresh(&Client::new(), &format!("{}/rdap/", server.uri()))
+            .await
+            .unwrap();
+
+        assert_eq!(bootstrap.domain_server("example.com"), None);
+        assert_eq!(bootstrap.domain_server("sub.example.test").as_deref(), Some("https://rdap.nic.test/"));
+        assert_eq!(
+            bootstrap.network_server("203.0.113.9/32".parse().unwrap()).as_deref(),
+            Some("https://rdap.rir.test/")
+        );
+        assert_eq!(bootstrap.asn_server(64600).as_deref(), Some("https://rdap.rir.test/"));
+
+        // Saved copies are preferred over the bundled files on the next start
+        let reloaded = RdapBootstrap::load(Some(&dir));
+        assert_eq!(reloaded.domain_server("example.test").as_deref(), Some("https://rdap.nic.test/"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
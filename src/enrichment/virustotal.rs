@@ -2,15 +2,20 @@
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
+use governor::Quota;
 use reqwest::Client;
+use std::num::NonZeroU32;
 use serde::Deserialize;
 use serde_json::{json, Value};
 
-use crate::enrichment::EnrichmentProvider;
-use crate::models::{Indicator, IocType};
+use crate::enrichment::{EnrichmentProvider, ProviderHttpError};
+use crate::models::{HashAlgorithm, Indicator, IocType};
 
 const VT_API_URL: &str = "https://www.virustotal.com/api/v3";
 
+/// Public API allowance
+const VT_FREE_REQUESTS_PER_MINUTE: u32 = 4;
+
 /// VirusTotal analysis stats
 #[derive(Debug, Deserialize)]
 struct VtAnalysisStats {
@@ -41,6 +46,18 @@ struct VtAttributes {
     meaningful_name: Option<String>,
     type_description: Option<String>,
     size: Option<i64>,
+    md5: Option<String>,
+    sha1: Option<String>,
+    sha256: Option<String>,
+    ssdeep: Option<String>,
+    tlsh: Option<String>,
+    names: Option<Vec<String>>,
+    pe_info: Option<VtPeInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VtPeInfo {
+    imphash: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -63,6 +80,7 @@ struct VtResponse {
 pub struct VirusTotalProvider {
     client: Client,
     api_key: String,
+    requests_per_minute: u32,
 }
 
 impl VirusTotalProvider {
@@ -73,7 +91,13 @@ impl VirusTotalProvider {
             .build()
             .expect("Failed to create HTTP client");
 
-        Self { client, api_key }
+        Self { client, api_key, requests_per_minute: VT_FREE_REQUESTS_PER_MINUTE }
+    }
+
+    /// Raise the request budget for premium API keys
+    pub fn with_requests_per_minute(mut self, requests_per_minute: u32) -> Self {
+        self.requests_per_minute = requests_per_minute;
+        self
     }
 
     /// Check an IP address
@@ -111,9 +135,7 @@ impl VirusTotalProvider {
         }
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("VirusTotal API error: {} - {}", status, body);
+            return Err(ProviderHttpError::from_response("VirusTotal", response).await.into());
         }
 
         let data: VtResponse = response
@@ -176,6 +198,21 @@ impl VirusTotalProvider {
             result["size"] = json!(size);
         }
 
+        // Every hash of the file, so MD5/SHA1/SHA256 indicators can be linked
+        if attrs.md5.is_some() || attrs.sha1.is_some() || attrs.sha256.is_some() {
+            result["file"] = json!({
+                "md5": attrs.md5,
+                "sha1": attrs.sha1,
+                "sha256": attrs.sha256,
+                "ssdeep": attrs.ssdeep,
+                "tlsh": attrs.tlsh,
+                "imphash": attrs.pe_info.as_ref().and_then(|p| p.imphash.clone()),
+                "size": attrs.size,
+                "file_type": attrs.type_description,
+                "names": attrs.names.clone().unwrap_or_default(),
+            });
+        }
+
         Ok(result)
     }
 }
@@ -201,7 +238,13 @@ impl EnrichmentProvider for VirusTotalProvider {
         let result = match indicator.ioc_type {
             IocType::Ip => self.check_ip(&indicator.value).await?,
             IocType::Domain => self.check_domain(&indicator.value).await?,
-            IocType::Hash => self.check_hash(&indicator.value).await?,
+            // The files endpoint only resolves cryptographic digests
+            IocType::Hash => match indicator.hash_algorithm {
+                None | Some(HashAlgorithm::Md5 | HashAlgorithm::Sha1 | HashAlgorithm::Sha256) => {
+                    self.check_hash(&indicator.value).await?
+                }
+                _ => return Ok(None),
+            },
             IocType::Url => self.check_url(&indicator.value).await?,
             _ => return Ok(None),
         };
@@ -217,4 +260,8 @@ impl EnrichmentProvider for VirusTotalProvider {
     fn ttl_hours(&self) -> i64 {
         12 // Check reputation frequently
     }
+
+    fn rate_limit(&self) -> Option<Quota> {
+        NonZeroU32::new(self.requests_per_minute).map(Quota::per_minute)
+    }
 }
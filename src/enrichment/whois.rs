@@ -2,42 +2,178 @@
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use governor::Quota;
 use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
 
 use crate::enrichment::EnrichmentProvider;
+use crate::models::ioc_utils::registrable_domain;
 use crate::models::{Indicator, IocType, WhoisData};
 
+/// TLD to WHOIS server map shipped with the binary
+const BUNDLED_SERVERS: &str = include_str!("../../data/whois_servers.json");
+
+/// Asked for the WHOIS server of TLDs missing from the server map
+const IANA_WHOIS_SERVER: &str = "whois.iana.org";
+
+const WHOIS_PORT: u16 = 43;
+
+/// Responses are truncated beyond this size
+const MAX_RESPONSE_BYTES: u64 = 1 << 20;
+
+/// Registry to registrar hops followed per lookup
+const MAX_REFERRALS: usize = 2;
+
+/// Markers of privacy/proxy services and GDPR redaction in contact fields.
+/// Kept specific so registrants that merely mention privacy or a proxy
+/// (e.g. "Privacy International", "Proxy Networks Inc") are not flagged.
+const PRIVACY_MARKERS: &[&str] = &[
+    "redacted for privacy",
+    "redacted for gdpr",
+    "withheld for privacy",
+    "data protected",
+    "not disclosed",
+    "statutory masking",
+    "whoisguard",
+    "domains by proxy",
+    "contact privacy",
+    "privacy service",
+    "privacy protect",
+    "privacyguardian",
+    "identity protect",
+    "private registration",
+    "perfect privacy",
+    "whoisprotect",
+    "gdpr masked",
+];
+
 /// WHOIS enrichment provider
 pub struct WhoisProvider {
-    // Could add configuration here for custom WHOIS servers
+    /// Configured servers keyed by TLD or public suffix (e.g. `co.uk`)
+    servers: HashMap<String, String>,
+    /// Servers learned from IANA
+    discovered: Mutex<HashMap<String, String>>,
+    timeout: Duration,
 }
 
 impl WhoisProvider {
-    /// Create a new WHOIS provider
+    /// Create a new WHOIS provider using the bundled server map
     pub fn new() -> Self {
-        Self {}
+        let servers = serde_json::from_str(BUNDLED_SERVERS).expect("bundled WHOIS server map is valid");
+
+        Self {
+            servers,
+            discovered: Mutex::new(HashMap::new()),
+            timeout: Duration::from_secs(10),
+        }
     }
 
-    /// Perform WHOIS lookup for a domain
+    /// Create a provider whose server map is extended by a JSON file of `{"tld": "server"}`
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read WHOIS server map {:?}", path))?;
+        let overrides: HashMap<String, String> =
+            serde_json::from_str(&text).context("Invalid WHOIS server map")?;
+
+        let mut provider = Self::new();
+        for (tld, server) in overrides {
+            let host = whois_host(&server)
+                .with_context(|| format!("Invalid WHOIS server {:?} for {}: only port {} is used", server, tld, WHOIS_PORT))?;
+            provider.servers.insert(tld, host);
+        }
+        Ok(provider)
+    }
+
+    /// Socket timeout for each query
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Perform WHOIS lookup for a domain, following registry referrals to the registrar
     pub async fn lookup(&self, domain: &str) -> Result<WhoisData> {
-        // Use whois-rust crate for synchronous lookup
-        // Wrap in spawn_blocking for async compatibility
-        let domain = domain.to_string();
-        
-        let result = tokio::task::spawn_blocking(move || {
-            whois_rust::WhoIs::from_path("./data/servers.json")
-                .or_else(|_| whois_rust::WhoIs::from_string(include_str!("../../data/whois_servers.json")))
-                .ok()
-                .and_then(|whois| whois.lookup(whois_rust::WhoIsLookupOptions::from_string(&domain).ok()?).ok())
-        })
-        .await
-        .context("WHOIS lookup task failed")?;
+        let domain = registrable_domain(domain).unwrap_or_else(|| domain.to_string());
+        let mut server = self.server_for(&domain).await?;
+        let mut data: Option<WhoisData> = None;
+
+        for _ in 0..=MAX_REFERRALS {
+            let raw = self.query(&server, &domain).await?;
+            let referral = find_referral(&raw).filter(|next| !next.eq_ignore_ascii_case(&server));
 
-        let raw = result.unwrap_or_default();
-        let data = parse_whois_response(&raw);
-        
+            let mut parsed = parse_whois_response(&raw);
+            parsed.whois_server = Some(server.clone());
+            data = Some(match data {
+                // The registrar's answer is more specific; the registry fills the gaps
+                Some(registry) => merge_whois(parsed, registry),
+                None => parsed,
+            });
+
+            match referral {
+                Some(next) => server = next,
+                None => break,
+            }
+        }
+
+        let mut data = data.unwrap_or_default();
+        data.privacy_protected = is_privacy_protected(&data);
         Ok(data)
     }
+
+    /// WHOIS server for a domain: the longest configured suffix, else IANA's referral for the TLD
+    async fn server_for(&self, domain: &str) -> Result<String> {
+        let labels: Vec<&str> = domain.split('.').collect();
+        for start in 1..labels.len() {
+            if let Some(server) = self.servers.get(&labels[start..].join(".")) {
+                return Ok(server.clone());
+            }
+        }
+
+        let tld = labels.last().copied().unwrap_or(domain);
+        if let Some(server) = self.discovered.lock().unwrap().get(tld) {
+            return Ok(server.clone());
+        }
+
+        let raw = self.query(IANA_WHOIS_SERVER, tld).await?;
+        let server = raw
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(key, _)| matches!(key.trim().to_lowercase().as_str(), "refer" | "whois"))
+            .and_then(|(_, value)| whois_host(value))
+            .with_context(|| format!("No WHOIS server known for .{}", tld))?;
+
+        self.discovered.lock().unwrap().insert(tld.to_string(), server.clone());
+        Ok(server)
+    }
+
+    /// Send a single query to a server over port 43
+    async fn query(&self, server: &str, query: &str) -> Result<String> {
+        let addr = format!("{}:{}", server, WHOIS_PORT);
+
+        let mut stream = tokio::time::timeout(self.timeout, TcpStream::connect(&addr))
+            .await
+            .with_context(|| format!("Timed out connecting to {}", addr))?
+            .with_context(|| format!("Failed to connect to {}", addr))?;
+
+        stream
+            .write_all(format!("{}\r\n", query).as_bytes())
+            .await
+            .with_context(|| format!("Failed to query {}", addr))?;
+
+        let mut response = Vec::new();
+        tokio::time::timeout(self.timeout, (&mut stream).take(MAX_RESPONSE_BYTES).read_to_end(&mut response))
+            .await
+            .with_context(|| format!("Timed out reading from {}", addr))?
+            .with_context(|| format!("Failed to read from {}", addr))?;
+
+        Ok(String::from_utf8_lossy(&response).into_owned())
+    }
 }
 
 impl Default for WhoisProvider {
@@ -46,63 +182,214 @@ impl Default for WhoisProvider {
     }
 }
 
-/// Parse raw WHOIS response into structured data
+/// Registrar server a registry response refers to
+fn find_referral(raw: &str) -> Option<String> {
+    raw.lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| {
+            matches!(
+                key.trim().to_lowercase().as_str(),
+                "registrar whois server" | "whois server" | "referralserver"
+            )
+        })
+        .and_then(|(_, value)| whois_host(value))
+}
+
+/// Host name of a WHOIS server given as `host`, `host:43` or `whois://host/`.
+/// Any other port is refused so a response cannot point us at arbitrary services.
+fn whois_host(value: &str) -> Option<String> {
+    let value = value.trim();
+    let value = value.split_once("://").map_or(value, |(_, rest)| rest);
+    let value = value.trim_end_matches('/').to_lowercase();
+
+    let host = match value.rsplit_once(':') {
+        Some((host, port)) if port.parse::<u16>() == Ok(WHOIS_PORT) => host.to_string(),
+        Some(_) => return None,
+        None => value,
+    };
+
+    (!host.is_empty() && !host.contains(|c: char| c.is_whitespace() || c == '/')).then_some(host)
+}
+
+/// Fill gaps in `primary` from `fallback`
+fn merge_whois(mut primary: WhoisData, fallback: WhoisData) -> WhoisData {
+    primary.registrar = primary.registrar.or(fallback.registrar);
+    primary.registrant = primary.registrant.or(fallback.registrant);
+    primary.registrant_org = primary.registrant_org.or(fallback.registrant_org);
+    primary.registrant_country = primary.registrant_country.or(fallback.registrant_country);
+    primary.creation_date = primary.creation_date.or(fallback.creation_date);
+    primary.expiration_date = primary.expiration_date.or(fallback.expiration_date);
+    primary.updated_date = primary.updated_date.or(fallback.updated_date);
+    if primary.name_servers.is_empty() {
+        primary.name_servers = fallback.name_servers;
+    }
+    if primary.status.is_empty() {
+        primary.status = fallback.status;
+    }
+    primary.raw = match (fallback.raw, primary.raw) {
+        (Some(registry), Some(registrar)) => Some(format!("{}\n\n{}", registry, registrar)),
+        (registry, registrar) => registrar.or(registry),
+    };
+    primary
+}
+
+/// Whether the registrant is hidden behind a privacy service or redacted
+pub fn is_privacy_protected(data: &WhoisData) -> bool {
+    [&data.registrant, &data.registrant_org]
+        .into_iter()
+        .flatten()
+        .any(|value| {
+            let value = value.to_lowercase();
+            PRIVACY_MARKERS.iter().any(|marker| value.contains(marker))
+        })
+}
+
+/// Parse the many date formats registries use, e.g. `2024-01-02T03:04:05Z`,
+/// `2024-01-02 03:04:05 UTC`, `02-Jan-2024`, `2024.01.02` or `02.01.2024`
+pub fn parse_whois_date(value: &str) -> Option<DateTime<Utc>> {
+    const DATETIME_FORMATS: &[&str] = &[
+        "%Y-%m-%dT%H:%M:%S%.f%z",
+        "%Y-%m-%d %H:%M:%S%.f%z",
+        "%Y-%m-%d %H:%M:%S %z",
+        "%d-%b-%Y %H:%M:%S %z",
+    ];
+    const NAIVE_DATETIME_FORMATS: &[&str] = &[
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y.%m.%d %H:%M:%S",
+        "%Y/%m/%d %H:%M:%S",
+        "%d.%m.%Y %H:%M:%S",
+        "%d-%b-%Y %H:%M:%S",
+        "%a %b %d %H:%M:%S %Y",
+    ];
+    const DATE_FORMATS: &[&str] = &[
+        "%Y-%m-%d", "%Y.%m.%d", "%Y/%m/%d", "%d.%m.%Y", "%d-%b-%Y", "%d %b %Y", "%b %d %Y", "%Y%m%d",
+    ];
+
+    // Drop annotations such as "(YYYY-MM-DD)" and the Korean "2024. 01. 02." spacing
+    let value = value.split(" (").next().unwrap_or(value).trim().replace(". ", ".");
+    let value = value.trim_end_matches('.');
+    if value.is_empty() {
+        return None;
+    }
+
+    let parse = |value: &str| -> Option<DateTime<Utc>> {
+        if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+            return Some(dt.with_timezone(&Utc));
+        }
+        for format in DATETIME_FORMATS {
+            if let Ok(dt) = DateTime::parse_from_str(value, format) {
+                return Some(dt.with_timezone(&Utc));
+            }
+        }
+        for format in NAIVE_DATETIME_FORMATS {
+            if let Ok(dt) = NaiveDateTime::parse_from_str(value, format) {
+                return Some(dt.and_utc());
+            }
+        }
+        for format in DATE_FORMATS {
+            if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+                return date.and_hms_opt(0, 0, 0).map(|dt| dt.and_utc());
+            }
+        }
+        None
+    };
+
+    parse(value).or_else(|| {
+        // Trailing zone names (UTC, GMT, CLST, ...) are not understood by chrono
+        let (rest, zone) = value.rsplit_once(' ')?;
+        zone.chars().all(|c| c.is_ascii_alphabetic()).then(|| parse(rest.trim())).flatten()
+    })
+}
+
+/// Parse raw WHOIS response into structured data.
+///
+/// Handles `key: value` lines as well as the block layout of registries such
+/// as Nominet, where the value follows an otherwise empty `key:` line.
 fn parse_whois_response(raw: &str) -> WhoisData {
     let mut data = WhoisData {
         raw: Some(raw.to_string()),
         ..Default::default()
     };
 
+    let mut block_key: Option<String> = None;
+
     for line in raw.lines() {
+        let indented = line.starts_with(char::is_whitespace);
         let line = line.trim();
-        if line.is_empty() || line.starts_with('%') || line.starts_with('#') {
+        if line.is_empty() {
+            block_key = None;
             continue;
         }
+        if line.starts_with('%') || line.starts_with('#') {
+            continue;
+        }
+        // Registry notices follow the record
+        if line.starts_with(">>>") {
+            break;
+        }
 
-        if let Some((key, value)) = line.split_once(':') {
-            let key = key.trim().to_lowercase();
-            let value = value.trim();
-
-            if value.is_empty() {
-                continue;
-            }
+        let pair = line
+            .split_once(':')
+            .map(|(key, value)| (key.trim().to_lowercase(), value.trim()))
+            .filter(|(key, _)| !key.is_empty() && !key.contains("http"));
 
-            match key.as_str() {
-                "registrar" | "registrar name" => {
-                    if data.registrar.is_none() {
-                        data.registrar = Some(value.to_string());
-                    }
-                }
-                "registrant" | "registrant name" => {
-                    data.registrant = Some(value.to_string());
-                }
-                "registrant organization" | "registrant org" => {
-                    data.registrant_org = Some(value.to_string());
-                }
-                "registrant country" => {
-                    data.registrant_country = Some(value.to_string());
-                }
-                "creation date" | "created" | "created date" | "registration date" => {
-                    // Parse date - simplified, just store as string for now
-                    // In production, use chrono to parse various date formats
-                }
-                "expiration date" | "expires" | "expiry date" | "registry expiry date" => {
-                    // Parse date
-                }
-                "name server" | "nserver" => {
-                    data.name_servers.push(value.to_lowercase());
-                }
-                "status" | "domain status" => {
-                    data.status.push(value.to_string());
-                }
+        match pair {
+            Some((key, "")) => block_key = Some(key),
+            Some((key, value)) => apply_whois_field(&mut data, &key, value),
+            None => match &block_key {
+                Some(key) if indented => apply_whois_field(&mut data, key, line),
                 _ => {}
-            }
+            },
         }
     }
 
     data
 }
 
+fn apply_whois_field(data: &mut WhoisData, key: &str, value: &str) {
+    match key {
+        "registrar" | "registrar name" | "sponsoring registrar" => {
+            data.registrar.get_or_insert_with(|| value.to_string());
+        }
+        "registrant" | "registrant name" | "registrant's name" => {
+            data.registrant.get_or_insert_with(|| value.to_string());
+        }
+        "registrant organization" | "registrant org" | "registrant organisation" => {
+            data.registrant_org.get_or_insert_with(|| value.to_string());
+        }
+        "registrant country" | "registrant country code" => {
+            data.registrant_country.get_or_insert_with(|| value.to_string());
+        }
+        "creation date" | "created" | "created date" | "created on" | "registration date"
+        | "registration time" | "registered on" | "registered" | "domain registration date" => {
+            data.creation_date = data.creation_date.or_else(|| parse_whois_date(value));
+        }
+        "expiration date" | "expires" | "expires on" | "expiry date" | "expire date"
+        | "registry expiry date" | "registrar registration expiration date" | "paid-till"
+        | "expiration time" | "domain expiration date" => {
+            data.expiration_date = data.expiration_date.or_else(|| parse_whois_date(value));
+        }
+        "updated date" | "updated" | "last updated" | "last update" | "last modified" | "changed"
+        | "modified" => {
+            data.updated_date = data.updated_date.or_else(|| parse_whois_date(value));
+        }
+        "name server" | "name servers" | "nserver" | "nameserver" | "nameservers" => {
+            // Some registries append glue addresses after the host name
+            if let Some(host) = value.split_whitespace().next() {
+                let host = host.trim_end_matches('.').to_lowercase();
+                if !data.name_servers.contains(&host) {
+                    data.name_servers.push(host);
+                }
+            }
+        }
+        "status" | "domain status" => {
+            data.status.push(value.to_string());
+        }
+        _ => {}
+    }
+}
+
 #[async_trait]
 impl EnrichmentProvider for WhoisProvider {
     fn name(&self) -> &'static str {
@@ -121,7 +408,7 @@ impl EnrichmentProvider for WhoisProvider {
         let data = self.lookup(&indicator.value).await?;
 
         // Only return if we got some meaningful data
-        if data.registrar.is_none() && data.name_servers.is_empty() {
+        if data.registrar.is_none() && data.name_servers.is_empty() && data.creation_date.is_none() {
             return Ok(None);
         }
 
@@ -130,12 +417,174 @@ impl EnrichmentProvider for WhoisProvider {
             "registrant": data.registrant,
             "registrant_org": data.registrant_org,
             "registrant_country": data.registrant_country,
+            "creation_date": data.creation_date,
+            "expiration_date": data.expiration_date,
+            "updated_date": data.updated_date,
+            "domain_age_days": data.domain_age_days(),
+            "privacy_protected": data.privacy_protected,
             "name_servers": data.name_servers,
             "status": data.status,
+            "whois_server": data.whois_server,
         })))
     }
 
     fn ttl_hours(&self) -> i64 {
         168 // 1 week - WHOIS data changes infrequently
     }
+
+    fn rate_limit(&self) -> Option<Quota> {
+        // Registries throttle aggressively; keep well under typical limits
+        Some(Quota::per_minute(NonZeroU32::new(30).unwrap()))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, s).unwrap()
+    }
+
+    #[test]
+    fn parses_registry_date_formats() {
+        let cases = [
+            ("2024-01-02T03:04:05Z", utc(2024, 1, 2, 3, 4, 5)),
+            ("2024-01-02T03:04:05.123+01:00", utc(2024, 1, 2, 2, 4, 5) + chrono::Duration::milliseconds(123)),
+            ("2024-01-02 03:04:05 UTC", utc(2024, 1, 2, 3, 4, 5)),
+            ("2024-01-02 03:04:05 CLST", utc(2024, 1, 2, 3, 4, 5)),
+            ("02-Jan-2024", utc(2024, 1, 2, 0, 0, 0)),
+            ("2024.01.02", utc(2024, 1, 2, 0, 0, 0)),
+            ("02.01.2024", utc(2024, 1, 2, 0, 0, 0)),
+            ("2024. 01. 02.", utc(2024, 1, 2, 0, 0, 0)),
+            ("2024-01-02 (YYYY-MM-DD)", utc(2024, 1, 2, 0, 0, 0)),
+            ("20240102", utc(2024, 1, 2, 0, 0, 0)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_whois_date(input), Some(expected), "{}", input);
+        }
+
+        assert_eq!(parse_whois_date(""), None);
+        assert_eq!(parse_whois_date("not a date"), None);
+    }
+
+    #[test]
+    fn parses_key_value_responses() {
+        let raw = "\
+% Comment line
+Domain Name: EXAMPLE.COM
+Registrar: Example Registrar, Inc.
+Registrant Organization: Example Org
+Registrant Country: US
+Creation Date: 1995-08-14T04:00:00Z
+Registry Expiry Date: 2030-08-13T04:00:00Z
+Name Server: NS1.EXAMPLE.COM.
+Name Server: ns2.example.com 192.0.2.53
+Name Server: ns1.example.com
+Domain Status: clientTransferProhibited https://icann.org/epp#clientTransferProhibited
+>>> Last update of whois database: 2024-01-02T00:00:00Z <<<
+Registrant Name: After the notice
+";
+        let data = parse_whois_response(raw);
+
+        assert_eq!(data.registrar.as_deref(), Some("Example Registrar, Inc."));
+        assert_eq!(data.registrant_org.as_deref(), Some("Example Org"));
+        assert_eq!(data.registrant_country.as_deref(), Some("US"));
+        assert_eq!(data.registrant, None);
+        assert_eq!(data.creation_date, Some(utc(1995, 8, 14, 4, 0, 0)));
+        assert_eq!(data.expiration_date, Some(utc(2030, 8, 13, 4, 0, 0)));
+        assert_eq!(data.name_servers, vec!["ns1.example.com", "ns2.example.com"]);
+        assert_eq!(data.status.len(), 1);
+        assert_eq!(data.raw.as_deref(), Some(raw));
+    }
+
+    #[test]
+    fn parses_block_layout_responses() {
+        let raw = "
+    Domain name:
+        example.co.uk
+
+    Registrar:
+        Example Registrar Ltd [Tag = EXAMPLE]
+
+    Relevant dates:
+        Registered on: 26-Aug-1996
+        Expiry date:  26-Aug-2030
+
+    Name servers:
+        ns1.example.net
+        ns2.example.net
+";
+        let data = parse_whois_response(raw);
+
+        assert_eq!(data.registrar.as_deref(), Some("Example Registrar Ltd [Tag = EXAMPLE]"));
+        assert_eq!(data.creation_date, Some(utc(1996, 8, 26, 0, 0, 0)));
+        assert_eq!(data.expiration_date, Some(utc(2030, 8, 26, 0, 0, 0)));
+        assert_eq!(data.name_servers, vec!["ns1.example.net", "ns2.example.net"]);
+    }
+
+    #[test]
+    fn follows_referrals_on_the_whois_port_only() {
+        let referral = |value: &str| find_referral(&format!("Domain Name: EXAMPLE.COM\nRegistrar WHOIS Server: {}\n", value));
+
+        assert_eq!(referral("whois.example-registrar.com"), Some("whois.example-registrar.com".to_string()));
+        assert_eq!(referral("WHOIS.Example-Registrar.com:43"), Some("whois.example-registrar.com".to_string()));
+        assert_eq!(referral("whois://whois.example-registrar.com/"), Some("whois.example-registrar.com".to_string()));
+        assert_eq!(
+            find_referral("ReferralServer: rwhois://rwhois.example.net:4321\n"),
+            None
+        );
+        assert_eq!(referral("whois.example-registrar.com:6379"), None);
+        assert_eq!(referral("10.0.0.1:22"), None);
+        assert_eq!(referral(""), None);
+        assert_eq!(referral("see the registrar"), None);
+        assert_eq!(find_referral("Registrar: Example\n"), None);
+    }
+
+    #[test]
+    fn registrar_answer_wins_and_registry_fills_gaps() {
+        let registry = WhoisData {
+            registrar: Some("Registry view".into()),
+            creation_date: Some(utc(2000, 1, 1, 0, 0, 0)),
+            name_servers: vec!["ns1.registry.example".into()],
+            status: vec!["ok".into()],
+            raw: Some("registry raw".into()),
+            ..Default::default()
+        };
+        let registrar = WhoisData {
+            registrar: Some("Registrar view".into()),
+            registrant: Some("Jane Doe".into()),
+            raw: Some("registrar raw".into()),
+            ..Default::default()
+        };
+
+        let merged = merge_whois(registrar, registry);
+
+        assert_eq!(merged.registrar.as_deref(), Some("Registrar view"));
+        assert_eq!(merged.registrant.as_deref(), Some("Jane Doe"));
+        assert_eq!(merged.creation_date, Some(utc(2000, 1, 1, 0, 0, 0)));
+        assert_eq!(merged.name_servers, vec!["ns1.registry.example"]);
+        assert_eq!(merged.status, vec!["ok"]);
+        assert_eq!(merged.raw.as_deref(), Some("registry raw\n\nregistrar raw"));
+    }
+
+    #[test]
+    fn flags_privacy_services_but_not_registrants_named_after_them() {
+        let with_org = |org: &str| WhoisData {
+            registrant_org: Some(org.to_string()),
+            ..Default::default()
+        };
+
+        assert!(is_privacy_protected(&with_org("REDACTED FOR PRIVACY")));
+        assert!(is_privacy_protected(&with_org("Domains By Proxy, LLC")));
+        assert!(is_privacy_protected(&with_org("Privacy service provided by Withheld for Privacy ehf")));
+        assert!(is_privacy_protected(&with_org("Whois Privacy Protection Service, Inc.")));
+
+        assert!(!is_privacy_protected(&with_org("Privacy International")));
+        assert!(!is_privacy_protected(&with_org("Proxy Networks Inc")));
+        assert!(!is_privacy_protected(&with_org("Redacted Media GmbH")));
+        assert!(!is_privacy_protected(&WhoisData::default()));
+    }
 }
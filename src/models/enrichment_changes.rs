@@ -0,0 +1,211 @@
+//! Material change detection between enrichment snapshots
+//!
+//! Providers return a lot of volatile detail (vote counts, timestamps, TXT
+//! records), so only the fields below raise a change event when a refresh
+//! differs from the previous snapshot, and a refresh that differs only in
+//! volatile fields does not start a new snapshot at all.
+
+use serde_json::Value;
+
+use crate::models::{ChangeKind, EnrichmentChange};
+
+/// How a watched field is compared
+enum Watch {
+    /// Numeric rise of at least this much, or any rise from zero
+    Increase(f64),
+    /// Numeric move of at least this much in either direction
+    Delta(f64),
+    /// New entries in a list
+    Additions,
+    /// Any change of value
+    Value,
+}
+
+/// Watched fields as JSON pointers into provider results
+const WATCHED: &[(&str, Watch)] = &[
+    // Reputation
+    ("/analysis/malicious", Watch::Increase(3.0)),
+    ("/analysis/suspicious", Watch::Increase(5.0)),
+    ("/reputation", Watch::Delta(10.0)),
+    ("/abuse_confidence_score", Watch::Delta(20.0)),
+    ("/total_reports", Watch::Increase(10.0)),
+    ("/is_whitelisted", Watch::Value),
+    ("/pulse_count", Watch::Increase(1.0)),
+    // DNS
+    ("/a_records", Watch::Additions),
+    ("/aaaa_records", Watch::Additions),
+    ("/cname_records", Watch::Additions),
+    ("/ns_records", Watch::Additions),
+    ("/mx_records", Watch::Additions),
+    ("/ptr_records", Watch::Additions),
+    // Location and ownership
+    ("/country_code", Watch::Value),
+    ("/country", Watch::Value),
+    ("/asn", Watch::Value),
+    ("/registrar", Watch::Value),
+    ("/registrant_org", Watch::Value),
+    ("/registrant_country", Watch::Value),
+    ("/name_servers", Watch::Additions),
+    ("/network/handle", Watch::Value),
+];
+
+/// Fields that move on nearly every refresh without saying anything new
+const VOLATILE: &[&str] = &[
+    // VirusTotal community votes and engine totals
+    "/votes",
+    "/analysis/harmless",
+    "/analysis/undetected",
+    "/analysis/detection_ratio",
+    // AbuseIPDB
+    "/last_reported_at",
+    "/num_distinct_users",
+    // MalwareBazaar, exposure scans
+    "/last_seen",
+    "/last_update",
+];
+
+/// Whether `after` only differs from `before` in volatile fields, so it can
+/// update the current snapshot instead of adding one
+pub fn same_snapshot(before: &Value, after: &Value) -> bool {
+    without_volatile(before) == without_volatile(after)
+}
+
+fn without_volatile(value: &Value) -> Value {
+    let mut value = value.clone();
+    for pointer in VOLATILE {
+        let (parent, key) = pointer.rsplit_once('/').unwrap_or_default();
+        if let Some(fields) = value.pointer_mut(parent).and_then(Value::as_object_mut) {
+            fields.remove(key);
+        }
+    }
+    value
+}
+
+/// Material differences from `before` to `after`
+pub fn material_changes(before: &Value, after: &Value) -> Vec<EnrichmentChange> {
+    WATCHED
+        .iter()
+        .filter_map(|(pointer, watch)| {
+            let old = before.pointer(pointer).unwrap_or(&Value::Null);
+            let new = after.pointer(pointer).unwrap_or(&Value::Null);
+            if old == new {
+                return None;
+            }
+
+            let kind = match watch {
+                Watch::Increase(min) => {
+                    let (old, new) = (old.as_f64().unwrap_or(0.0), new.as_f64()?);
+                    (new - old >= *min || (old == 0.0 && new > 0.0)).then_some(ChangeKind::Increased)?
+                }
+                Watch::Delta(min) => {
+                    let (old, new) = (old.as_f64()?, new.as_f64()?);
+                    ((new - old).abs() >= *min).then_some(if new > old { ChangeKind::Increased } else { ChangeKind::Decreased })?
+                }
+                Watch::Additions => {
+                    let old = old.as_array().map(Vec::as_slice).unwrap_or_default();
+                    let new = new.as_array()?;
+                    new.iter().any(|v| !old.contains(v)).then_some(ChangeKind::Added)?
+                }
+                Watch::Value => {
+                    // A field appearing for the first time is not a change
+                    if old.is_null() || new.is_null() {
+                        return None;
+                    }
+                    ChangeKind::Changed
+                }
+            };
+
+            Some(EnrichmentChange {
+                field: pointer.trim_start_matches('/').replace('/', "."),
+                kind,
+                before: old.clone(),
+                after: new.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn kinds(before: Value, after: Value) -> Vec<(String, ChangeKind)> {
+        material_changes(&before, &after).into_iter().map(|c| (c.field, c.kind)).collect()
+    }
+
+    #[test]
+    fn detection_increases_past_threshold() {
+        assert_eq!(
+            kinds(json!({ "analysis": { "malicious": 2 } }), json!({ "analysis": { "malicious": 5 } })),
+            vec![("analysis.malicious".to_string(), ChangeKind::Increased)]
+        );
+        // Small rises and drops are noise
+        assert!(kinds(json!({ "analysis": { "malicious": 2 } }), json!({ "analysis": { "malicious": 4 } })).is_empty());
+        assert!(kinds(json!({ "analysis": { "malicious": 9 } }), json!({ "analysis": { "malicious": 1 } })).is_empty());
+        // Any first detection counts
+        assert_eq!(
+            kinds(json!({ "analysis": { "malicious": 0 } }), json!({ "analysis": { "malicious": 1 } })),
+            vec![("analysis.malicious".to_string(), ChangeKind::Increased)]
+        );
+        assert_eq!(
+            kinds(json!({}), json!({ "pulse_count": 1 })),
+            vec![("pulse_count".to_string(), ChangeKind::Increased)]
+        );
+    }
+
+    #[test]
+    fn score_moves_both_ways() {
+        assert_eq!(
+            kinds(json!({ "abuse_confidence_score": 80 }), json!({ "abuse_confidence_score": 40 })),
+            vec![("abuse_confidence_score".to_string(), ChangeKind::Decreased)]
+        );
+        assert_eq!(
+            kinds(json!({ "reputation": -5 }), json!({ "reputation": 10 })),
+            vec![("reputation".to_string(), ChangeKind::Increased)]
+        );
+        assert!(kinds(json!({ "abuse_confidence_score": 80 }), json!({ "abuse_confidence_score": 95 })).is_empty());
+    }
+
+    #[test]
+    fn new_records_but_not_removed_ones() {
+        assert_eq!(
+            kinds(json!({ "a_records": ["192.0.2.1"] }), json!({ "a_records": ["192.0.2.1", "192.0.2.2"] })),
+            vec![("a_records".to_string(), ChangeKind::Added)]
+        );
+        assert!(kinds(json!({ "a_records": ["192.0.2.1", "192.0.2.2"] }), json!({ "a_records": ["192.0.2.2"] })).is_empty());
+        assert_eq!(
+            kinds(json!({}), json!({ "name_servers": ["ns1.example.net"] })),
+            vec![("name_servers".to_string(), ChangeKind::Added)]
+        );
+    }
+
+    #[test]
+    fn ownership_changes_but_not_first_appearance() {
+        let changes = material_changes(&json!({ "network": { "handle": "NET-1" } }), &json!({ "network": { "handle": "NET-2" } }));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "network.handle");
+        assert_eq!((changes[0].before.clone(), changes[0].after.clone()), (json!("NET-1"), json!("NET-2")));
+
+        assert!(kinds(json!({}), json!({ "registrar": "Example Registrar" })).is_empty());
+        assert!(kinds(json!({ "registrar": "Example Registrar" }), json!({})).is_empty());
+    }
+
+    #[test]
+    fn volatile_fields_are_not_material() {
+        let before = json!({
+            "analysis": { "malicious": 3, "harmless": 60, "undetected": 10, "detection_ratio": "3/73" },
+            "votes": { "harmless": 1, "malicious": 0 },
+        });
+        let after = json!({
+            "analysis": { "malicious": 3, "harmless": 62, "undetected": 9, "detection_ratio": "3/74" },
+            "votes": { "harmless": 4, "malicious": 2 },
+        });
+
+        assert!(material_changes(&before, &after).is_empty());
+        assert!(same_snapshot(&before, &after));
+        assert!(!same_snapshot(&before, &json!({ "analysis": { "malicious": 4 } })));
+        assert!(same_snapshot(&json!({ "last_reported_at": "2024-05-01T00:00:00Z" }), &json!({})));
+        assert!(same_snapshot(&json!("not an object"), &json!("not an object")));
+    }
+}
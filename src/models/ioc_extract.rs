@@ -0,0 +1,666 @@
+// src/models/ioc_extract.rs
+
+//! Indicator extraction from free text, emails and HTML reports
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::LazyLock;
+
+use crate::models::ioc_utils::{normalize_ioc, refang};
+use crate::models::IocType;
+
+/// Input document formats understood by the extractor
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+    #[default]
+    Text,
+    Email,
+    Html,
+}
+
+impl DocumentFormat {
+    /// Guess the format from an uploaded file name
+    pub fn from_filename(name: &str) -> Option<Self> {
+        let ext = name.rsplit_once('.')?.1.to_lowercase();
+        match ext.as_str() {
+            "txt" | "log" | "md" => Some(DocumentFormat::Text),
+            "eml" | "msg" => Some(DocumentFormat::Email),
+            "html" | "htm" => Some(DocumentFormat::Html),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from a Content-Type header
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next()?.trim().to_lowercase();
+        match mime.as_str() {
+            "text/plain" => Some(DocumentFormat::Text),
+            "message/rfc822" => Some(DocumentFormat::Email),
+            "text/html" => Some(DocumentFormat::Html),
+            _ => None,
+        }
+    }
+}
+
+/// Byte range of a match within the scanned text
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TextSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A candidate indicator found in a document, for analyst review before import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractedIndicator {
+    pub ioc_type: IocType,
+    /// Refanged and normalized value, ready for import
+    pub value: String,
+    /// The value as it first appeared in the text
+    pub raw: String,
+    /// Whether any occurrence was written in defanged form
+    pub defanged: bool,
+    pub occurrences: Vec<TextSpan>,
+}
+
+/// Extraction result; offsets refer to `text`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractionResult {
+    pub format: DocumentFormat,
+    pub text: String,
+    pub candidates: Vec<ExtractedIndicator>,
+}
+
+const DOT: &str = r"(?:\.|\[\.\]|\(\.\)|\{\.\}|\[dot\]|\(dot\))";
+const AT: &str = r"(?:@|\[@\]|\(@\)|\[at\]|\(at\))";
+const LABEL: &str = r"[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?";
+const OCTET: &str = r"(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)";
+
+static URL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\b(?:https?|hxxps?|hxps?|ftps?|fxps?)(?:://|\[:\]//|\[://\])[^\s"'<>]+"#).unwrap()
+});
+
+static EMAIL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(r"(?i)\b[a-z0-9._%+-]+{AT}(?:{LABEL}{DOT})+[a-z]{{2,63}}\b")).unwrap()
+});
+
+static CVE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\bCVE-\d{4}-\d{4,}\b").unwrap());
+
+static HASH_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:[a-fA-F0-9]{64}|[a-fA-F0-9]{40}|[a-fA-F0-9]{32})\b").unwrap());
+
+static IPV4_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(r"(?i)\b(?:{OCTET}{DOT}){{3}}{OCTET}(?:/(?:3[0-2]|[12]?\d))?\b")).unwrap()
+});
+
+static IPV6_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:[0-9a-f]{1,4}:|:){2,7}(?:[0-9a-f]{1,4}|:)(?:/(?:12[0-8]|1[01]\d|[1-9]?\d))?").unwrap()
+});
+
+static DOMAIN_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(r"(?i)\b(?:{LABEL}{DOT})+[a-z][a-z0-9-]{{1,62}}\b")).unwrap()
+});
+
+static HTML_TAG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+
+static HTML_SKIP_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<(script|style)\b[^>]*>.*?</(script|style)>").unwrap());
+
+static HTML_LINK_ATTR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\b(?:href|src|action)\s*=\s*["']?([^"'\s>]+)"#).unwrap());
+
+static HTML_ENTITY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"&(#x[0-9a-fA-F]+|#\d+|[a-zA-Z]+);").unwrap());
+
+/// File extensions that look like TLDs but are not delegated, so `invoice.pdf` is not a domain
+const FILE_EXTENSIONS: &[&str] = &[
+    "exe", "dll", "pdf", "doc", "docx", "docm", "xls", "xlsx", "xlsm", "ppt", "pptx", "js",
+    "vbs", "ps1", "bat", "cmd", "hta", "lnk", "jar", "msi", "scr", "iso", "img", "php", "asp",
+    "aspx", "jsp", "html", "htm", "txt", "csv", "log", "json", "xml", "yml", "yaml", "ini",
+    "cfg", "conf", "dat", "tmp", "sys", "bin", "elf", "png", "jpg", "jpeg", "gif", "bmp",
+    "svg", "eml", "rtf", "gz", "tar", "rar", "7z", "cab", "c", "h", "cpp", "rs", "go", "java",
+];
+
+/// Domains that routinely appear in documents without being malicious
+const BENIGN_DOMAINS: &[&str] = &[
+    "example.com", "example.net", "example.org", "localhost", "localdomain", "w3.org",
+    "schema.org", "schemas.microsoft.com", "schemas.openxmlformats.org", "xmlsoap.org",
+    "purl.org", "ns.adobe.com", "mitre.org", "cve.org", "nvd.nist.gov",
+];
+
+/// Extract candidate indicators from a document
+pub fn extract_indicators(input: &str, format: DocumentFormat) -> ExtractionResult {
+    let text = match format {
+        DocumentFormat::Text => input.to_string(),
+        DocumentFormat::Html => html_to_text(input),
+        DocumentFormat::Email => email_to_text(input),
+    };
+
+    let candidates = scan_text(&text);
+
+    ExtractionResult {
+        format,
+        text,
+        candidates,
+    }
+}
+
+/// Scan plain text for indicators, most specific patterns first so a domain inside
+/// a URL or email is not reported twice
+fn scan_text(text: &str) -> Vec<ExtractedIndicator> {
+    let mut taken: Vec<TextSpan> = vec![];
+    let mut found: Vec<ExtractedIndicator> = vec![];
+    let mut index: HashMap<(String, String), usize> = HashMap::new();
+
+    let passes: [(&Regex, IocType); 7] = [
+        (&URL_RE, IocType::Url),
+        (&EMAIL_RE, IocType::Email),
+        (&CVE_RE, IocType::Cve),
+        (&HASH_RE, IocType::Hash),
+        (&IPV4_RE, IocType::Ip),
+        (&IPV6_RE, IocType::Ip),
+        (&DOMAIN_RE, IocType::Domain),
+    ];
+
+    for (re, ioc_type) in passes {
+        for m in re.find_iter(text) {
+            let raw = trim_trailing_punctuation(m.as_str());
+            if raw.is_empty() {
+                continue;
+            }
+            let span = TextSpan {
+                start: m.start(),
+                end: m.start() + raw.len(),
+            };
+
+            if taken.iter().any(|t| span.start < t.end && t.start < span.end) {
+                continue;
+            }
+
+            if !has_clean_boundaries(text, span, &ioc_type) {
+                continue;
+            }
+
+            let refanged = refang(raw);
+            let ioc_type = if ioc_type == IocType::Ip && refanged.contains('/') {
+                IocType::Cidr
+            } else {
+                ioc_type.clone()
+            };
+            if is_benign(&refanged, &ioc_type) {
+                continue;
+            }
+
+            let value = normalize_ioc(&refanged, &ioc_type);
+            taken.push(span);
+
+            let key = (ioc_type.to_string(), value.clone());
+            match index.get(&key) {
+                Some(&i) => {
+                    found[i].occurrences.push(span);
+                    found[i].defanged |= refanged != raw;
+                }
+                None => {
+                    index.insert(key, found.len());
+                    found.push(ExtractedIndicator {
+                        ioc_type: ioc_type.clone(),
+                        defanged: refanged != raw,
+                        value,
+                        raw: raw.to_string(),
+                        occurrences: vec![span],
+                    });
+                }
+            }
+        }
+    }
+
+    for candidate in &mut found {
+        candidate.occurrences.sort_by_key(|s| s.start);
+    }
+    found.sort_by_key(|c| c.occurrences[0].start);
+    found
+}
+
+/// Strip sentence punctuation and unbalanced closing brackets picked up at the end of a match
+fn trim_trailing_punctuation(value: &str) -> &str {
+    let mut value = value;
+    loop {
+        let Some(last) = value.chars().last() else {
+            return value;
+        };
+        let unbalanced = match last {
+            ')' => value.matches('(').count() < value.matches(')').count(),
+            ']' => value.matches('[').count() < value.matches(']').count(),
+            '}' => value.matches('{').count() < value.matches('}').count(),
+            '.' | ',' | ';' | ':' | '!' | '?' | '\'' | '"' | '>' => true,
+            _ => false,
+        };
+        if !unbalanced {
+            return value;
+        }
+        value = &value[..value.len() - last.len_utf8()];
+    }
+}
+
+/// Reject matches that are fragments of a longer token, e.g. `1.2.3.4` inside a
+/// version string `1.2.3.4.5` or a hash inside a longer hex blob
+fn has_clean_boundaries(text: &str, span: TextSpan, ioc_type: &IocType) -> bool {
+    let before = text[..span.start].chars().next_back();
+    let after = text[span.end..].chars().next();
+
+    match ioc_type {
+        IocType::Ip => {
+            let mut after_chars = text[span.end..].chars();
+            let dotted_continuation =
+                after == Some('.') && after_chars.nth(1).is_some_and(|c| c.is_ascii_digit());
+            !matches!(before, Some(c) if c == '.' || c.is_ascii_alphanumeric())
+                && !matches!(after, Some(c) if c.is_ascii_alphanumeric())
+                && !dotted_continuation
+        }
+        IocType::Domain => {
+            !matches!(before, Some(c) if c == '.' || c == '-' || c == '_' || c == '/' || c == '\\')
+                && !matches!(after, Some(c) if c == '-' || c == '_')
+        }
+        _ => true,
+    }
+}
+
+/// Known-benign noise: documentation addresses, file names, schema hosts, placeholder hashes
+fn is_benign(value: &str, ioc_type: &IocType) -> bool {
+    match ioc_type {
+        IocType::Ip | IocType::Cidr => {
+            let addr = value.split('/').next().unwrap_or(value);
+            match addr.parse::<IpAddr>() {
+                Ok(IpAddr::V4(v4)) => is_benign_ipv4(&v4),
+                Ok(IpAddr::V6(v6)) => is_benign_ipv6(&v6),
+                Err(_) => true,
+            }
+        }
+        IocType::Domain => {
+            let lower = value.to_lowercase();
+            let tld = lower.rsplit('.').next().unwrap_or("");
+            FILE_EXTENSIONS.contains(&tld)
+                || tld.chars().all(|c| c.is_ascii_digit())
+                || is_benign_domain(&lower)
+        }
+        IocType::Email => value
+            .rsplit_once('@')
+            .is_some_and(|(_, domain)| is_benign_domain(&domain.to_lowercase())),
+        IocType::Url => url::Url::parse(value)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| is_benign_domain(&h.to_lowercase())))
+            .unwrap_or(true),
+        IocType::Hash => {
+            let first = value.chars().next();
+            value.chars().all(|c| Some(c) == first)
+        }
+        _ => false,
+    }
+}
+
+fn is_benign_domain(domain: &str) -> bool {
+    BENIGN_DOMAINS
+        .iter()
+        .any(|b| domain == *b || domain.ends_with(&format!(".{}", b)))
+}
+
+fn is_benign_ipv4(ip: &Ipv4Addr) -> bool {
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_link_local()
+        || ip.is_documentation()
+}
+
+fn is_benign_ipv6(ip: &Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || (segments[0] & 0xffc0) == 0xfe80 // link-local
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8) // documentation
+}
+
+/// Flatten HTML to text, keeping link targets that are otherwise hidden in attributes
+fn html_to_text(html: &str) -> String {
+    let without_scripts = HTML_SKIP_RE.replace_all(html, " ");
+    let flattened = HTML_TAG_RE.replace_all(&without_scripts, |caps: &regex::Captures| {
+        let links: Vec<&str> = HTML_LINK_ATTR_RE
+            .captures_iter(&caps[0])
+            .filter_map(|c| c.get(1).map(|m| m.as_str()))
+            .collect();
+        if links.is_empty() {
+            " ".to_string()
+        } else {
+            format!(" {} ", links.join(" "))
+        }
+    });
+    decode_html_entities(&flattened)
+}
+
+fn decode_html_entities(text: &str) -> String {
+    HTML_ENTITY_RE
+        .replace_all(text, |caps: &regex::Captures| {
+            let entity = &caps[1];
+            let decoded = if let Some(hex) = entity.strip_prefix("#x") {
+                u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+            } else if let Some(dec) = entity.strip_prefix('#') {
+                dec.parse::<u32>().ok().and_then(char::from_u32)
+            } else {
+                match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some(' '),
+                    _ => None,
+                }
+            };
+            decoded.map(String::from).unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+/// Flatten an RFC 822 message: headers are kept verbatim (they carry relays and
+/// sender addresses), text parts are decoded and attachments skipped
+fn email_to_text(raw: &str) -> String {
+    let raw = raw.replace("\r\n", "\n");
+    let (headers, body) = split_headers(&raw);
+    let mut out = unfold_headers(headers);
+    out.push_str("\n\n");
+    out.push_str(&decode_mime_part(headers, body));
+    out
+}
+
+fn split_headers(part: &str) -> (&str, &str) {
+    part.split_once("\n\n").unwrap_or((part, ""))
+}
+
+fn unfold_headers(headers: &str) -> String {
+    let mut out = String::with_capacity(headers.len());
+    for line in headers.lines() {
+        if line.starts_with(' ') || line.starts_with('\t') {
+            out.push(' ');
+            out.push_str(line.trim());
+        } else {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(line);
+        }
+    }
+    out
+}
+
+fn header_value(headers: &str, name: &str) -> Option<String> {
+    unfold_headers(headers).lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| value.trim().to_string())
+    })
+}
+
+fn decode_mime_part(headers: &str, body: &str) -> String {
+    let content_type = header_value(headers, "Content-Type")
+        .unwrap_or_else(|| "text/plain".to_string());
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+
+    if mime.starts_with("multipart/") {
+        let Some(boundary) = mime_parameter(&content_type, "boundary") else {
+            return body.to_string();
+        };
+        let delimiter = format!("--{}", boundary);
+        return body
+            .split(&delimiter)
+            .skip(1)
+            .take_while(|part| !part.starts_with("--"))
+            .map(|part| {
+                let (part_headers, part_body) = split_headers(part.trim_start_matches('\n'));
+                decode_mime_part(part_headers, part_body)
+            })
+            .filter(|text| !text.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+    }
+
+    if mime == "message/rfc822" {
+        return email_to_text(body);
+    }
+
+    if !mime.starts_with("text/") {
+        return String::new();
+    }
+
+    let encoding = header_value(headers, "Content-Transfer-Encoding")
+        .unwrap_or_default()
+        .to_lowercase();
+    let decoded = match encoding.as_str() {
+        "base64" => {
+            let compact: String = body.split_whitespace().collect();
+            STANDARD
+                .decode(compact)
+                .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                .unwrap_or_else(|_| body.to_string())
+        }
+        "quoted-printable" => decode_quoted_printable(body),
+        _ => body.to_string(),
+    };
+
+    if mime == "text/html" {
+        html_to_text(&decoded)
+    } else {
+        decoded
+    }
+}
+
+fn mime_parameter(content_type: &str, name: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+fn decode_quoted_printable(body: &str) -> String {
+    let mut bytes = Vec::with_capacity(body.len());
+    let mut lines = body.split('\n').peekable();
+
+    while let Some(line) = lines.next() {
+        let line = line.trim_end_matches('\r');
+        let (line, soft_break) = match line.strip_suffix('=') {
+            Some(stripped) => (stripped, true),
+            None => (line, false),
+        };
+
+        let raw = line.as_bytes();
+        let mut i = 0;
+        while i < raw.len() {
+            if raw[i] == b'='
+                && let Some(byte) = line
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                bytes.push(byte);
+                i += 3;
+                continue;
+            }
+            bytes.push(raw[i]);
+            i += 1;
+        }
+
+        if !soft_break && lines.peek().is_some() {
+            bytes.push(b'\n');
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(result: &ExtractionResult) -> Vec<(IocType, &str)> {
+        result
+            .candidates
+            .iter()
+            .map(|c| (c.ioc_type.clone(), c.value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn refangs_defanged_indicators() {
+        let text = "C2 at hxxps://update-check[.]top/gate.php and 185.220.101[.]45, \
+                    mail from billing[at]invoices-portal(dot)xyz, resolves via ns1.badhost{.}ru";
+        let result = extract_indicators(text, DocumentFormat::Text);
+
+        assert_eq!(
+            values(&result),
+            vec![
+                (IocType::Url, "https://update-check.top/gate.php"),
+                (IocType::Ip, "185.220.101.45"),
+                (IocType::Email, "billing@invoices-portal.xyz"),
+                (IocType::Domain, "ns1.badhost.ru"),
+            ]
+        );
+        assert!(result.candidates.iter().all(|c| c.defanged));
+        assert_eq!(result.candidates[0].raw, "hxxps://update-check[.]top/gate.php");
+    }
+
+    #[test]
+    fn trims_punctuation_and_keeps_balanced_brackets() {
+        let text = "See (https://evil-cdn.net/a_(1).js). Also \"https://evil-cdn.net/b\", then 45.133.1.20.";
+        let result = extract_indicators(text, DocumentFormat::Text);
+
+        assert_eq!(
+            values(&result),
+            vec![
+                (IocType::Url, "https://evil-cdn.net/a_(1).js"),
+                (IocType::Url, "https://evil-cdn.net/b"),
+                (IocType::Ip, "45.133.1.20"),
+            ]
+        );
+        assert_eq!(trim_trailing_punctuation("evil.net]."), "evil.net");
+        assert_eq!(trim_trailing_punctuation("a[0]"), "a[0]");
+    }
+
+    #[test]
+    fn overlapping_matches_are_reported_once() {
+        let text = "Download http://files.badhost.ru/x.bin from files.badhost.ru, \
+                    contact ops@badhost.ru; files.badhost.ru again";
+        let result = extract_indicators(text, DocumentFormat::Text);
+
+        assert_eq!(
+            values(&result),
+            vec![
+                (IocType::Url, "http://files.badhost.ru/x.bin"),
+                (IocType::Domain, "files.badhost.ru"),
+                (IocType::Email, "ops@badhost.ru"),
+            ]
+        );
+        let domain = &result.candidates[1];
+        assert_eq!(domain.occurrences.len(), 2);
+        assert_eq!(&text[domain.occurrences[0].start..domain.occurrences[0].end], "files.badhost.ru");
+    }
+
+    #[test]
+    fn skips_fragments_and_benign_noise() {
+        let text = "version 10.2.3.4.5, docs 192.0.2.1 and 127.0.0.1, \
+                    open invoice.pdf or https://www.example.com/x, zero hash \
+                    00000000000000000000000000000000, blob \
+                    d41d8cd98f00b204e9800998ecf8427eaa, ns schemas.microsoft.com";
+        let result = extract_indicators(text, DocumentFormat::Text);
+
+        assert!(result.candidates.is_empty(), "{:?}", values(&result));
+    }
+
+    #[test]
+    fn finds_hashes_cves_networks_and_ipv6() {
+        let text = "CVE-2024-3400 exploited from 45.133.1.0/24 and 2a03:b0c0:3:d0::1e:7001; \
+                    dropper D41D8CD98F00B204E9800998ECF8427E";
+        let result = extract_indicators(text, DocumentFormat::Text);
+
+        assert_eq!(
+            values(&result),
+            vec![
+                (IocType::Cve, "CVE-2024-3400"),
+                (IocType::Cidr, "45.133.1.0/24"),
+                (IocType::Ip, "2a03:b0c0:3:d0::1e:7001"),
+                (IocType::Hash, "d41d8cd98f00b204e9800998ecf8427e"),
+            ]
+        );
+    }
+
+    #[test]
+    fn html_keeps_link_targets_and_drops_scripts() {
+        let html = r#"<html><head><script>var cdn = "tracker.adnet.io";</script></head>
+            <body><p>Payload at <a href="http://dl.badhost.ru/p.exe">this link</a> &amp; 45.133.1.20</p>
+            <img src="https://img.badhost.ru/t.gif"></body></html>"#;
+        let result = extract_indicators(html, DocumentFormat::Html);
+
+        assert_eq!(
+            values(&result),
+            vec![
+                (IocType::Url, "http://dl.badhost.ru/p.exe"),
+                (IocType::Ip, "45.133.1.20"),
+                (IocType::Url, "https://img.badhost.ru/t.gif"),
+            ]
+        );
+        assert!(result.text.contains(" & "));
+    }
+
+    #[test]
+    fn email_decodes_parts_and_keeps_headers() {
+        let email = "Received: from mail.badhost.ru (mail.badhost.ru [45.133.1.20])\r\n\
+                     \tby relay with ESMTP id 4Vq7Lk\r\n\
+                     From: \"Billing\" <billing@invoices-portal.xyz>\r\n\
+                     Content-Type: multipart/mixed; boundary=\"b1\"\r\n\
+                     \r\n\
+                     --b1\r\n\
+                     Content-Type: text/plain\r\n\
+                     Content-Transfer-Encoding: quoted-printable\r\n\
+                     \r\n\
+                     Pay at https://pay.invoices-portal.xyz/login?id=3D7=\r\n\
+                     7\r\n\
+                     --b1\r\n\
+                     Content-Type: text/html\r\n\
+                     Content-Transfer-Encoding: base64\r\n\
+                     \r\n\
+                     PGEgaHJlZj0iaHR0cDovL2Nkbi5iYWRob3N0LnJ1L2YiPmNsaWNrPC9hPg==\r\n\
+                     --b1\r\n\
+                     Content-Type: application/octet-stream\r\n\
+                     \r\n\
+                     45.133.1.99\r\n\
+                     --b1--\r\n";
+        let result = extract_indicators(email, DocumentFormat::Email);
+
+        assert_eq!(
+            values(&result),
+            vec![
+                (IocType::Domain, "mail.badhost.ru"),
+                (IocType::Ip, "45.133.1.20"),
+                (IocType::Email, "billing@invoices-portal.xyz"),
+                (IocType::Url, "https://pay.invoices-portal.xyz/login?id=77"),
+                (IocType::Url, "http://cdn.badhost.ru/f"),
+            ]
+        );
+    }
+
+    #[test]
+    fn guesses_document_format() {
+        assert_eq!(DocumentFormat::from_filename("report.HTM"), Some(DocumentFormat::Html));
+        assert_eq!(DocumentFormat::from_filename("phish.eml"), Some(DocumentFormat::Email));
+        assert_eq!(DocumentFormat::from_filename("notes"), None);
+        assert_eq!(
+            DocumentFormat::from_content_type("text/plain; charset=utf-8"),
+            Some(DocumentFormat::Text)
+        );
+        assert_eq!(DocumentFormat::from_content_type("application/pdf"), None);
+    }
+}
@@ -1,9 +1,14 @@
 // src/models/ioc_utils.rs
 
-use crate::models::IocType;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use crate::models::{HashAlgorithm, IocType};
+use ipnetwork::IpNetwork;
+use publicsuffix::Psl;
+use regex::Regex;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::LazyLock;
 
-/// Detect the IOC type from a raw value string
+/// Detect the IOC type from a raw value string (defanged URLs, emails, IPs
+/// and domains are accepted)
 pub fn detect_ioc_type(value: &str) -> Option<IocType> {
     let trimmed = value.trim();
     
@@ -16,12 +21,44 @@ pub fn detect_ioc_type(value: &str) -> Option<IocType> {
         return Some(IocType::Cve);
     }
     
-    // Hash patterns (MD5=32, SHA1=40, SHA256=64 hex chars)
-    if (trimmed.len() == 32 || trimmed.len() == 40 || trimmed.len() == 64)
-        && trimmed.chars().all(|c| c.is_ascii_hexdigit())
-    {
+    // Windows registry keys (HKEY_LOCAL_MACHINE\..., HKLM\...)
+    if registry_hive(trimmed).is_some() {
+        return Some(IocType::RegistryKey);
+    }
+
+    // Named kernel objects (Global\..., Local\...); mutex names have no other shape
+    if MUTEX_NAMESPACE.is_match(trimmed) {
+        return Some(IocType::Mutex);
+    }
+
+    // File paths (C:\..., \\server\share, %APPDATA%\..., /usr/bin/...)
+    if is_file_path(trimmed) {
+        return Some(IocType::FilePath);
+    }
+
+    // JA4 fingerprint (e.g., t13d1516h2_8daaf6152771_b186095e22b6)
+    if JA4.is_match(trimmed) {
+        return Some(IocType::Ja4);
+    }
+
+    // Certificate thumbprints in colon notation; bare hex digests are reported as hashes
+    if CERT_FINGERPRINT.is_match(trimmed) {
+        return Some(IocType::CertFingerprint);
+    }
+
+    // Autonomous system number (e.g., AS13335)
+    if ASN.is_match(trimmed) {
+        return Some(IocType::Asn);
+    }
+
+    // Hash patterns (cryptographic, fuzzy and structural)
+    if detect_hash_algorithm(trimmed).is_some() {
         return Some(IocType::Hash);
     }
+
+    // Only network indicators are written defanged
+    let refanged = refang(trimmed);
+    let trimmed = refanged.as_str();
     
     // URL pattern
     if trimmed.starts_with("http://") || trimmed.starts_with("https://") {
@@ -43,14 +80,9 @@ pub fn detect_ioc_type(value: &str) -> Option<IocType> {
         return Some(IocType::Ip);
     }
     
-    // CIDR patterns (treat as IP)
-    if trimmed.contains('/') {
-        let parts: Vec<&str> = trimmed.split('/').collect();
-        if parts.len() == 2 {
-            if parts[0].parse::<Ipv4Addr>().is_ok() || parts[0].parse::<Ipv6Addr>().is_ok() {
-                return Some(IocType::Ip);
-            }
-        }
+    // CIDR networks (e.g., 10.0.0.0/8) and address ranges (e.g., 10.0.0.0-10.0.0.255)
+    if (trimmed.contains('/') && trimmed.parse::<IpNetwork>().is_ok()) || parse_ip_range(trimmed).is_some() {
+        return Some(IocType::Cidr);
     }
     
     // Domain pattern
@@ -62,32 +94,613 @@ pub fn detect_ioc_type(value: &str) -> Option<IocType> {
     {
         return Some(IocType::Domain);
     }
+
+    // User agent (product/version followed by optional comments), checked on the
+    // value as written once nothing network-shaped matched
+    if USER_AGENT.is_match(value.trim()) {
+        return Some(IocType::UserAgent);
+    }
     
     None
 }
 
-/// Normalize an IOC value based on its type
+/// Normalize an IOC value based on its type, refanging URLs, domains, IPs and
+/// emails to canonical form
 pub fn normalize_ioc(value: &str, ioc_type: &IocType) -> String {
-    let trimmed = value.trim();
+    let refanged = match ioc_type {
+        IocType::Url | IocType::Domain | IocType::Ip | IocType::Cidr | IocType::Email => refang(value.trim()),
+        _ => value.trim().to_string(),
+    };
+    let trimmed = refanged.as_str();
     
     match ioc_type {
         IocType::Domain => trimmed.to_lowercase(),
-        IocType::Url => {
-            if let Some(idx) = trimmed.find("://") {
-                let (scheme, rest) = trimmed.split_at(idx + 3);
-                if let Some(path_idx) = rest.find('/') {
-                    let (host, path) = rest.split_at(path_idx);
-                    format!("{}{}{}", scheme.to_lowercase(), host.to_lowercase(), path)
-                } else {
-                    trimmed.to_lowercase()
-                }
-            } else {
-                trimmed.to_lowercase()
-            }
-        }
+        IocType::Url => canonicalize_url(trimmed).unwrap_or_else(|| legacy_normalize_url(trimmed)),
         IocType::Email => trimmed.to_lowercase(),
-        IocType::Ip => trimmed.to_lowercase(),
-        IocType::Hash => trimmed.to_lowercase(),
+        IocType::Ip => match trimmed.parse::<IpAddr>() {
+            Ok(ip) => ip.to_string(),
+            Err(_) => trimmed.to_lowercase(),
+        },
+        IocType::Cidr => normalize_network(trimmed),
+        IocType::Hash => match detect_hash_algorithm(trimmed) {
+            // ssdeep digests are base64 and case-sensitive
+            Some(HashAlgorithm::Ssdeep) => trimmed.to_string(),
+            _ => trimmed.to_lowercase(),
+        },
         IocType::Cve => trimmed.to_uppercase(),
+        IocType::Asn => match ASN.captures(trimmed) {
+            Some(caps) => format!("AS{}", &caps[1]),
+            None if trimmed.chars().all(|c| c.is_ascii_digit()) => format!("AS{}", trimmed),
+            None => trimmed.to_uppercase(),
+        },
+        IocType::FilePath => normalize_file_path(trimmed),
+        IocType::RegistryKey => normalize_registry_key(trimmed),
+        IocType::Mutex => trimmed.to_string(),
+        IocType::UserAgent => trimmed.split_whitespace().collect::<Vec<_>>().join(" "),
+        IocType::Ja3 | IocType::Ja4 => trimmed.to_lowercase(),
+        IocType::CertFingerprint => trimmed
+            .chars()
+            .filter(|c| *c != ':' && !c.is_whitespace())
+            .collect::<String>()
+            .to_lowercase(),
     }
-}
\ No newline at end of file
+}
+
+/// Query parameters that only carry campaign or click tracking state
+const TRACKING_PARAMS: [&str; 14] = [
+    "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid",
+    "_ga", "_gl", "_hsenc", "_hsmi",
+];
+
+fn is_tracking_param(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name.as_str())
+}
+
+/// Canonical URL form so the same resource is stored once: IDN hosts in punycode,
+/// default ports, `.`/`..` segments, fragments, tracking parameters and trailing
+/// slashes removed, remaining query parameters sorted
+pub fn canonicalize_url(value: &str) -> Option<String> {
+    let mut url = url::Url::parse(value).ok()?;
+    url.set_fragment(None);
+
+    if let Some(host) = url.host_str().filter(|h| h.ends_with('.')).map(|h| h.trim_end_matches('.').to_string()) {
+        url.set_host(Some(&host)).ok()?;
+    }
+
+    // Sort the raw `&`-separated segments: decoding and re-encoding them would
+    // turn `?token` into `?token=` and `%20` into `+`, so the stored URL would
+    // no longer be the one that was seen
+    let mut params: Vec<String> = url
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|segment| !segment.is_empty())
+        .filter(|segment| {
+            let name = segment.split('=').next().unwrap_or_default();
+            let name = url::form_urlencoded::parse(name.as_bytes()).next().map(|(name, _)| name);
+            !name.is_some_and(|name| is_tracking_param(&name))
+        })
+        .map(str::to_string)
+        .collect();
+    params.sort();
+    if params.is_empty() {
+        url.set_query(None);
+    } else {
+        url.set_query(Some(&params.join("&")));
+    }
+
+    let path = url.path().to_string();
+    if path.len() > 1 && path.ends_with('/') {
+        url.set_path(path.trim_end_matches('/'));
+    }
+
+    Some(url.to_string())
+}
+
+/// Lowercase scheme and host of a value the URL parser rejects
+fn legacy_normalize_url(value: &str) -> String {
+    if let Some(idx) = value.find("://") {
+        let (scheme, rest) = value.split_at(idx + 3);
+        if let Some(path_idx) = rest.find('/') {
+            let (host, path) = rest.split_at(path_idx);
+            format!("{}{}{}", scheme.to_lowercase(), host.to_lowercase(), path)
+        } else {
+            value.to_lowercase()
+        }
+    } else {
+        value.to_lowercase()
+    }
+}
+
+/// Host of a URL as the indicator it would be stored as (domain or IP)
+pub fn url_host(value: &str) -> Option<(IocType, String)> {
+    match url::Url::parse(value).ok()?.host()? {
+        url::Host::Domain(domain) => {
+            let domain = domain.trim_end_matches('.');
+            (!domain.is_empty()).then(|| (IocType::Domain, domain.to_lowercase()))
+        }
+        url::Host::Ipv4(ip) => Some((IocType::Ip, ip.to_string())),
+        url::Host::Ipv6(ip) => Some((IocType::Ip, ip.to_string())),
+    }
+}
+
+/// Public suffix list: `./data/public_suffix_list.dat` when present, else the copy
+/// bundled at build time. Includes the private section (github.io, blogspot.com, ...)
+/// so each tenant of a shared hosting suffix rolls up separately.
+static PUBLIC_SUFFIXES: LazyLock<publicsuffix::List> = LazyLock::new(|| {
+    std::fs::read_to_string("./data/public_suffix_list.dat")
+        .ok()
+        .and_then(|list| list.parse().ok())
+        .unwrap_or_else(|| {
+            include_str!("../../data/public_suffix_list.dat")
+                .parse()
+                .expect("bundled public suffix list is valid")
+        })
+});
+
+/// Public suffix of a domain (`co.uk` for `www.evil.co.uk`)
+pub fn public_suffix(domain: &str) -> Option<String> {
+    let domain = domain.trim_end_matches('.').to_lowercase();
+    PUBLIC_SUFFIXES
+        .suffix(domain.as_bytes())
+        .and_then(|suffix| std::str::from_utf8(suffix.as_bytes()).ok().map(str::to_string))
+}
+
+/// Registrable domain, i.e. public suffix plus one label (`evil.co.uk` for
+/// `www.evil.co.uk`); `None` for bare suffixes and single labels
+pub fn registrable_domain(domain: &str) -> Option<String> {
+    let domain = domain.trim_end_matches('.').to_lowercase();
+    if !domain.contains('.') {
+        return None;
+    }
+    PUBLIC_SUFFIXES
+        .domain(domain.as_bytes())
+        .and_then(|d| std::str::from_utf8(d.as_bytes()).ok().map(str::to_string))
+}
+
+/// Registrable domain of a normalized domain or URL indicator (URLs on an IP have none)
+pub fn indicator_registrable_domain(value: &str, ioc_type: &IocType) -> Option<String> {
+    match ioc_type {
+        IocType::Domain => registrable_domain(value),
+        IocType::Url => match url_host(value)? {
+            (IocType::Domain, host) => registrable_domain(&host),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+static ASN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)^ASN?\s*(\d{1,10})$").unwrap());
+
+static JA4: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^[tqd]\d{2}[di]\d{4}[0-9a-z]{2}_[0-9a-f]{12}_[0-9a-f]{12}$").unwrap()
+});
+
+/// SHA1 (20 byte) or SHA256 (32 byte) thumbprint written as colon separated octets
+static CERT_FINGERPRINT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[0-9A-Fa-f]{2}(?::[0-9A-Fa-f]{2}){19}$|^[0-9A-Fa-f]{2}(?::[0-9A-Fa-f]{2}){31}$").unwrap()
+});
+
+static MUTEX_NAMESPACE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^(?:Global|Local|Session\\\d+)\\\S").unwrap());
+
+/// Leading `product/version` token: a dotless product name and a dotted numeric version
+static USER_AGENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[A-Za-z][A-Za-z0-9_+-]*/\d+(?:\.\d+)+[\w.+-]*(?:\s.*)?$").unwrap()
+});
+
+/// Registry hives with their abbreviations
+const REGISTRY_HIVES: [(&str, &str); 5] = [
+    ("HKEY_LOCAL_MACHINE", "HKLM"),
+    ("HKEY_CURRENT_USER", "HKCU"),
+    ("HKEY_CLASSES_ROOT", "HKCR"),
+    ("HKEY_USERS", "HKU"),
+    ("HKEY_CURRENT_CONFIG", "HKCC"),
+];
+
+/// Full hive name of a registry key and the remainder of the path
+fn registry_hive(value: &str) -> Option<(&'static str, &str)> {
+    let (root, rest) = value.split_once('\\').unwrap_or((value, ""));
+    REGISTRY_HIVES
+        .iter()
+        .find(|(full, short)| root.eq_ignore_ascii_case(full) || root.eq_ignore_ascii_case(short))
+        .map(|(full, _)| (*full, rest))
+}
+
+/// Registry paths are case-insensitive: spell out the hive and lowercase the rest
+fn normalize_registry_key(value: &str) -> String {
+    let Some((hive, rest)) = registry_hive(value) else {
+        return value.to_string();
+    };
+    let path: Vec<&str> = rest.split('\\').filter(|s| !s.is_empty()).collect();
+    if path.is_empty() {
+        hive.to_string()
+    } else {
+        format!("{}\\{}", hive, path.join("\\").to_lowercase())
+    }
+}
+
+fn is_windows_path(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    let drive = bytes.len() > 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && matches!(bytes[2], b'\\' | b'/');
+    let unc = value.starts_with("\\\\") && value.len() > 2;
+    let env = value.starts_with('%') && value[1..].find('%').is_some_and(|i| i > 0);
+    drive || unc || env
+}
+
+fn is_file_path(value: &str) -> bool {
+    if is_windows_path(value) {
+        return true;
+    }
+    // Absolute Unix path with at least one named segment, so `/24` is not a path
+    value.starts_with('/')
+        && !value.starts_with("//")
+        && value.len() > 1
+        && !value.contains('\0')
+        && value.chars().any(|c| c.is_alphabetic())
+}
+
+/// Windows paths are case-insensitive and accept either separator; Unix paths only
+/// lose repeated and trailing slashes
+fn normalize_file_path(value: &str) -> String {
+    if is_windows_path(value) {
+        let unc = value.starts_with("\\\\");
+        let path: Vec<&str> = value.split(['\\', '/']).filter(|s| !s.is_empty()).collect();
+        let joined = path.join("\\").to_lowercase();
+        return if unc { format!("\\\\{}", joined) } else { joined };
+    }
+
+    let path: Vec<&str> = value.split('/').filter(|s| !s.is_empty()).collect();
+    format!("/{}", path.join("/"))
+}
+
+/// Canonical network form: host bits cleared, and a range that maps onto exactly
+/// one CIDR block collapsed to it. Other ranges keep their `start-end` spelling.
+fn normalize_network(value: &str) -> String {
+    if let Some((start, end)) = parse_ip_range(value) {
+        return match ip_range_to_networks(start, end).as_slice() {
+            [network] => network.to_string(),
+            _ => format!("{}-{}", start, end),
+        };
+    }
+
+    match value.parse::<IpNetwork>() {
+        Ok(network) => IpNetwork::new(network.network(), network.prefix())
+            .map(|n| n.to_string())
+            .unwrap_or_else(|_| value.to_lowercase()),
+        Err(_) => value.to_lowercase(),
+    }
+}
+
+/// Parse an inclusive address range such as `10.0.0.0-10.0.0.255` (both ends must
+/// share an address family and be in ascending order)
+pub fn parse_ip_range(value: &str) -> Option<(IpAddr, IpAddr)> {
+    let (start, end) = value.split_once('-')?;
+    let start = start.trim().parse::<IpAddr>().ok()?;
+    let end = end.trim().parse::<IpAddr>().ok()?;
+
+    match (start, end) {
+        (IpAddr::V4(_), IpAddr::V4(_)) | (IpAddr::V6(_), IpAddr::V6(_)) if start <= end => {
+            Some((start, end))
+        }
+        _ => None,
+    }
+}
+
+/// Split an inclusive address range into the smallest list of CIDR blocks covering it
+pub fn ip_range_to_networks(start: IpAddr, end: IpAddr) -> Vec<IpNetwork> {
+    let (bits, mut next, last) = match (start, end) {
+        (IpAddr::V4(s), IpAddr::V4(e)) => (32u32, u32::from(s) as u128, u32::from(e) as u128),
+        (IpAddr::V6(s), IpAddr::V6(e)) => (128u32, u128::from(s), u128::from(e)),
+        _ => return vec![],
+    };
+    let to_addr = |n: u128| -> IpAddr {
+        if bits == 32 {
+            IpAddr::V4(Ipv4Addr::from(n as u32))
+        } else {
+            IpAddr::V6(Ipv6Addr::from(n))
+        }
+    };
+
+    // Offset of the last address in a block of 2^size addresses
+    let block_end = |size: u32| if size >= 128 { u128::MAX } else { (1u128 << size) - 1 };
+
+    let mut networks = vec![];
+    while next <= last {
+        // Largest block aligned on `next` that does not run past `last`
+        let mut size = next.trailing_zeros().min(bits);
+        while size > 0 && last - next < block_end(size) {
+            size -= 1;
+        }
+        if let Ok(network) = IpNetwork::new(to_addr(next), (bits - size) as u8) {
+            networks.push(network);
+        }
+        match next.checked_add(block_end(size)).and_then(|n| n.checked_add(1)) {
+            Some(n) => next = n,
+            None => break,
+        }
+    }
+    networks
+}
+
+/// Network covered by a normalized IP or CIDR indicator value (a single address
+/// becomes a host network)
+pub fn indicator_network(value: &str, ioc_type: &IocType) -> Option<IpNetwork> {
+    match ioc_type {
+        IocType::Ip => value.parse::<IpAddr>().ok().map(IpNetwork::from),
+        IocType::Cidr => value.parse::<IpNetwork>().ok(),
+        _ => None,
+    }
+}
+
+/// ssdeep digest: `blocksize:hash:hash`, optionally followed by `,"filename"`
+static SSDEEP: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^\d+:[A-Za-z0-9/+]+:[A-Za-z0-9/+]+(,".*")?$"#).unwrap());
+
+/// Detect the hash algorithm from the shape of a digest.
+///
+/// A 32 character hex digest is reported as MD5; imphashes share that shape
+/// and must be declared explicitly.
+pub fn detect_hash_algorithm(value: &str) -> Option<HashAlgorithm> {
+    let is_hex = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_hexdigit());
+
+    if is_hex(value) {
+        return match value.len() {
+            32 => Some(HashAlgorithm::Md5),
+            40 => Some(HashAlgorithm::Sha1),
+            64 => Some(HashAlgorithm::Sha256),
+            70 => Some(HashAlgorithm::Tlsh),
+            128 => Some(HashAlgorithm::Sha512),
+            _ => None,
+        };
+    }
+
+    // TLSH with version prefix (T1 + 70 hex)
+    if value.len() == 72
+        && value.get(..2).is_some_and(|p| p.eq_ignore_ascii_case("t1"))
+        && value.get(2..).is_some_and(is_hex)
+    {
+        return Some(HashAlgorithm::Tlsh);
+    }
+
+    if SSDEEP.is_match(value) {
+        return Some(HashAlgorithm::Ssdeep);
+    }
+
+    None
+}
+
+/// Render a value in defanged form so it cannot be clicked or resolved by accident
+pub fn defang(value: &str, ioc_type: &IocType) -> String {
+    match ioc_type {
+        IocType::Url => {
+            let (scheme, rest) = match value.split_once("://") {
+                Some((scheme, rest)) => (scheme, rest),
+                None => return value.replace('.', "[.]"),
+            };
+            let scheme = match scheme.to_lowercase().as_str() {
+                "http" => "hxxp".to_string(),
+                "https" => "hxxps".to_string(),
+                "ftp" => "fxp".to_string(),
+                other => other.to_string(),
+            };
+            let (host, path) = rest.split_at(rest.find(['/', '?', '#']).unwrap_or(rest.len()));
+            format!("{}://{}{}", scheme, defang_host(host), path)
+        }
+        IocType::Domain | IocType::Ip | IocType::Cidr => defang_host(value),
+        IocType::Email => match value.rsplit_once('@') {
+            Some((local, domain)) => format!("{}[@]{}", local, defang_host(domain)),
+            None => value.to_string(),
+        },
+        IocType::Hash
+        | IocType::Cve
+        | IocType::Asn
+        | IocType::FilePath
+        | IocType::RegistryKey
+        | IocType::Mutex
+        | IocType::UserAgent
+        | IocType::Ja3
+        | IocType::Ja4
+        | IocType::CertFingerprint => value.to_string(),
+    }
+}
+
+/// Bracket the dots of a hostname or IPv4 address, or the colons of an IPv6 address
+fn defang_host(host: &str) -> String {
+    if host.contains('.') {
+        host.replace('.', "[.]")
+    } else {
+        host.replace(':', "[:]")
+    }
+}
+
+/// Defanged scheme spellings (`hxxp`, `hXXps`, `fxp`) and their canonical form
+static DEFANGED_SCHEME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b(hxxp|hxp|fxp)(s?)://").unwrap());
+
+/// Bracketed separators used to defang dots, at-signs, colons and slashes
+static DEFANGED_TOKEN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\[\.\]|\(\.\)|\{\.\}|\[dot\]|\(dot\)|\[@\]|\(@\)|\[at\]|\(at\)|\[:\]|\[://\]|\[/\]")
+        .unwrap()
+});
+
+/// Undo common defanging conventions (`hxxp://`, `1.2.3[.]4`, `evil[.]com`, `user[@]host`)
+pub fn refang(value: &str) -> String {
+    let value = DEFANGED_TOKEN.replace_all(value, |caps: &regex::Captures| {
+        match caps[0].to_lowercase().as_str() {
+            "[.]" | "(.)" | "{.}" | "[dot]" | "(dot)" => ".",
+            "[@]" | "(@)" | "[at]" | "(at)" => "@",
+            "[:]" => ":",
+            "[://]" => "://",
+            _ => "/",
+        }
+        .to_string()
+    });
+
+    DEFANGED_SCHEME
+        .replace_all(&value, |caps: &regex::Captures| {
+            let scheme = if caps[1].eq_ignore_ascii_case("fxp") { "ftp" } else { "http" };
+            format!("{}{}://", scheme, &caps[2])
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refangs_network_indicators() {
+        for (value, ioc_type, normalized) in [
+            ("hxxps://evil[.]com/login", IocType::Url, "https://evil.com/login"),
+            ("hXXp://files(.)example[.]net/a", IocType::Url, "http://files.example.net/a"),
+            ("evil[dot]com", IocType::Domain, "evil.com"),
+            ("198.51.100[.]7", IocType::Ip, "198.51.100.7"),
+            ("billing[at]evil[.]com", IocType::Email, "billing@evil.com"),
+            ("10.0.0[.]0/8", IocType::Cidr, "10.0.0.0/8"),
+        ] {
+            assert_eq!(detect_ioc_type(value), Some(ioc_type.clone()), "{}", value);
+            assert_eq!(normalize_ioc(value, &ioc_type), normalized, "{}", value);
+        }
+    }
+
+    #[test]
+    fn leaves_other_types_as_written() {
+        let path = r"C:\Users\Public\invoice[.]pdf.exe";
+        assert_eq!(detect_ioc_type(path), Some(IocType::FilePath));
+        assert!(normalize_ioc(path, &IocType::FilePath).ends_with("invoice[.]pdf.exe"));
+
+        let mutex = r"Global\hxxp://lock(dot)";
+        assert_eq!(detect_ioc_type(mutex), Some(IocType::Mutex));
+        assert_eq!(normalize_ioc(mutex, &IocType::Mutex), mutex);
+
+        let key = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run\updater[.]exe";
+        assert_eq!(detect_ioc_type(key), Some(IocType::RegistryKey));
+        assert!(normalize_ioc(key, &IocType::RegistryKey).ends_with("updater[.]exe"));
+    }
+
+    #[test]
+    fn detects_extended_types() {
+        for (value, ioc_type) in [
+            ("AS13335", IocType::Asn),
+            ("asn 64500", IocType::Asn),
+            ("t13d1516h2_8daaf6152771_b186095e22b6", IocType::Ja4),
+            (
+                "A0:B1:C2:D3:E4:F5:06:17:28:39:4A:5B:6C:7D:8E:9F:A0:B1:C2:D3",
+                IocType::CertFingerprint,
+            ),
+            (r"Global\MsWinZonesCacheCounterMutexA", IocType::Mutex),
+            (r"HKLM\Software\Microsoft\Windows\CurrentVersion\Run", IocType::RegistryKey),
+            (r"C:\Users\Public\svchost.exe", IocType::FilePath),
+            ("/tmp/.x/kinsing", IocType::FilePath),
+            ("cve-2021-44228", IocType::Cve),
+            ("Mozilla/5.0 (Windows NT 10.0; Win64; x64)", IocType::UserAgent),
+            ("curl/7.68.0", IocType::UserAgent),
+            ("python-requests/2.31.0", IocType::UserAgent),
+        ] {
+            assert_eq!(detect_ioc_type(value), Some(ioc_type), "{}", value);
+        }
+    }
+
+    #[test]
+    fn user_agent_needs_a_product_version_token() {
+        assert_eq!(detect_ioc_type("evil.com/123"), None);
+        assert_eq!(detect_ioc_type("evil.com/1.2"), None);
+        assert_eq!(detect_ioc_type("api/2"), None);
+        assert_eq!(detect_ioc_type("hxxp://evil[.]com/5.0"), Some(IocType::Url));
+        assert_eq!(detect_ioc_type("10.0.0.0/8"), Some(IocType::Cidr));
+        assert_eq!(detect_ioc_type("e7d705a3286e19ea42f587b344ee6865"), Some(IocType::Hash));
+    }
+
+    #[test]
+    fn detects_hash_algorithms() {
+        let tlsh = "A5B4C3D2E1F0A9B8C7D6E5F4A3B2C1D0E9F8A7B6C5D4E3F2A1B0C9D8E7F6A5B4C3D2E1";
+        for (value, algorithm) in [
+            ("d41d8cd98f00b204e9800998ecf8427e", HashAlgorithm::Md5),
+            ("DA39A3EE5E6B4B0D3255BFEF95601890AFD80709", HashAlgorithm::Sha1),
+            ("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855", HashAlgorithm::Sha256),
+            (
+                "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e",
+                HashAlgorithm::Sha512,
+            ),
+            (tlsh, HashAlgorithm::Tlsh),
+            (&format!("T1{}", tlsh), HashAlgorithm::Tlsh),
+            ("3072:Z3z1y/Nc1CgWPCb3YKqfUgxz8wZp+3VfvEY5:Z3z1y/Nc1CgWPCb3Y", HashAlgorithm::Ssdeep),
+        ] {
+            assert_eq!(detect_hash_algorithm(value), Some(algorithm), "{}", value);
+        }
+
+        for value in ["", "d41d8cd98f00b204e9800998ecf8427", "g41d8cd98f00b204e9800998ecf8427e", "T2deadbeef"] {
+            assert_eq!(detect_hash_algorithm(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn canonicalizes_urls() {
+        for (value, canonical) in [
+            ("HTTP://Evil.COM:80/a/./b/../c/?utm_source=x&b=2&a=1#frag", "http://evil.com/a/c?a=1&b=2"),
+            ("https://evil.com:443/", "https://evil.com/"),
+            ("https://evil.com.:8443/login/", "https://evil.com:8443/login"),
+            ("https://xn--80ak6aa92e.com/", "https://xn--80ak6aa92e.com/"),
+            ("https://пример.рф/путь", "https://xn--e1afmkfd.xn--p1ai/%D0%BF%D1%83%D1%82%D1%8C"),
+            ("http://evil.com/?fbclid=abc&gclid=def", "http://evil.com/"),
+            ("http://evil.com/dl?id=7&UTM_Medium=mail", "http://evil.com/dl?id=7"),
+            ("http://evil.com/dl?b=1&a", "http://evil.com/dl?a&b=1"),
+            ("http://evil.com/dl?token&utm_source=x", "http://evil.com/dl?token"),
+            ("http://evil.com/s?q=a%20b&p=c+d&r=%2B", "http://evil.com/s?p=c+d&q=a%20b&r=%2B"),
+            ("http://evil.com/s?q=%E2%9C%93&utm%5Fcampaign=x&&", "http://evil.com/s?q=%E2%9C%93"),
+        ] {
+            assert_eq!(canonicalize_url(value).as_deref(), Some(canonical), "{}", value);
+        }
+
+        assert_eq!(canonicalize_url("not a url"), None);
+        assert_eq!(canonicalize_url("//evil.com/x"), None);
+    }
+
+    fn networks(start: &str, end: &str) -> Vec<String> {
+        ip_range_to_networks(start.parse().unwrap(), end.parse().unwrap())
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn splits_ranges_into_covering_networks() {
+        assert_eq!(networks("10.1.2.3", "10.1.2.3"), vec!["10.1.2.3/32"]);
+        assert_eq!(networks("255.255.255.255", "255.255.255.255"), vec!["255.255.255.255/32"]);
+        assert_eq!(networks("10.0.0.0", "10.0.0.255"), vec!["10.0.0.0/24"]);
+        assert_eq!(networks("0.0.0.0", "255.255.255.255"), vec!["0.0.0.0/0"]);
+        assert_eq!(networks("::", "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"), vec!["::/0"]);
+        assert_eq!(
+            networks("10.0.0.5", "10.0.0.20"),
+            vec!["10.0.0.5/32", "10.0.0.6/31", "10.0.0.8/29", "10.0.0.16/30", "10.0.0.20/32"]
+        );
+        assert_eq!(
+            networks("2001:db8::1", "2001:db8::10"),
+            vec!["2001:db8::1/128", "2001:db8::2/127", "2001:db8::4/126", "2001:db8::8/125", "2001:db8::10/128"]
+        );
+        assert_eq!(networks("2001:db8::", "2001:db8:0:ffff:ffff:ffff:ffff:ffff"), vec!["2001:db8::/48"]);
+
+        assert!(networks("10.0.0.9", "10.0.0.1").is_empty());
+        assert!(networks("10.0.0.1", "::1").is_empty());
+        assert_eq!(
+            parse_ip_range("10.0.0.0 - 10.0.0.255").map(|(s, e)| ip_range_to_networks(s, e).len()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn defang_round_trips() {
+        for (value, ioc_type) in [
+            ("https://evil.com/login?a=1", IocType::Url),
+            ("evil.com", IocType::Domain),
+            ("198.51.100.7", IocType::Ip),
+            ("billing@evil.com", IocType::Email),
+        ] {
+            let defanged = defang(value, &ioc_type);
+            assert_ne!(defanged, value);
+            assert_eq!(normalize_ioc(&defanged, &ioc_type), value);
+        }
+    }
+}
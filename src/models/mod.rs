@@ -1,15 +1,16 @@
 //! Core data models for Threat Intelligence
 
 use chrono::{DateTime, Utc};
+use ipnetwork::IpNetwork;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 use validator::Validate;
 
 /// Types of Indicators of Compromise
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
-#[sqlx(type_name = "ioc_type", rename_all = "lowercase")]
-#[serde(rename_all = "lowercase")]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, sqlx::Type)]
+#[sqlx(type_name = "ioc_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 pub enum IocType {
     Ip,
     Domain,
@@ -17,6 +18,15 @@ pub enum IocType {
     Hash,
     Email,
     Cve,
+    Cidr,
+    Asn,              // Autonomous system number (AS13335)
+    FilePath,
+    RegistryKey,
+    Mutex,
+    UserAgent,
+    Ja3,              // JA3/JA3S TLS client/server fingerprint (MD5)
+    Ja4,              // JA4 TLS client fingerprint
+    CertFingerprint,  // X.509 certificate SHA1/SHA256 thumbprint
 }
 
 impl std::fmt::Display for IocType {
@@ -28,12 +38,35 @@ impl std::fmt::Display for IocType {
             IocType::Hash => write!(f, "hash"),
             IocType::Email => write!(f, "email"),
             IocType::Cve => write!(f, "cve"),
+            IocType::Cidr => write!(f, "cidr"),
+            IocType::Asn => write!(f, "asn"),
+            IocType::FilePath => write!(f, "file_path"),
+            IocType::RegistryKey => write!(f, "registry_key"),
+            IocType::Mutex => write!(f, "mutex"),
+            IocType::UserAgent => write!(f, "user_agent"),
+            IocType::Ja3 => write!(f, "ja3"),
+            IocType::Ja4 => write!(f, "ja4"),
+            IocType::CertFingerprint => write!(f, "cert_fingerprint"),
         }
     }
 }
 
+/// Digest algorithm of a hash indicator
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, sqlx::Type)]
+#[sqlx(type_name = "hash_algorithm", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+    Ssdeep,
+    Tlsh,
+    Imphash,
+}
+
 /// Threat severity levels
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, sqlx::Type)]
 #[sqlx(type_name = "severity", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum Severity {
@@ -80,6 +113,7 @@ pub struct IocSource {
     pub last_fetch: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub sync_cursor: Option<String>,  // Incremental feed position
 }
 
 /// Main IOC record
@@ -99,6 +133,51 @@ pub struct Indicator {
     pub source_ids: Vec<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub hash_algorithm: Option<HashAlgorithm>,  // Only set for hash indicators
+    pub file_object_id: Option<Uuid>,           // File shared by linked hash indicators
+    pub network: Option<IpNetwork>,             // Only set for CIDR indicators
+    pub registrable_domain: Option<String>,     // eTLD+1 of domain and URL indicators
+}
+
+/// A file known under several hashes (MD5, SHA1, SHA256, ...)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FileObject {
+    pub id: Uuid,
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
+    pub sha256: Option<String>,
+    pub sha512: Option<String>,
+    pub ssdeep: Option<String>,
+    pub tlsh: Option<String>,
+    pub imphash: Option<String>,
+    pub size: Option<i64>,
+    pub file_type: Option<String>,
+    pub names: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Hashes and metadata of a file as reported by an enrichment provider
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FileHashes {
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
+    pub sha256: Option<String>,
+    pub sha512: Option<String>,
+    pub ssdeep: Option<String>,
+    pub tlsh: Option<String>,
+    pub imphash: Option<String>,
+    pub size: Option<i64>,
+    pub file_type: Option<String>,
+    #[serde(default)]
+    pub names: Vec<String>,
+}
+
+/// File object together with every hash indicator linked to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileRecord {
+    pub file: FileObject,
+    pub indicators: Vec<Indicator>,
 }
 
 /// Enrichment data for an IOC
@@ -111,6 +190,97 @@ pub struct Enrichment {
     pub provider: String,
     pub fetched_at: DateTime<Utc>,
     pub expires_at: Option<DateTime<Utc>>,
+    pub is_latest: bool,                   // Newest snapshot for this indicator/type/provider
+    pub changes: Option<serde_json::Value>, // Material changes from the previous snapshot
+}
+
+/// How a watched enrichment field moved between snapshots
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Increased,
+    Decreased,
+    Added,    // New list entries (A records, name servers, ...)
+    Changed,
+}
+
+/// A material difference between two enrichment snapshots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnrichmentChange {
+    pub field: String,  // Dotted path, e.g. analysis.malicious
+    pub kind: ChangeKind,
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+}
+
+/// How stored results are used when enriching
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, sqlx::Type)]
+#[sqlx(type_name = "enrichment_mode", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EnrichMode {
+    Expired,  // Call only providers whose stored result has expired
+    Cached,   // Call providers without a fresh stored result
+    Force,    // Call every provider, ignoring stored and cached results
+}
+
+/// Queue priority of an enrichment job; higher runs first
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, sqlx::Type)]
+#[repr(i16)]
+#[serde(rename_all = "lowercase")]
+pub enum EnrichmentPriority {
+    Refresh = 0,   // Scheduled refresh of expired results
+    Feed = 10,     // Bulk imports and feed collectors
+    Sighting = 20, // New sighting of a known indicator
+    Manual = 30,   // Created or requested by a user
+}
+
+/// Enrichment job lifecycle; completed jobs are deleted
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "enrichment_job_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EnrichmentJobStatus {
+    Pending,
+    Running,
+    Dead,  // Gave up after repeated failures
+}
+
+/// Queued enrichment of one indicator
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct EnrichmentJob {
+    pub id: Uuid,
+    pub indicator_id: Uuid,
+    pub priority: EnrichmentPriority,
+    pub mode: EnrichMode,
+    pub status: EnrichmentJobStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub requested_by: String,
+    pub run_after: DateTime<Utc>,
+    pub locked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    // Follow-up run requested while the job was running
+    pub rerun_mode: Option<EnrichMode>,
+    pub rerun_priority: Option<EnrichmentPriority>,
+    pub rerun_requested_by: Option<String>,
+}
+
+/// Pending jobs at one priority
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct QueuePriorityCount {
+    pub priority: EnrichmentPriority,
+    pub pending: i64,
+    pub oldest: Option<DateTime<Utc>>,
+}
+
+/// Enrichment queue overview
+#[derive(Debug, Clone, Serialize)]
+pub struct EnrichmentQueueStats {
+    pub pending: i64,
+    pub running: i64,
+    pub dead: i64,
+    pub by_priority: Vec<QueuePriorityCount>,
+    pub jobs: Vec<EnrichmentJob>,
 }
 
 /// GeoIP enrichment data
@@ -139,9 +309,18 @@ pub struct WhoisData {
     pub updated_date: Option<DateTime<Utc>>,
     pub name_servers: Vec<String>,
     pub status: Vec<String>,
+    pub privacy_protected: bool,     // Registrant hidden behind a proxy or redacted
+    pub whois_server: Option<String>, // Server that gave the most specific answer
     pub raw: Option<String>,
 }
 
+impl WhoisData {
+    /// Days since the domain was registered
+    pub fn domain_age_days(&self) -> Option<i64> {
+        self.creation_date.map(|created| (Utc::now() - created).num_days())
+    }
+}
+
 /// DNS enrichment data
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct DnsData {
@@ -150,7 +329,121 @@ pub struct DnsData {
     pub mx_records: Vec<String>,
     pub txt_records: Vec<String>,
     pub ns_records: Vec<String>,
-    pub cname_records: Vec<String>,
+    pub cname_records: Vec<String>,  // CNAME chain in resolution order
+    pub soa: Option<SoaRecord>,
+    pub caa_records: Vec<CaaRecord>,
+    pub srv_records: Vec<SrvRecord>,
+    pub spf: Option<SpfPolicy>,
+    pub dmarc: Option<DmarcPolicy>,
+    pub verifications: Vec<DomainVerification>,
+    pub dnssec: DnssecStatus,
+}
+
+/// Start of authority of a zone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoaRecord {
+    pub mname: String,
+    pub rname: String,
+    pub serial: u32,
+    pub refresh: i32,
+    pub retry: i32,
+    pub expire: i32,
+    pub minimum: u32,
+}
+
+/// Certificate authority authorization record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaaRecord {
+    pub critical: bool,
+    pub tag: String,    // issue, issuewild, iodef
+    pub value: String,
+}
+
+/// Service location record, e.g. `_sip._tcp`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SrvRecord {
+    pub service: String,
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u16,
+    pub target: String,
+}
+
+/// Parsed `v=spf1` TXT record
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpfPolicy {
+    pub record: String,
+    pub includes: Vec<String>,
+    pub ip4: Vec<String>,
+    pub ip6: Vec<String>,
+    pub redirect: Option<String>,
+    pub all: Option<String>,        // Catch-all term as written (-all, ~all, ...)
+    pub mechanisms: Vec<String>,    // Remaining terms (a, mx, exists:, ...)
+}
+
+/// Parsed `_dmarc` TXT record
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DmarcPolicy {
+    pub record: String,
+    pub policy: Option<String>,            // p=
+    pub subdomain_policy: Option<String>,  // sp=
+    pub percentage: Option<u8>,            // pct=
+    pub rua: Vec<String>,
+    pub ruf: Vec<String>,
+    pub adkim: Option<String>,
+    pub aspf: Option<String>,
+}
+
+/// Domain ownership token published for a third-party service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainVerification {
+    pub service: String,
+    pub token: String,
+}
+
+/// DNSSEC state reported by the upstream resolver
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DnssecStatus {
+    Secure,         // Answer carried the authenticated-data flag
+    Insecure,       // Answered without it: unsigned zone or non-validating upstream
+    #[default]
+    Indeterminate,  // No usable answer (timeout, SERVFAIL on bogus signatures, ...)
+}
+
+/// Services a host exposes to the internet, from Shodan or Censys
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HostExposure {
+    pub ip: String,
+    pub ports: Vec<u16>,
+    pub services: Vec<ExposedService>,
+    pub vulns: Vec<String>,  // CVE identifiers
+    pub hostnames: Vec<String>,
+    pub os: Option<String>,
+    pub org: Option<String>,
+    pub asn: Option<String>,
+    pub last_update: Option<String>,
+}
+
+/// One open port and what answered on it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExposedService {
+    pub port: u16,
+    pub transport: String,  // tcp, udp
+    pub product: Option<String>,
+    pub version: Option<String>,
+    pub banner: Option<String>,  // Truncated
+    pub certificate: Option<TlsCertificate>,
+}
+
+/// Leaf certificate presented by a TLS service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsCertificate {
+    pub subject: Option<String>,
+    pub issuer: Option<String>,
+    pub sha256: Option<String>,
+    pub sha1: Option<String>,
+    pub not_after: Option<DateTime<Utc>>,
 }
 
 /// Sighting - when an IOC was observed
@@ -170,12 +463,28 @@ pub struct CreateIndicatorRequest {
     #[validate(length(min = 1, max = 2048))]
     pub value: String,
     pub ioc_type: Option<IocType>,  // Auto-detect if not provided
+    #[serde(default)]
+    pub hash_algorithm: Option<HashAlgorithm>,  // Auto-detect if not provided (required for imphash)
     pub severity: Option<Severity>,
     pub confidence: Option<i32>,
     pub tlp: Option<Tlp>,
     pub tags: Option<Vec<String>>,
     pub source: Option<String>,
     pub expiration_days: Option<i32>,
+    #[serde(default)]
+    pub context: Option<ContextInput>,  // Report the indicator was published in
+}
+
+/// Report context supplied with an indicator
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ContextInput {
+    pub report_id: String,
+    pub report_name: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub report_urls: Vec<String>,
+    #[serde(default)]
+    pub attack_ids: Vec<String>,
 }
 
 /// API response for IOC queries
@@ -185,6 +494,245 @@ pub struct IndicatorResponse {
     pub enrichments: Vec<Enrichment>,
     pub sightings_count: i64,
     pub related_indicators: Vec<Indicator>,
+    pub relations: Vec<IndicatorRelation>,
+    pub context: Vec<IndicatorContext>,
+    pub file: Option<FileObject>,
+    pub history: Vec<AuditEvent>,
+}
+
+/// Indicators stored under one host of a registrable domain
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SubdomainRollup {
+    pub host: String,
+    pub indicators: i64,
+    pub ioc_types: Vec<String>,
+    pub max_threat_score: Option<i32>,
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+/// Everything known under a registrable domain, grouped by host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainRollup {
+    pub registrable_domain: String,
+    pub public_suffix: Option<String>,
+    pub total: i64,
+    pub subdomains: Vec<SubdomainRollup>,
+}
+
+/// Kind of link between two indicators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationType {
+    HostedOn,            // URL -> domain or IP serving it
+    ResolvesTo,          // Domain -> IP from A/AAAA records
+    AliasOf,             // Domain -> CNAME target
+    UsesNameserver,      // Domain -> NS host
+    UsesMailServer,      // Domain -> MX host
+    ReverseDns,          // IP -> PTR name
+    PresentsCertificate, // IP -> certificate fingerprint served on one of its ports
+    HasVulnerability,    // IP -> CVE reported for its services
+    DownloadsFrom,       // Honeypot attacker IP -> URL it fetched a payload from
+    Dropped,             // Honeypot attacker IP -> hash of a payload it dropped
+    Delivers,            // URL -> hash of the payload it served
+}
+
+impl RelationType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RelationType::HostedOn => "hosted_on",
+            RelationType::ResolvesTo => "resolves_to",
+            RelationType::AliasOf => "alias_of",
+            RelationType::UsesNameserver => "uses_nameserver",
+            RelationType::UsesMailServer => "uses_mail_server",
+            RelationType::ReverseDns => "reverse_dns",
+            RelationType::PresentsCertificate => "presents_certificate",
+            RelationType::HasVulnerability => "has_vulnerability",
+            RelationType::DownloadsFrom => "downloads_from",
+            RelationType::Dropped => "dropped",
+            RelationType::Delivers => "delivers",
+        }
+    }
+}
+
+/// Directed link between two indicators
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct IndicatorRelation {
+    pub id: Uuid,
+    pub source_indicator_id: Uuid,
+    pub target_indicator_id: Uuid,
+    pub relation_type: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One honeypot event as recorded for session analytics
+#[derive(Debug, Clone)]
+pub struct HoneypotObservation {
+    pub session_id: String,
+    /// Identifies the event within its session across replays
+    pub event_key: String,
+    pub source_ip: String,
+    pub protocol: String,
+    pub category: String,
+    pub observed_at: DateTime<Utc>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub command: Option<String>,
+    pub download_urls: Vec<String>,
+}
+
+/// Honeypot session aggregated from its events
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct HoneypotSession {
+    pub session_id: String,
+    pub source_ip: String,
+    pub protocol: String,
+    pub category: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub event_count: i32,
+    pub login_attempts: i32,
+    pub command_count: i32,
+    pub download_urls: Vec<String>,
+}
+
+/// Credentials or command seen in a honeypot session
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct HoneypotSessionEvent {
+    pub id: Uuid,
+    pub session_id: String,
+    pub observed_at: DateTime<Utc>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub command: Option<String>,
+}
+
+/// Session with its events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoneypotSessionDetail {
+    pub session: HoneypotSession,
+    pub events: Vec<HoneypotSessionEvent>,
+}
+
+/// How often a credential pair was tried
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CredentialStat {
+    pub username: String,
+    pub password: String,
+    pub attempts: i64,
+    pub sessions: i64,
+    pub source_ips: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// How often a command was run
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CommandStat {
+    pub command: String,
+    pub executions: i64,
+    pub sessions: i64,
+    pub source_ips: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Honeypot activity in one time bucket
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct HoneypotActivity {
+    pub bucket: DateTime<Utc>,
+    pub sessions: i64,
+    pub source_ips: i64,
+    pub login_attempts: i64,
+    pub commands: i64,
+}
+
+/// Report context stored for an indicator, one row per report
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct IndicatorContext {
+    pub id: Uuid,
+    pub indicator_id: Uuid,
+    pub source_id: Option<Uuid>,
+    pub report_id: String,
+    pub report_name: Option<String>,
+    pub description: Option<String>,
+    pub report_urls: Vec<String>,
+    pub attack_ids: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// DNS record type of a passive DNS observation
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, sqlx::Type)]
+#[sqlx(type_name = "pdns_rrtype", rename_all = "UPPERCASE")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum PdnsRecordType {
+    A,
+    Aaaa,
+    Cname,
+    Ns,
+    Mx,
+    Ptr,
+}
+
+impl PdnsRecordType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PdnsRecordType::A => "A",
+            PdnsRecordType::Aaaa => "AAAA",
+            PdnsRecordType::Cname => "CNAME",
+            PdnsRecordType::Ns => "NS",
+            PdnsRecordType::Mx => "MX",
+            PdnsRecordType::Ptr => "PTR",
+        }
+    }
+
+    /// Relation recorded between indicators for this record type
+    pub fn relation(&self) -> RelationType {
+        match self {
+            PdnsRecordType::A | PdnsRecordType::Aaaa => RelationType::ResolvesTo,
+            PdnsRecordType::Cname => RelationType::AliasOf,
+            PdnsRecordType::Ns => RelationType::UsesNameserver,
+            PdnsRecordType::Mx => RelationType::UsesMailServer,
+            PdnsRecordType::Ptr => RelationType::ReverseDns,
+        }
+    }
+}
+
+/// A DNS answer seen during enrichment
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DnsObservation {
+    pub rrname: String,
+    pub rrtype: PdnsRecordType,
+    pub rdata: String,
+}
+
+/// Passive DNS history of one name/answer pairing
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PassiveDnsRecord {
+    pub id: Uuid,
+    pub rrname: String,
+    pub rrtype: PdnsRecordType,
+    pub rdata: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub count: i64,
+}
+
+/// Address churn of a name over a recent window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FastFluxAssessment {
+    pub window_hours: i64,
+    pub distinct_ips: i64,
+    pub distinct_networks: i64,  // Distinct /16 (IPv4) or /32 (IPv6) networks
+    pub suspected: bool,
+}
+
+/// Forward and reverse passive DNS history of a value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassiveDnsResponse {
+    pub value: String,
+    pub forward: Vec<PassiveDnsRecord>,  // Records whose name is the value
+    pub reverse: Vec<PassiveDnsRecord>,  // Records answering with the value
+    pub fast_flux: Option<FastFluxAssessment>,  // Domains only
 }
 
 /// Bulk import request
@@ -203,9 +751,42 @@ pub struct BulkImportResponse {
     pub created: usize,
     pub updated: usize,
     pub failed: usize,
+    pub suppressed: usize,  // Rejected by the allowlist
     pub errors: Vec<String>,
 }
 
+/// Batch lookup request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchLookupRequest {
+    pub values: Vec<String>,
+    #[serde(default)]
+    pub record_sightings: bool,
+    pub source: Option<String>,  // Sighting source, defaults to "batch_lookup"
+}
+
+/// Result for a single value in a batch lookup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchLookupResult {
+    pub value: String,
+    pub ioc_type: Option<IocType>,
+    pub normalized: Option<String>,
+    pub found: bool,
+    pub indicator: Option<Indicator>,
+    pub enrichments: Vec<Enrichment>,
+    pub threat_score: Option<i32>,
+    /// Stored networks containing the value, most specific first
+    pub matching_networks: Vec<Indicator>,
+    pub error: Option<String>,
+}
+
+/// Batch lookup response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchLookupResponse {
+    pub total: usize,
+    pub found: usize,
+    pub results: Vec<BatchLookupResult>,
+}
+
 /// Search/filter parameters
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct IndicatorFilter {
@@ -218,6 +799,8 @@ pub struct IndicatorFilter {
     pub first_seen_after: Option<DateTime<Utc>>,
     pub first_seen_before: Option<DateTime<Utc>>,
     pub search: Option<String>,
+    /// Every domain and URL under this registrable domain (subdomain rollup)
+    pub registrable_domain: Option<String>,
     pub page: Option<i64>,
     pub per_page: Option<i64>,
 }
@@ -255,4 +838,195 @@ pub struct DashboardStats {
     pub top_tags: Vec<(String, i64)>,
     pub recent_sightings: i64,
 }
+
+/// Kind of mutation recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    Sighting,
+    Enrichment,
+    ScoreChange,
+    SourceChange,
+    EnrichmentChange,
+    AllowlistCreate,
+    AllowlistDelete,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::Sighting => "sighting",
+            AuditAction::Enrichment => "enrichment",
+            AuditAction::ScoreChange => "score_change",
+            AuditAction::SourceChange => "source_change",
+            AuditAction::EnrichmentChange => "enrichment_change",
+            AuditAction::AllowlistCreate => "allowlist_create",
+            AuditAction::AllowlistDelete => "allowlist_delete",
+        }
+    }
+}
+
+/// Where a mutation originated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditOrigin {
+    Api,
+    Collector(String),
+    Scheduler,
+}
+
+impl std::fmt::Display for AuditOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditOrigin::Api => write!(f, "api"),
+            AuditOrigin::Collector(name) => write!(f, "collector:{}", name),
+            AuditOrigin::Scheduler => write!(f, "scheduler"),
+        }
+    }
+}
+
+/// Who is making a change and why, attached to every repository mutation
+#[derive(Debug, Clone)]
+pub struct AuditContext {
+    pub actor: String,
+    /// False when `actor` was supplied by the client and not authenticated
+    pub actor_verified: bool,
+    pub origin: AuditOrigin,
+    pub request_id: Option<String>,
+    pub reason: Option<String>,
+}
+
+impl AuditContext {
+    /// Context for a change made through the REST API. The actor is whatever
+    /// the caller claims to be, so it is recorded as unverified.
+    pub fn api(actor: impl Into<String>, request_id: Option<String>) -> Self {
+        Self {
+            actor: actor.into(),
+            actor_verified: false,
+            origin: AuditOrigin::Api,
+            request_id,
+            reason: None,
+        }
+    }
+
+    /// Context for a change made by a feed collector
+    pub fn collector(name: &str) -> Self {
+        Self {
+            actor: name.to_string(),
+            actor_verified: true,
+            origin: AuditOrigin::Collector(name.to_string()),
+            request_id: None,
+            reason: None,
+        }
+    }
+
+    /// Context for a change made by a scheduled job
+    pub fn scheduler(job: &str) -> Self {
+        Self {
+            actor: job.to_string(),
+            actor_verified: true,
+            origin: AuditOrigin::Scheduler,
+            request_id: None,
+            reason: None,
+        }
+    }
+}
+
+/// Audit log entry
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AuditEvent {
+    pub id: Uuid,
+    pub indicator_id: Option<Uuid>,
+    pub source_id: Option<Uuid>,
+    pub action: String,
+    pub actor: String,
+    pub actor_verified: bool,
+    pub origin: String,
+    pub request_id: Option<String>,
+    pub reason: Option<String>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub diff: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Audit log query parameters
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AuditFilter {
+    pub indicator_id: Option<Uuid>,
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+pub mod enrichment_changes;
+pub mod ioc_extract;
 pub mod ioc_utils;
+
+/// How an allowlist entry is matched against indicator values
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "allowlist_match", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AllowlistMatch {
+    Exact,   // Normalized value equality
+    Cidr,    // IPs, networks and URL hosts inside a network
+    Suffix,  // Domain, URL host or email domain equal to or below a domain
+    Regex,   // Regular expression over the normalized value
+}
+
+/// What happens to an indicator matching the allowlist
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, sqlx::Type)]
+#[sqlx(type_name = "allowlist_action", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AllowlistAction {
+    Tag,          // Store it, tagged `allowlisted`
+    CapSeverity,  // Store it with severity lowered to `max_severity`
+    Reject,       // Do not store it
+}
+
+/// Allowlist entry managed through the API
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AllowlistEntry {
+    pub id: Uuid,
+    pub match_type: AllowlistMatch,
+    pub value: String,
+    pub ioc_type: Option<IocType>,  // Restrict the entry to one indicator type
+    pub action: AllowlistAction,
+    pub max_severity: Option<Severity>,
+    pub comment: Option<String>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// API request to add an allowlist entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateAllowlistEntryRequest {
+    pub match_type: AllowlistMatch,
+    pub value: String,
+    pub ioc_type: Option<IocType>,
+    #[serde(default = "default_allowlist_action")]
+    pub action: AllowlistAction,
+    pub max_severity: Option<Severity>,
+    pub comment: Option<String>,
+}
+
+fn default_allowlist_action() -> AllowlistAction {
+    AllowlistAction::Reject
+}
+
+/// Indicators suppressed per feed, list and action
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SuppressionSummary {
+    pub feed: String,
+    pub list_name: String,
+    pub action: AllowlistAction,
+    pub suppressed: i64,
+    pub last_suppressed_at: DateTime<Utc>,
+    pub examples: Vec<String>,
+}
@@ -1,22 +1,39 @@
 //! Database storage layer for threat intelligence
 
 use anyhow::{Context, Result};
-use chrono::{Duration, Utc};
-use crate::models::ioc_utils::{detect_ioc_type, normalize_ioc};
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+use crate::allowlist::{Allowlist, AllowlistHit, AllowlistRejection, AllowlistRule, ALLOWLISTED_TAG};
+use crate::models::enrichment_changes::{material_changes, same_snapshot};
+use crate::models::ioc_utils::{
+    detect_hash_algorithm, detect_ioc_type, indicator_network, indicator_registrable_domain,
+    ip_range_to_networks, normalize_ioc, parse_ip_range, refang, registrable_domain, url_host,
+};
+use ipnetwork::IpNetwork;
+use serde_json::Value;
 use sqlx::postgres::PgPoolOptions;
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool, Postgres, QueryBuilder, Transaction};
 use uuid::Uuid;
 
 use crate::models::{
-    CreateIndicatorRequest, DashboardStats, Enrichment, Indicator, IndicatorFilter,
-    IocSource, IocType, PaginatedResponse, Severity, Sighting, Tlp,
+    AllowlistAction, AllowlistEntry, AuditAction, AuditContext, AuditEvent, AuditFilter,
+    CommandStat, CreateAllowlistEntryRequest, CreateIndicatorRequest, CredentialStat, DashboardStats, DnsObservation, EnrichMode, Enrichment,
+    EnrichmentJob, EnrichmentJobStatus, EnrichmentPriority, EnrichmentQueueStats, FileHashes,
+    FileObject, FileRecord, HashAlgorithm, HoneypotActivity, HoneypotObservation, HoneypotSession, HoneypotSessionDetail, HoneypotSessionEvent,
+    HostExposure, Indicator, IndicatorContext, IndicatorFilter, IndicatorRelation, IocSource,
+    IocType, PaginatedResponse, PassiveDnsRecord, QueuePriorityCount, RelationType, Severity, Sighting, SubdomainRollup,
+    SuppressionSummary, Tlp,
 };
 // use crate::models::ioc_utils::{detect_ioc_type, normalize_ioc};
 
+/// Superseded enrichment snapshots kept per indicator, type and provider
+const MAX_ENRICHMENT_HISTORY: i64 = 50;
+
 /// Database repository for threat intelligence
 #[derive(Clone)]
 pub struct ThreatIntelRepo {
     pool: PgPool,
+    allowlist: Arc<Allowlist>,
 }
 
 impl ThreatIntelRepo {
@@ -28,7 +45,10 @@ impl ThreatIntelRepo {
             .await
             .context("Failed to connect to database")?;
 
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            allowlist: Arc::new(Allowlist::default()),
+        })
     }
 
     /// Get the connection pool
@@ -36,57 +56,110 @@ impl ThreatIntelRepo {
         &self.pool
     }
 
+    /// Allowlist consulted on every indicator write
+    pub fn allowlist(&self) -> &Allowlist {
+        &self.allowlist
+    }
+
     /// Run database migrations
     pub async fn migrate(&self) -> Result<()> {
         sqlx::migrate!("./migrations")
             .run(&self.pool)
             .await
             .context("Failed to run migrations")?;
+        self.backfill_registrable_domains().await?;
         Ok(())
     }
 
-    // ==================== Indicators ====================
-
-    /// Create or update an indicator
-    pub async fn upsert_indicator(&self, req: &CreateIndicatorRequest, source_id: Option<Uuid>) -> Result<Indicator> {
-        let ioc_type = req.ioc_type.clone().or_else(|| detect_ioc_type(&req.value))
-            .ok_or_else(|| anyhow::anyhow!("Could not detect IOC type for: {}", req.value))?;
-        
-        let normalized_value = normalize_ioc(&req.value, &ioc_type);
-        let now = Utc::now();
-        let expiration = req.expiration_days.map(|days| now + Duration::days(days as i64));
-        
-        let indicator = sqlx::query_as::<_, Indicator>(
-            r#"
-            INSERT INTO indicators (
-                id, ioc_type, value, severity, confidence, threat_score, tlp,
-                first_seen, last_seen, expiration, tags, source_ids, created_at, updated_at
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8, $9, $10, $11, $8, $8)
-            ON CONFLICT (ioc_type, value) DO UPDATE SET
-                severity = CASE WHEN EXCLUDED.severity > indicators.severity THEN EXCLUDED.severity ELSE indicators.severity END,
-                confidence = GREATEST(indicators.confidence, EXCLUDED.confidence),
-                last_seen = EXCLUDED.last_seen,
-                tags = array_cat(indicators.tags, EXCLUDED.tags),
-                source_ids = array_cat(indicators.source_ids, EXCLUDED.source_ids),
-                updated_at = EXCLUDED.updated_at
-            RETURNING *
-            "#,
+    /// Fill `registrable_domain` for domain and URL indicators stored before it existed
+    async fn backfill_registrable_domains(&self) -> Result<()> {
+        let rows: Vec<(Uuid, IocType, String)> = sqlx::query_as(
+            "SELECT id, ioc_type, value FROM indicators
+             WHERE registrable_domain IS NULL AND ioc_type IN ('domain', 'url')"
         )
-        .bind(Uuid::new_v4())
-        .bind(&ioc_type)
-        .bind(&normalized_value)
-        .bind(req.severity.clone().unwrap_or(Severity::Unknown))
-        .bind(req.confidence.unwrap_or(50))
-        .bind(req.confidence.unwrap_or(50)) // Initial threat_score = confidence
-        .bind(req.tlp.clone().unwrap_or(Tlp::Amber))
-        .bind(now)
-        .bind(expiration)
-        .bind(&req.tags.clone().unwrap_or_default())
-        .bind(&source_id.map(|id| vec![id]).unwrap_or_default())
-        .fetch_one(&self.pool)
+        .fetch_all(&self.pool)
         .await
-        .context("Failed to upsert indicator")?;
+        .context("Failed to fetch indicators without registrable domain")?;
+
+        let (ids, domains): (Vec<Uuid>, Vec<String>) = rows
+            .into_iter()
+            .filter_map(|(id, ioc_type, value)| {
+                indicator_registrable_domain(&value, &ioc_type).map(|domain| (id, domain))
+            })
+            .unzip();
+
+        for (ids, domains) in ids.chunks(1000).zip(domains.chunks(1000)) {
+            sqlx::query(
+                r#"
+                UPDATE indicators i SET registrable_domain = u.domain
+                FROM UNNEST($1::uuid[], $2::text[]) AS u(id, domain)
+                WHERE i.id = u.id
+                "#
+            )
+            .bind(ids)
+            .bind(domains)
+            .execute(&self.pool)
+            .await
+            .context("Failed to backfill registrable domains")?;
+        }
+
+        Ok(())
+    }
+
+    // ==================== Indicators ====================
+
+    /// Create or update an indicator. A URL also upserts its host (domain or IP)
+    /// as its own indicator, linked to the URL as `hosted_on`.
+    pub async fn upsert_indicator(
+        &self,
+        req: &CreateIndicatorRequest,
+        source_id: Option<Uuid>,
+        ctx: &AuditContext,
+    ) -> Result<Indicator> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let indicator = match upsert_indicator_in_tx(&mut tx, &self.allowlist, req, source_id, ctx).await {
+            Ok(indicator) => indicator,
+            Err(e) => {
+                if let Some(rejection) = e.downcast_ref::<AllowlistRejection>() {
+                    let feed = feed_name(req, ctx);
+                    record_suppression(&self.pool, &feed, &rejection.ioc_type, &rejection.value, &rejection.hit)
+                        .await?;
+                }
+                return Err(e);
+            }
+        };
+
+        if indicator.ioc_type == IocType::Url
+            && let Some((host_type, host)) = url_host(&indicator.value)
+        {
+            let mut tags = req.tags.clone().unwrap_or_default();
+            tags.push("derived:url".to_string());
+            let host_req = CreateIndicatorRequest {
+                value: host,
+                ioc_type: Some(host_type),
+                hash_algorithm: None,
+                tags: Some(tags),
+                context: None,
+                ..req.clone()
+            };
+            match upsert_indicator_in_tx(&mut tx, &self.allowlist, &host_req, source_id, ctx).await {
+                Ok(host_indicator) => {
+                    insert_relation(&mut *tx, indicator.id, host_indicator.id, RelationType::HostedOn).await?;
+                }
+                // An allowlisted host (e.g. a file sharing site) does not block the URL itself
+                Err(e) => match e.downcast_ref::<AllowlistRejection>() {
+                    Some(rejection) => {
+                        let feed = feed_name(req, ctx);
+                        record_suppression(&mut *tx, &feed, &rejection.ioc_type, &rejection.value, &rejection.hit)
+                            .await?;
+                    }
+                    None => return Err(e),
+                },
+            }
+        }
+
+        tx.commit().await.context("Failed to commit indicator upsert")?;
 
         Ok(indicator)
     }
@@ -125,7 +198,7 @@ impl ThreatIntelRepo {
         let indicator = sqlx::query_as::<_, Indicator>(
             "SELECT * FROM indicators WHERE value = $1"
         )
-        .bind(value)
+        .bind(refang(value.trim()))
         .fetch_optional(&self.pool)
         .await
         .context("Failed to fetch indicator by value")?;
@@ -133,50 +206,215 @@ impl ThreatIntelRepo {
         Ok(indicator)
     }
 
-    /// Search indicators with filters
-    pub async fn search_indicators(&self, filter: &IndicatorFilter) -> Result<PaginatedResponse<Indicator>> {
-        let page = filter.page.unwrap_or(1).max(1);
-        let per_page = filter.per_page.unwrap_or(50).min(1000);
-        let offset = (page - 1) * per_page;
-
-        // Build dynamic query
-        let mut conditions = vec!["1=1".to_string()];
-        
-        if filter.ioc_type.is_some() {
-            conditions.push("ioc_type = $1".to_string());
-        }
-        if filter.severity.is_some() {
-            conditions.push("severity = $2".to_string());
-        }
-        if filter.min_confidence.is_some() {
-            conditions.push("confidence >= $3".to_string());
-        }
-        if filter.min_threat_score.is_some() {
-            conditions.push("threat_score >= $4".to_string());
+    /// Resolve many `(type, normalized value)` pairs in a single round trip,
+    /// returning each match together with its enrichments
+    pub async fn get_indicators_with_enrichments(
+        &self,
+        keys: &[(IocType, String)],
+    ) -> Result<Vec<(Indicator, Vec<Enrichment>)>> {
+        if keys.is_empty() {
+            return Ok(vec![]);
         }
-        if filter.search.is_some() {
-            conditions.push("value ILIKE $5".to_string());
+
+        let types: Vec<String> = keys.iter().map(|(t, _)| t.to_string()).collect();
+        let values: Vec<String> = keys.iter().map(|(_, v)| v.clone()).collect();
+
+        let rows = sqlx::query_as::<_, IndicatorWithEnrichments>(
+            r#"
+            SELECT i.*, COALESCE(
+                (SELECT json_agg(e ORDER BY e.fetched_at DESC) FROM enrichments e WHERE e.indicator_id = i.id AND e.is_latest),
+                '[]'::json
+            ) AS enrichments
+            FROM indicators i
+            JOIN UNNEST($1::text[], $2::text[]) AS q(ioc_type, value)
+                ON i.ioc_type = q.ioc_type::ioc_type AND i.value = q.value
+            "#
+        )
+        .bind(&types)
+        .bind(&values)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to batch lookup indicators")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.indicator, row.enrichments.0))
+            .collect())
+    }
+
+    /// Stored CIDR indicators containing (or equal to) a network, most specific first
+    pub async fn get_containing_networks(&self, network: IpNetwork) -> Result<Vec<Indicator>> {
+        let indicators = sqlx::query_as::<_, Indicator>(
+            r#"
+            SELECT * FROM indicators
+            WHERE ioc_type = 'cidr' AND network >>= $1
+            ORDER BY masklen(network) DESC
+            "#
+        )
+        .bind(network)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch containing networks")?;
+
+        Ok(indicators)
+    }
+
+    /// Containing networks for many addresses in a single round trip, returned as
+    /// `(queried network, stored network)` pairs ordered most specific first
+    pub async fn get_containing_networks_batch(
+        &self,
+        networks: &[IpNetwork],
+    ) -> Result<Vec<(IpNetwork, Indicator)>> {
+        if networks.is_empty() {
+            return Ok(vec![]);
         }
 
-        let where_clause = conditions.join(" AND ");
+        let rows = sqlx::query_as::<_, NetworkMatch>(
+            r#"
+            SELECT q.queried, i.*
+            FROM UNNEST($1::inet[]) AS q(queried)
+            JOIN indicators i ON i.ioc_type = 'cidr' AND i.network >>= q.queried
+            ORDER BY q.queried, masklen(i.network) DESC
+            "#
+        )
+        .bind(networks)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to batch fetch containing networks")?;
+
+        Ok(rows.into_iter().map(|row| (row.queried, row.indicator)).collect())
+    }
+
+    /// Domain and URL indicators on `domain` or any of its subdomains (`*.domain`)
+    pub async fn find_under_domain(&self, domain: &str, limit: i64) -> Result<Vec<Indicator>> {
+        let Some(registrable) = registrable_domain(domain) else {
+            return Ok(vec![]);
+        };
 
-        // For simplicity, using a basic query - in production, use query builder
         let indicators = sqlx::query_as::<_, Indicator>(
-            &format!(
-                "SELECT * FROM indicators WHERE {} ORDER BY last_seen DESC LIMIT {} OFFSET {}",
-                where_clause, per_page, offset
-            )
+            r#"
+            SELECT * FROM indicators
+            WHERE registrable_domain = $1
+              AND (indicator_host(ioc_type, value) = $2
+                   OR right(indicator_host(ioc_type, value), length($2) + 1) = '.' || $2)
+            ORDER BY threat_score DESC, last_seen DESC
+            LIMIT $3
+            "#
         )
+        .bind(&registrable)
+        .bind(domain)
+        .bind(limit)
         .fetch_all(&self.pool)
         .await
-        .context("Failed to search indicators")?;
+        .context("Failed to fetch indicators under domain")?;
+
+        Ok(indicators)
+    }
 
-        let total: (i64,) = sqlx::query_as(
-            &format!("SELECT COUNT(*) FROM indicators WHERE {}", where_clause)
+    /// Domain and URL indicators under a registrable domain, grouped by host
+    pub async fn get_domain_rollup(&self, registrable: &str) -> Result<Vec<SubdomainRollup>> {
+        let rollup = sqlx::query_as::<_, SubdomainRollup>(
+            r#"
+            SELECT indicator_host(ioc_type, value) AS host,
+                   COUNT(*) AS indicators,
+                   array_agg(DISTINCT ioc_type::text) AS ioc_types,
+                   MAX(threat_score) AS max_threat_score,
+                   MAX(last_seen) AS last_seen
+            FROM indicators
+            WHERE registrable_domain = $1
+            GROUP BY 1
+            ORDER BY max_threat_score DESC NULLS LAST, host
+            "#
         )
-        .fetch_one(&self.pool)
+        .bind(registrable)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch domain rollup")?;
+
+        Ok(rollup)
+    }
+
+    /// Indicators linked to this one in either direction, with the linking relation
+    pub async fn get_related_indicators(&self, indicator_id: Uuid) -> Result<Vec<(IndicatorRelation, Indicator)>> {
+        let rows = sqlx::query_as::<_, RelatedRow>(
+            r#"
+            SELECT r.id AS relation_id, r.source_indicator_id, r.target_indicator_id,
+                   r.relation_type, r.created_at AS relation_created_at, i.*
+            FROM indicator_relations r
+            JOIN indicators i ON i.id = CASE
+                WHEN r.source_indicator_id = $1 THEN r.target_indicator_id
+                ELSE r.source_indicator_id
+            END
+            WHERE r.source_indicator_id = $1 OR r.target_indicator_id = $1
+            ORDER BY r.created_at
+            "#
+        )
+        .bind(indicator_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch related indicators")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let relation = IndicatorRelation {
+                    id: row.relation_id,
+                    source_indicator_id: row.source_indicator_id,
+                    target_indicator_id: row.target_indicator_id,
+                    relation_type: row.relation_type,
+                    created_at: row.relation_created_at,
+                };
+                (relation, row.indicator)
+            })
+            .collect())
+    }
+
+    /// Link two indicators
+    pub async fn add_relation(&self, source_id: Uuid, target_id: Uuid, relation: RelationType) -> Result<()> {
+        insert_relation(&self.pool, source_id, target_id, relation).await
+    }
+
+    /// Report context an indicator was published with, newest first
+    pub async fn get_indicator_context(&self, indicator_id: Uuid) -> Result<Vec<IndicatorContext>> {
+        let context = sqlx::query_as::<_, IndicatorContext>(
+            "SELECT * FROM indicator_context WHERE indicator_id = $1 ORDER BY updated_at DESC"
+        )
+        .bind(indicator_id)
+        .fetch_all(&self.pool)
         .await
-        .context("Failed to count indicators")?;
+        .context("Failed to fetch indicator context")?;
+
+        Ok(context)
+    }
+
+    /// Search indicators with filters
+    pub async fn search_indicators(&self, filter: &IndicatorFilter) -> Result<PaginatedResponse<Indicator>> {
+        let page = filter.page.unwrap_or(1).max(1);
+        let per_page = filter.per_page.unwrap_or(50).min(1000);
+        let offset = (page - 1) * per_page;
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM indicators");
+        push_indicator_filters(&mut query, filter);
+        query
+            .push(" ORDER BY last_seen DESC LIMIT ")
+            .push_bind(per_page)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let indicators = query
+            .build_query_as::<Indicator>()
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to search indicators")?;
+
+        let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM indicators");
+        push_indicator_filters(&mut count, filter);
+
+        let total: (i64,) = count
+            .build_query_as()
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to count indicators")?;
 
         Ok(PaginatedResponse {
             data: indicators,
@@ -187,66 +425,505 @@ impl ThreatIntelRepo {
         })
     }
 
+    /// All indicators matching a filter, ignoring pagination, up to `limit` rows
+    pub async fn export_indicators(&self, filter: &IndicatorFilter, limit: i64) -> Result<Vec<Indicator>> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM indicators");
+        push_indicator_filters(&mut query, filter);
+        query.push(" ORDER BY last_seen DESC LIMIT ").push_bind(limit);
+
+        let indicators = query
+            .build_query_as::<Indicator>()
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to export indicators")?;
+
+        Ok(indicators)
+    }
+
     /// Update threat score for an indicator
-    pub async fn update_threat_score(&self, id: Uuid, score: i32) -> Result<()> {
-        sqlx::query(
-            "UPDATE indicators SET threat_score = $1, severity = $2, updated_at = NOW() WHERE id = $3"
+    pub async fn update_threat_score(&self, id: Uuid, score: i32, ctx: &AuditContext) -> Result<()> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let before = sqlx::query_as::<_, Indicator>("SELECT * FROM indicators WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await
+            .context("Failed to fetch indicator")?;
+
+        let after = sqlx::query_as::<_, Indicator>(
+            "UPDATE indicators SET threat_score = $1, severity = $2, updated_at = NOW() WHERE id = $3 RETURNING *"
         )
         .bind(score)
         .bind(Severity::from(score))
         .bind(id)
-        .execute(&self.pool)
+        .fetch_optional(&mut *tx)
         .await
         .context("Failed to update threat score")?;
 
+        if let Some(after) = after {
+            record_audit(&mut *tx, ctx, AuditEntry {
+                action: AuditAction::ScoreChange,
+                indicator_id: Some(id),
+                source_id: None,
+                before: before.as_ref().map(to_json),
+                after: Some(to_json(&after)),
+            })
+            .await?;
+        }
+
+        tx.commit().await.context("Failed to commit threat score update")?;
+
         Ok(())
     }
 
+    /// Delete an indicator, returning whether it existed
+    pub async fn delete_indicator(&self, id: Uuid, ctx: &AuditContext) -> Result<bool> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let deleted = sqlx::query_as::<_, Indicator>("DELETE FROM indicators WHERE id = $1 RETURNING *")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await
+            .context("Failed to delete indicator")?;
+
+        if let Some(ref indicator) = deleted {
+            record_audit(&mut *tx, ctx, AuditEntry {
+                action: AuditAction::Delete,
+                indicator_id: Some(indicator.id),
+                source_id: None,
+                before: Some(to_json(indicator)),
+                after: None,
+            })
+            .await?;
+        }
+
+        tx.commit().await.context("Failed to commit indicator delete")?;
+
+        Ok(deleted.is_some())
+    }
+
     /// Delete expired indicators
-    pub async fn delete_expired(&self) -> Result<i64> {
-        let result = sqlx::query(
-            "DELETE FROM indicators WHERE expiration IS NOT NULL AND expiration < NOW()"
+    pub async fn delete_expired(&self, ctx: &AuditContext) -> Result<i64> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let deleted = sqlx::query_as::<_, Indicator>(
+            "DELETE FROM indicators WHERE expiration IS NOT NULL AND expiration < NOW() RETURNING *"
         )
-        .execute(&self.pool)
+        .fetch_all(&mut *tx)
         .await
         .context("Failed to delete expired indicators")?;
 
-        Ok(result.rows_affected() as i64)
+        for indicator in &deleted {
+            record_audit(&mut *tx, ctx, AuditEntry {
+                action: AuditAction::Delete,
+                indicator_id: Some(indicator.id),
+                source_id: None,
+                before: Some(to_json(indicator)),
+                after: None,
+            })
+            .await?;
+        }
+
+        tx.commit().await.context("Failed to commit expired indicator delete")?;
+
+        Ok(deleted.len() as i64)
+    }
+
+    // ==================== Files ====================
+
+    /// Record that several hashes describe the same file: merge them into one
+    /// file object, create indicators for hashes we have not seen yet (inheriting
+    /// the classification of `origin`) and link all of them to the file
+    pub async fn link_file_hashes(
+        &self,
+        origin: &Indicator,
+        hashes: &FileHashes,
+        ctx: &AuditContext,
+    ) -> Result<FileObject> {
+        let mut hashes = FileHashes {
+            md5: hashes.md5.as_ref().map(|h| h.to_lowercase()),
+            sha1: hashes.sha1.as_ref().map(|h| h.to_lowercase()),
+            sha256: hashes.sha256.as_ref().map(|h| h.to_lowercase()),
+            ..hashes.clone()
+        };
+
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let existing = sqlx::query_as::<_, FileObject>(
+            r#"
+            SELECT * FROM file_objects
+            WHERE md5 = $1 OR sha1 = $2 OR sha256 = $3
+            ORDER BY created_at
+            FOR UPDATE
+            "#
+        )
+        .bind(&hashes.md5)
+        .bind(&hashes.sha1)
+        .bind(&hashes.sha256)
+        .fetch_all(&mut *tx)
+        .await
+        .context("Failed to fetch file object")?;
+
+        // The hashes can tie together files recorded separately, e.g. an MD5-only
+        // and a SHA256-only record: fold the newer ones into the oldest
+        let mut existing = existing.into_iter();
+        let keep = existing.next();
+        let duplicates: Vec<FileObject> = existing.collect();
+
+        if let Some(ref keep) = keep
+            && !duplicates.is_empty()
+        {
+            let ids: Vec<Uuid> = duplicates.iter().map(|f| f.id).collect();
+
+            let moved = sqlx::query_as::<_, Indicator>(
+                r#"
+                UPDATE indicators SET file_object_id = $1, updated_at = NOW()
+                WHERE file_object_id = ANY($2)
+                RETURNING *
+                "#
+            )
+            .bind(keep.id)
+            .bind(&ids)
+            .fetch_all(&mut *tx)
+            .await
+            .context("Failed to move indicators to merged file object")?;
+
+            for indicator in &moved {
+                record_audit(&mut *tx, ctx, AuditEntry {
+                    action: AuditAction::Update,
+                    indicator_id: Some(indicator.id),
+                    source_id: None,
+                    before: None,
+                    after: Some(serde_json::json!({ "file_object_id": keep.id })),
+                })
+                .await?;
+            }
+
+            sqlx::query("DELETE FROM file_objects WHERE id = ANY($1)")
+                .bind(&ids)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to delete merged file objects")?;
+
+            for duplicate in duplicates {
+                absorb_file_object(&mut hashes, duplicate);
+            }
+        }
+
+        let file = sqlx::query_as::<_, FileObject>(
+            r#"
+            INSERT INTO file_objects (
+                id, md5, sha1, sha256, sha512, ssdeep, tlsh, imphash, size, file_type, names,
+                created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, NOW(), NOW())
+            ON CONFLICT (id) DO UPDATE SET
+                md5 = COALESCE(file_objects.md5, EXCLUDED.md5),
+                sha1 = COALESCE(file_objects.sha1, EXCLUDED.sha1),
+                sha256 = COALESCE(file_objects.sha256, EXCLUDED.sha256),
+                sha512 = COALESCE(EXCLUDED.sha512, file_objects.sha512),
+                ssdeep = COALESCE(EXCLUDED.ssdeep, file_objects.ssdeep),
+                tlsh = COALESCE(EXCLUDED.tlsh, file_objects.tlsh),
+                imphash = COALESCE(EXCLUDED.imphash, file_objects.imphash),
+                size = COALESCE(EXCLUDED.size, file_objects.size),
+                file_type = COALESCE(EXCLUDED.file_type, file_objects.file_type),
+                names = ARRAY(SELECT DISTINCT unnest(file_objects.names || EXCLUDED.names))
+            RETURNING *
+            "#
+        )
+        .bind(keep.as_ref().map(|f| f.id).unwrap_or_else(Uuid::new_v4))
+        .bind(&hashes.md5)
+        .bind(&hashes.sha1)
+        .bind(&hashes.sha256)
+        .bind(&hashes.sha512)
+        .bind(&hashes.ssdeep)
+        .bind(&hashes.tlsh)
+        .bind(&hashes.imphash)
+        .bind(hashes.size)
+        .bind(&hashes.file_type)
+        .bind(&hashes.names)
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to upsert file object")?;
+
+        tx.commit().await.context("Failed to commit file object")?;
+
+        // Make sure every cryptographic hash of the file exists as an indicator
+        let mut indicator_ids = vec![origin.id];
+        let siblings = [
+            (HashAlgorithm::Md5, &file.md5),
+            (HashAlgorithm::Sha1, &file.sha1),
+            (HashAlgorithm::Sha256, &file.sha256),
+        ];
+
+        for (algorithm, value) in siblings {
+            let Some(value) = value else { continue };
+            if *value == origin.value {
+                continue;
+            }
+
+            let req = CreateIndicatorRequest {
+                value: value.clone(),
+                ioc_type: Some(IocType::Hash),
+                hash_algorithm: Some(algorithm),
+                severity: Some(origin.severity.clone()),
+                confidence: Some(origin.confidence),
+                tlp: Some(origin.tlp.clone()),
+                tags: Some(origin.tags.clone()),
+                source: None,
+                expiration_days: None,
+                context: None,
+            };
+            let sibling = self.upsert_indicator(&req, None, ctx).await?;
+            indicator_ids.push(sibling.id);
+        }
+
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let linked = sqlx::query_as::<_, Indicator>(
+            r#"
+            UPDATE indicators SET file_object_id = $1, updated_at = NOW()
+            WHERE id = ANY($2) AND file_object_id IS DISTINCT FROM $1
+            RETURNING *
+            "#
+        )
+        .bind(file.id)
+        .bind(&indicator_ids)
+        .fetch_all(&mut *tx)
+        .await
+        .context("Failed to link indicators to file object")?;
+
+        for indicator in &linked {
+            record_audit(&mut *tx, ctx, AuditEntry {
+                action: AuditAction::Update,
+                indicator_id: Some(indicator.id),
+                source_id: None,
+                before: None,
+                after: Some(serde_json::json!({ "file_object_id": file.id })),
+            })
+            .await?;
+        }
+
+        tx.commit().await.context("Failed to commit file linking")?;
+
+        Ok(file)
+    }
+
+    /// File object together with all hash indicators linked to it
+    pub async fn get_file_record(&self, file_object_id: Uuid) -> Result<Option<FileRecord>> {
+        let file = sqlx::query_as::<_, FileObject>("SELECT * FROM file_objects WHERE id = $1")
+            .bind(file_object_id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch file object")?;
+
+        let Some(file) = file else {
+            return Ok(None);
+        };
+
+        let indicators = sqlx::query_as::<_, Indicator>(
+            "SELECT * FROM indicators WHERE file_object_id = $1 ORDER BY hash_algorithm"
+        )
+        .bind(file_object_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch file indicators")?;
+
+        Ok(Some(FileRecord { file, indicators }))
+    }
+
+    // ==================== Host Exposure ====================
+
+    /// Create indicators for the certificate fingerprints and CVEs seen on an
+    /// exposed host (inheriting its confidence and TLP) and link them to it.
+    /// Returns the number of linked indicators.
+    pub async fn link_exposure(
+        &self,
+        host: &Indicator,
+        exposure: &HostExposure,
+        ctx: &AuditContext,
+    ) -> Result<usize> {
+        let certificates = exposure
+            .services
+            .iter()
+            .filter_map(|s| s.certificate.as_ref()?.sha256.clone())
+            .map(|fp| (fp, IocType::CertFingerprint, RelationType::PresentsCertificate));
+        let vulns = exposure
+            .vulns
+            .iter()
+            .map(|cve| (cve.clone(), IocType::Cve, RelationType::HasVulnerability));
+
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+        let mut linked = 0;
+
+        for (value, ioc_type, relation) in certificates.chain(vulns) {
+            let req = CreateIndicatorRequest {
+                value,
+                ioc_type: Some(ioc_type),
+                hash_algorithm: None,
+                severity: None,
+                confidence: Some(host.confidence),
+                tlp: Some(host.tlp.clone()),
+                tags: Some(vec!["derived:exposure".to_string()]),
+                source: None,
+                expiration_days: None,
+                context: None,
+            };
+            match upsert_indicator_in_tx(&mut tx, &self.allowlist, &req, None, ctx).await {
+                Ok(related) => {
+                    insert_relation(&mut *tx, host.id, related.id, relation).await?;
+                    linked += 1;
+                }
+                // Shared certificates (CDNs, default vendor certs) are commonly allowlisted
+                Err(e) if e.downcast_ref::<AllowlistRejection>().is_some() => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        tx.commit().await.context("Failed to commit exposure links")?;
+
+        Ok(linked)
     }
 
     // ==================== Enrichments ====================
 
-    /// Add enrichment data for an indicator
+    /// Store a new enrichment snapshot for an indicator.
+    ///
+    /// The previous snapshot is kept as history (the newest
+    /// `MAX_ENRICHMENT_HISTORY` of them); when the result is unchanged apart
+    /// from volatile fields the current snapshot is updated instead. Material
+    /// changes are stored on the new snapshot and recorded as an
+    /// `enrichment_change` audit event.
     pub async fn add_enrichment(
         &self,
         indicator_id: Uuid,
         enrichment_type: &str,
         provider: &str,
         data: serde_json::Value,
-        ttl_hours: Option<i64>,
+        expires_at: Option<DateTime<Utc>>,
+        ctx: &AuditContext,
     ) -> Result<Enrichment> {
-        let expires_at = ttl_hours.map(|h| Utc::now() + Duration::hours(h));
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        // Serializes concurrent results for the same snapshot, including the
+        // first one, when there is no row to lock yet
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1::text || '/' || $2 || '/' || $3, 0))")
+            .bind(indicator_id)
+            .bind(enrichment_type)
+            .bind(provider)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to lock enrichment")?;
 
-        let enrichment = sqlx::query_as::<_, Enrichment>(
+        let before = sqlx::query_as::<_, Enrichment>(
             r#"
-            INSERT INTO enrichments (id, indicator_id, enrichment_type, provider, data, fetched_at, expires_at)
-            VALUES ($1, $2, $3, $4, $5, NOW(), $6)
-            ON CONFLICT (indicator_id, enrichment_type, provider) DO UPDATE SET
-                data = EXCLUDED.data,
-                fetched_at = EXCLUDED.fetched_at,
-                expires_at = EXCLUDED.expires_at
-            RETURNING *
+            SELECT * FROM enrichments
+            WHERE indicator_id = $1 AND enrichment_type = $2 AND provider = $3 AND is_latest
             "#
         )
-        .bind(Uuid::new_v4())
         .bind(indicator_id)
         .bind(enrichment_type)
         .bind(provider)
-        .bind(data)
-        .bind(expires_at)
-        .fetch_one(&self.pool)
+        .fetch_optional(&mut *tx)
         .await
-        .context("Failed to add enrichment")?;
+        .context("Failed to fetch existing enrichment")?;
+
+        let enrichment = match &before {
+            Some(previous) if same_snapshot(&previous.data, &data) => {
+                sqlx::query_as::<_, Enrichment>(
+                    r#"
+                    UPDATE enrichments SET data = $3, fetched_at = NOW(), expires_at = $2, refresh_failures = 0
+                    WHERE id = $1
+                    RETURNING *
+                    "#
+                )
+                .bind(previous.id)
+                .bind(expires_at)
+                .bind(data)
+                .fetch_one(&mut *tx)
+                .await
+                .context("Failed to refresh enrichment")?
+            }
+            _ => {
+                let changes = before
+                    .as_ref()
+                    .map(|previous| material_changes(&previous.data, &data))
+                    .unwrap_or_default();
+
+                if let Some(previous) = &before {
+                    sqlx::query("UPDATE enrichments SET is_latest = FALSE WHERE id = $1")
+                        .bind(previous.id)
+                        .execute(&mut *tx)
+                        .await
+                        .context("Failed to supersede enrichment")?;
+
+                    sqlx::query(
+                        r#"
+                        DELETE FROM enrichments WHERE id IN (
+                            SELECT id FROM enrichments
+                            WHERE indicator_id = $1 AND enrichment_type = $2 AND provider = $3 AND NOT is_latest
+                            ORDER BY fetched_at DESC
+                            OFFSET $4
+                        )
+                        "#
+                    )
+                    .bind(indicator_id)
+                    .bind(enrichment_type)
+                    .bind(provider)
+                    .bind(MAX_ENRICHMENT_HISTORY)
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to prune enrichment history")?;
+                }
+
+                let enrichment = sqlx::query_as::<_, Enrichment>(
+                    r#"
+                    INSERT INTO enrichments (id, indicator_id, enrichment_type, provider, data, fetched_at, expires_at, is_latest, changes)
+                    VALUES ($1, $2, $3, $4, $5, NOW(), $6, TRUE, $7)
+                    RETURNING *
+                    "#
+                )
+                .bind(Uuid::new_v4())
+                .bind(indicator_id)
+                .bind(enrichment_type)
+                .bind(provider)
+                .bind(data)
+                .bind(expires_at)
+                .bind((!changes.is_empty()).then(|| to_json(&changes)))
+                .fetch_one(&mut *tx)
+                .await
+                .context("Failed to add enrichment")?;
+
+                if !changes.is_empty() {
+                    record_audit(&mut *tx, ctx, AuditEntry {
+                        action: AuditAction::EnrichmentChange,
+                        indicator_id: Some(indicator_id),
+                        source_id: None,
+                        before: None,
+                        after: Some(serde_json::json!({
+                            "enrichment_id": enrichment.id,
+                            "enrichment_type": enrichment_type,
+                            "provider": provider,
+                            "changes": changes,
+                        })),
+                    })
+                    .await?;
+                }
+
+                enrichment
+            }
+        };
+
+        record_audit(&mut *tx, ctx, AuditEntry {
+            action: AuditAction::Enrichment,
+            indicator_id: Some(indicator_id),
+            source_id: None,
+            before: before.as_ref().map(to_json),
+            after: Some(to_json(&enrichment)),
+        })
+        .await?;
+
+        tx.commit().await.context("Failed to commit enrichment")?;
 
         Ok(enrichment)
     }
@@ -254,7 +931,7 @@ impl ThreatIntelRepo {
     /// Get enrichments for an indicator
     pub async fn get_enrichments(&self, indicator_id: Uuid) -> Result<Vec<Enrichment>> {
         let enrichments = sqlx::query_as::<_, Enrichment>(
-            "SELECT * FROM enrichments WHERE indicator_id = $1 ORDER BY fetched_at DESC"
+            "SELECT * FROM enrichments WHERE indicator_id = $1 AND is_latest ORDER BY fetched_at DESC"
         )
         .bind(indicator_id)
         .fetch_all(&self.pool)
@@ -264,56 +941,792 @@ impl ThreatIntelRepo {
         Ok(enrichments)
     }
 
-    // ==================== Sightings ====================
-
-    /// Record a sighting of an indicator
-    pub async fn add_sighting(
+    /// Snapshots from one provider for an indicator, newest first
+    pub async fn get_enrichment_history(
         &self,
         indicator_id: Uuid,
-        source: &str,
-        context: Option<serde_json::Value>,
-    ) -> Result<Sighting> {
-        let sighting = sqlx::query_as::<_, Sighting>(
+        provider: &str,
+        limit: i64,
+    ) -> Result<Vec<Enrichment>> {
+        let enrichments = sqlx::query_as::<_, Enrichment>(
             r#"
-            INSERT INTO sightings (id, indicator_id, source, context, observed_at, created_at)
-            VALUES ($1, $2, $3, $4, NOW(), NOW())
-            RETURNING *
+            SELECT * FROM enrichments
+            WHERE indicator_id = $1 AND provider = $2
+            ORDER BY fetched_at DESC
+            LIMIT $3
             "#
         )
-        .bind(Uuid::new_v4())
         .bind(indicator_id)
-        .bind(source)
-        .bind(context)
-        .fetch_one(&self.pool)
+        .bind(provider)
+        .bind(limit)
+        .fetch_all(&self.pool)
         .await
-        .context("Failed to add sighting")?;
-
-        // Update last_seen on indicator
-        sqlx::query("UPDATE indicators SET last_seen = NOW() WHERE id = $1")
-            .bind(indicator_id)
-            .execute(&self.pool)
-            .await?;
+        .context("Failed to fetch enrichment history")?;
 
-        Ok(sighting)
+        Ok(enrichments)
     }
 
-    /// Count sightings for an indicator
-    pub async fn count_sightings(&self, indicator_id: Uuid) -> Result<i64> {
-        let count: (i64,) = sqlx::query_as(
-            "SELECT COUNT(*) FROM sightings WHERE indicator_id = $1"
+    /// Indicators holding at least one enrichment from `providers` past its
+    /// `expires_at`, longest-expired first. Results of providers that are no
+    /// longer configured are left alone.
+    pub async fn get_indicators_with_expired_enrichments(&self, providers: &[&str], limit: i64) -> Result<Vec<Indicator>> {
+        let indicators = sqlx::query_as::<_, Indicator>(
+            r#"
+            SELECT i.* FROM indicators i
+            JOIN (
+                SELECT indicator_id, MIN(expires_at) AS oldest
+                FROM enrichments
+                WHERE is_latest AND expires_at IS NOT NULL AND expires_at <= NOW()
+                  AND provider = ANY($1)
+                GROUP BY indicator_id
+            ) e ON e.indicator_id = i.id
+            WHERE i.expiration IS NULL OR i.expiration > NOW()
+            ORDER BY e.oldest
+            LIMIT $2
+            "#
         )
-        .bind(indicator_id)
-        .fetch_one(&self.pool)
+        .bind(providers)
+        .bind(limit)
+        .fetch_all(&self.pool)
         .await
-        .context("Failed to count sightings")?;
+        .context("Failed to fetch indicators with expired enrichments")?;
 
-        Ok(count.0)
+        Ok(indicators)
     }
 
-    // ==================== Sources ====================
+    /// Push back the refresh of an expired enrichment whose provider failed
+    /// or returned nothing: one hour after the first failure, doubling with
+    /// each consecutive one up to `max_hours`. The stored data is kept.
+    pub async fn defer_enrichment_refresh(&self, enrichment_id: Uuid, max_hours: i64) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE enrichments
+            SET expires_at = NOW() + LEAST(
+                    INTERVAL '1 hour' * POWER(2, LEAST(refresh_failures, 16)),
+                    make_interval(hours => GREATEST($2, 1)::int)
+                ),
+                refresh_failures = refresh_failures + 1
+            WHERE id = $1 AND is_latest
+            "#
+        )
+        .bind(enrichment_id)
+        .bind(max_hours)
+        .execute(&self.pool)
+        .await
+        .context("Failed to defer enrichment refresh")?;
+
+        Ok(())
+    }
+
+    // ==================== Passive DNS ====================
+
+    /// Record DNS answers seen by enrichment, bumping `last_seen` and `count` of
+    /// known pairings, and link the indicators on both ends of each answer
+    pub async fn record_passive_dns(&self, observations: &[DnsObservation]) -> Result<()> {
+        let mut observations = observations.to_vec();
+        observations.sort();
+        observations.dedup();
+        if observations.is_empty() {
+            return Ok(());
+        }
+
+        let ids: Vec<Uuid> = observations.iter().map(|_| Uuid::new_v4()).collect();
+        let rrnames: Vec<&str> = observations.iter().map(|o| o.rrname.as_str()).collect();
+        let rrtypes: Vec<&str> = observations.iter().map(|o| o.rrtype.as_str()).collect();
+        let rdata: Vec<&str> = observations.iter().map(|o| o.rdata.as_str()).collect();
+
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO passive_dns (id, rrname, rrtype, rdata)
+            SELECT * FROM UNNEST($1::uuid[], $2::text[], $3::text[]::pdns_rrtype[], $4::text[])
+            ON CONFLICT (rrname, rrtype, rdata) DO UPDATE SET
+                last_seen = NOW(),
+                count = passive_dns.count + 1
+            "#
+        )
+        .bind(&ids)
+        .bind(&rrnames)
+        .bind(&rrtypes)
+        .bind(&rdata)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to record passive DNS")?;
+
+        let relation_ids: Vec<Uuid> = observations.iter().map(|_| Uuid::new_v4()).collect();
+        let relations: Vec<&str> = observations.iter().map(|o| o.rrtype.relation().as_str()).collect();
+
+        sqlx::query(
+            r#"
+            INSERT INTO indicator_relations (id, source_indicator_id, target_indicator_id, relation_type)
+            SELECT q.id, s.id, t.id, q.relation_type
+            FROM UNNEST($1::uuid[], $2::text[], $3::text[], $4::text[]) AS q(id, rrname, rdata, relation_type)
+            JOIN indicators s ON s.ioc_type IN ('domain', 'ip') AND s.value = q.rrname
+            JOIN indicators t ON t.ioc_type IN ('domain', 'ip') AND t.value = q.rdata
+            ON CONFLICT (source_indicator_id, target_indicator_id, relation_type) DO NOTHING
+            "#
+        )
+        .bind(&relation_ids)
+        .bind(&rrnames)
+        .bind(&rdata)
+        .bind(&relations)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to link passive DNS indicators")?;
+
+        tx.commit().await.context("Failed to commit passive DNS")?;
+
+        Ok(())
+    }
+
+    /// Passive DNS records naming `value` (forward) and answering with it (reverse),
+    /// most recently seen first
+    pub async fn get_passive_dns(
+        &self,
+        value: &str,
+        limit: i64,
+    ) -> Result<(Vec<PassiveDnsRecord>, Vec<PassiveDnsRecord>)> {
+        let forward = sqlx::query_as::<_, PassiveDnsRecord>(
+            "SELECT * FROM passive_dns WHERE rrname = $1 ORDER BY last_seen DESC LIMIT $2"
+        )
+        .bind(value)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch passive DNS")?;
+
+        let reverse = sqlx::query_as::<_, PassiveDnsRecord>(
+            "SELECT * FROM passive_dns WHERE rdata = $1 ORDER BY last_seen DESC LIMIT $2"
+        )
+        .bind(value)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch reverse passive DNS")?;
+
+        Ok((forward, reverse))
+    }
+
+    /// Distinct addresses and networks a name resolved to within `window`
+    pub async fn get_resolution_churn(&self, rrname: &str, window: Duration) -> Result<(i64, i64)> {
+        let churn = sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT COUNT(DISTINCT rdata),
+                   COUNT(DISTINCT network(set_masklen(rdata::inet, CASE WHEN rrtype = 'A' THEN 16 ELSE 32 END)))
+            FROM passive_dns
+            WHERE rrname = $1 AND rrtype IN ('A', 'AAAA') AND last_seen >= $2
+            "#
+        )
+        .bind(rrname)
+        .bind(Utc::now() - window)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to fetch resolution churn")?;
+
+        Ok(churn)
+    }
+
+    // ==================== Enrichment queue ====================
+
+    /// Queue an indicator for enrichment.
+    ///
+    /// A pending job for the same indicator is merged (highest priority, broadest
+    /// mode). A forced request, or one broader than the run in progress, for an
+    /// indicator already being enriched is kept as a follow-up run of its job.
+    pub async fn enqueue_enrichment(
+        &self,
+        indicator_id: Uuid,
+        priority: EnrichmentPriority,
+        mode: EnrichMode,
+        requested_by: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO enrichment_jobs (id, indicator_id, priority, mode, requested_by)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (indicator_id) WHERE status IN ('pending', 'running') DO UPDATE SET
+                priority = CASE WHEN enrichment_jobs.status = 'pending'
+                    THEN GREATEST(enrichment_jobs.priority, EXCLUDED.priority) ELSE enrichment_jobs.priority END,
+                mode = CASE WHEN enrichment_jobs.status = 'pending'
+                    THEN GREATEST(enrichment_jobs.mode, EXCLUDED.mode) ELSE enrichment_jobs.mode END,
+                rerun_priority = CASE WHEN enrichment_jobs.status = 'running'
+                    THEN GREATEST(enrichment_jobs.rerun_priority, EXCLUDED.priority) ELSE enrichment_jobs.rerun_priority END,
+                rerun_mode = CASE WHEN enrichment_jobs.status = 'running'
+                    THEN GREATEST(enrichment_jobs.rerun_mode, EXCLUDED.mode) ELSE enrichment_jobs.rerun_mode END,
+                rerun_requested_by = CASE WHEN enrichment_jobs.status = 'running'
+                    THEN EXCLUDED.requested_by ELSE enrichment_jobs.rerun_requested_by END,
+                updated_at = NOW()
+            WHERE enrichment_jobs.status = 'pending'
+                OR EXCLUDED.mode = 'force'
+                OR EXCLUDED.mode > enrichment_jobs.mode
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(indicator_id)
+        .bind(priority)
+        .bind(mode)
+        .bind(requested_by)
+        .execute(&self.pool)
+        .await
+        .context("Failed to enqueue enrichment")?;
+
+        Ok(())
+    }
+
+    /// Claim the most urgent ready job, skipping jobs other workers hold
+    pub async fn claim_enrichment_job(&self) -> Result<Option<EnrichmentJob>> {
+        let job = sqlx::query_as::<_, EnrichmentJob>(
+            r#"
+            UPDATE enrichment_jobs SET
+                status = 'running',
+                attempts = attempts + 1,
+                locked_at = NOW(),
+                updated_at = NOW()
+            WHERE id = (
+                SELECT id FROM enrichment_jobs
+                WHERE status = 'pending' AND run_after <= NOW()
+                ORDER BY priority DESC, run_after, created_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+            "#
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to claim enrichment job")?;
+
+        Ok(job)
+    }
+
+    /// Remove a finished job, or queue its follow-up run if one was requested
+    /// while it was running
+    pub async fn complete_enrichment_job(&self, id: Uuid) -> Result<()> {
+        let deleted = sqlx::query("DELETE FROM enrichment_jobs WHERE id = $1 AND rerun_mode IS NULL")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to complete enrichment job")?;
+
+        if deleted.rows_affected() == 0 {
+            sqlx::query(
+                r#"
+                UPDATE enrichment_jobs SET
+                    status = 'pending',
+                    priority = rerun_priority,
+                    mode = rerun_mode,
+                    requested_by = rerun_requested_by,
+                    rerun_priority = NULL,
+                    rerun_mode = NULL,
+                    rerun_requested_by = NULL,
+                    attempts = 0,
+                    last_error = NULL,
+                    run_after = NOW(),
+                    locked_at = NULL,
+                    updated_at = NOW()
+                WHERE id = $1 AND rerun_mode IS NOT NULL
+                "#
+            )
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to queue follow-up enrichment")?;
+        }
+
+        Ok(())
+    }
+
+    /// Reschedule a failed job with exponential backoff, or dead-letter it once
+    /// it has used `max_attempts`. Forced jobs are retried in cached mode so
+    /// providers that already succeeded are not queried again. A follow-up
+    /// run requested meanwhile is folded into the retry, which then starts
+    /// right away with a fresh attempt count.
+    pub async fn fail_enrichment_job(
+        &self,
+        id: Uuid,
+        error: &str,
+        max_attempts: i32,
+    ) -> Result<EnrichmentJobStatus> {
+        self.retry_enrichment_job(id, error, max_attempts, None)
+            .await?
+            .context("Enrichment job not found")
+    }
+
+    /// Fail jobs left running by a crashed or stuck worker, so they are retried
+    /// with backoff like any failure and dead-lettered once they have used
+    /// `max_attempts` (the claim counted the attempt). Returns how many were
+    /// released and how many of those were dead-lettered.
+    pub async fn release_stale_enrichment_jobs(&self, older_than: Duration, max_attempts: i32) -> Result<(u64, u64)> {
+        let locked_before = Utc::now() - older_than;
+        let stale = sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM enrichment_jobs WHERE status = 'running' AND locked_at < $1"
+        )
+        .bind(locked_before)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to find stale enrichment jobs")?;
+
+        let (mut released, mut dead) = (0, 0);
+        for id in stale {
+            // Skipped when the worker finished in the meantime
+            let status = self
+                .retry_enrichment_job(id, "Worker stopped responding; job released", max_attempts, Some(locked_before))
+                .await?;
+            match status {
+                Some(EnrichmentJobStatus::Dead) => {
+                    released += 1;
+                    dead += 1;
+                }
+                Some(_) => released += 1,
+                None => {}
+            }
+        }
+
+        Ok((released, dead))
+    }
+
+    /// Shared by failures and stale releases; with `locked_before` only a job
+    /// still running under a lock older than that is touched
+    async fn retry_enrichment_job(
+        &self,
+        id: Uuid,
+        error: &str,
+        max_attempts: i32,
+        locked_before: Option<DateTime<Utc>>,
+    ) -> Result<Option<EnrichmentJobStatus>> {
+        let status = sqlx::query_scalar::<_, EnrichmentJobStatus>(
+            r#"
+            UPDATE enrichment_jobs SET
+                status = CASE WHEN attempts >= $3 AND rerun_mode IS NULL THEN 'dead' ELSE 'pending' END::enrichment_job_status,
+                priority = GREATEST(priority, rerun_priority),
+                mode = GREATEST(LEAST(mode, 'cached'), rerun_mode),
+                requested_by = COALESCE(rerun_requested_by, requested_by),
+                attempts = CASE WHEN rerun_mode IS NULL THEN attempts ELSE 0 END,
+                last_error = $2,
+                run_after = CASE WHEN rerun_mode IS NULL
+                    THEN NOW() + LEAST(INTERVAL '30 seconds' * POWER(2, attempts - 1), INTERVAL '1 hour')
+                    ELSE NOW() END,
+                rerun_priority = NULL,
+                rerun_mode = NULL,
+                rerun_requested_by = NULL,
+                locked_at = NULL,
+                updated_at = NOW()
+            WHERE id = $1
+                AND ($4::timestamptz IS NULL OR (status = 'running' AND locked_at < $4))
+            RETURNING status
+            "#
+        )
+        .bind(id)
+        .bind(error)
+        .bind(max_attempts)
+        .bind(locked_before)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fail enrichment job")?;
+
+        Ok(status)
+    }
+
+    /// Queue depth by status and priority, with the most recent jobs in `status`
+    pub async fn get_enrichment_queue_stats(
+        &self,
+        status: EnrichmentJobStatus,
+        limit: i64,
+    ) -> Result<EnrichmentQueueStats> {
+        let (pending, running, dead): (i64, i64, i64) = sqlx::query_as(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE status = 'pending'),
+                COUNT(*) FILTER (WHERE status = 'running'),
+                COUNT(*) FILTER (WHERE status = 'dead')
+            FROM enrichment_jobs
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to count enrichment jobs")?;
+
+        let by_priority = sqlx::query_as::<_, QueuePriorityCount>(
+            r#"
+            SELECT priority, COUNT(*) AS pending, MIN(created_at) AS oldest
+            FROM enrichment_jobs
+            WHERE status = 'pending'
+            GROUP BY priority
+            ORDER BY priority DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to count enrichment jobs by priority")?;
+
+        let jobs = sqlx::query_as::<_, EnrichmentJob>(
+            "SELECT * FROM enrichment_jobs WHERE status = $1 ORDER BY updated_at DESC LIMIT $2"
+        )
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list enrichment jobs")?;
+
+        Ok(EnrichmentQueueStats { pending, running, dead, by_priority, jobs })
+    }
+
+    // ==================== Sightings ====================
+
+    /// Record a sighting of an indicator, observed now unless `observed_at` is given
+    pub async fn add_sighting(
+        &self,
+        indicator_id: Uuid,
+        source: &str,
+        context: Option<serde_json::Value>,
+        observed_at: Option<DateTime<Utc>>,
+        ctx: &AuditContext,
+    ) -> Result<Sighting> {
+        self.insert_sighting(indicator_id, source, None, context, observed_at, ctx)
+            .await?
+            .context("Sighting was not recorded")
+    }
+
+    /// Record a sighting of a feed event, keyed on the event so a replay is
+    /// ignored. Returns `None` when the event was already recorded.
+    pub async fn add_feed_sighting(
+        &self,
+        indicator_id: Uuid,
+        source: &str,
+        event_key: &str,
+        context: Option<serde_json::Value>,
+        observed_at: Option<DateTime<Utc>>,
+        ctx: &AuditContext,
+    ) -> Result<Option<Sighting>> {
+        self.insert_sighting(indicator_id, source, Some(event_key), context, observed_at, ctx)
+            .await
+    }
+
+    async fn insert_sighting(
+        &self,
+        indicator_id: Uuid,
+        source: &str,
+        event_key: Option<&str>,
+        context: Option<serde_json::Value>,
+        observed_at: Option<DateTime<Utc>>,
+        ctx: &AuditContext,
+    ) -> Result<Option<Sighting>> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let sighting = sqlx::query_as::<_, Sighting>(
+            r#"
+            INSERT INTO sightings (id, indicator_id, source, context, observed_at, created_at, event_key)
+            VALUES ($1, $2, $3, $4, COALESCE($5, NOW()), NOW(), $6)
+            ON CONFLICT (source, event_key) WHERE event_key IS NOT NULL DO NOTHING
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(indicator_id)
+        .bind(source)
+        .bind(context)
+        .bind(observed_at)
+        .bind(event_key)
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to add sighting")?;
+
+        let Some(sighting) = sighting else {
+            return Ok(None);
+        };
+
+        // Update last_seen on indicator (replayed events may be older)
+        sqlx::query("UPDATE indicators SET last_seen = GREATEST(last_seen, $2) WHERE id = $1")
+            .bind(indicator_id)
+            .bind(sighting.observed_at)
+            .execute(&mut *tx)
+            .await?;
+
+        record_audit(&mut *tx, ctx, AuditEntry {
+            action: AuditAction::Sighting,
+            indicator_id: Some(indicator_id),
+            source_id: None,
+            before: None,
+            after: Some(to_json(&sighting)),
+        })
+        .await?;
+
+        tx.commit().await.context("Failed to commit sighting")?;
+
+        Ok(Some(sighting))
+    }
+
+    /// Count sightings for an indicator
+    pub async fn count_sightings(&self, indicator_id: Uuid) -> Result<i64> {
+        let count: (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM sightings WHERE indicator_id = $1"
+        )
+        .bind(indicator_id)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to count sightings")?;
+
+        Ok(count.0)
+    }
+
+    /// Unexpired IP, network, domain and URL indicators as
+    /// `(id, type, normalized value)`, for matching log traffic in memory
+    pub async fn list_watch_indicators(&self) -> Result<Vec<(Uuid, IocType, String)>> {
+        let rows = sqlx::query_as::<_, (Uuid, IocType, String)>(
+            r#"
+            SELECT id, ioc_type, value FROM indicators
+            WHERE ioc_type IN ('ip', 'cidr', 'domain', 'url')
+              AND (expiration IS NULL OR expiration > NOW())
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch watched indicators")?;
+
+        Ok(rows)
+    }
+
+    // ==================== Honeypot ====================
+
+    /// Fold honeypot events into their sessions and keep the credentials and
+    /// commands they carry. Events are keyed per session, so a replayed event
+    /// changes nothing. Returns the number of new events.
+    pub async fn record_honeypot_observations(&self, observations: &[HoneypotObservation]) -> Result<usize> {
+        if observations.is_empty() {
+            return Ok(0);
+        }
+
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+        let mut recorded = 0;
+
+        for obs in observations {
+            sqlx::query(
+                r#"
+                INSERT INTO honeypot_sessions (session_id, source_ip, protocol, category, first_seen, last_seen)
+                VALUES ($1, $2, $3, $4, $5, $5)
+                ON CONFLICT (session_id) DO NOTHING
+                "#
+            )
+            .bind(&obs.session_id)
+            .bind(&obs.source_ip)
+            .bind(&obs.protocol)
+            .bind(&obs.category)
+            .bind(obs.observed_at)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to create honeypot session")?;
+
+            let inserted = sqlx::query(
+                r#"
+                INSERT INTO honeypot_events (id, session_id, event_key, observed_at, username, password, command)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT (session_id, event_key) DO NOTHING
+                "#
+            )
+            .bind(Uuid::new_v4())
+            .bind(&obs.session_id)
+            .bind(&obs.event_key)
+            .bind(obs.observed_at)
+            .bind(&obs.username)
+            .bind(&obs.password)
+            .bind(&obs.command)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to insert honeypot event")?
+            .rows_affected();
+
+            if inserted == 0 {
+                continue;
+            }
+            recorded += 1;
+
+            sqlx::query(
+                r#"
+                UPDATE honeypot_sessions SET
+                    first_seen = LEAST(first_seen, $2),
+                    last_seen = GREATEST(last_seen, $2),
+                    event_count = event_count + 1,
+                    login_attempts = login_attempts + $3,
+                    command_count = command_count + $4,
+                    download_urls = ARRAY(SELECT DISTINCT unnest(download_urls || $5))
+                WHERE session_id = $1
+                "#
+            )
+            .bind(&obs.session_id)
+            .bind(obs.observed_at)
+            .bind(obs.username.is_some() as i32)
+            .bind(obs.command.is_some() as i32)
+            .bind(&obs.download_urls)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to update honeypot session")?;
+        }
+
+        tx.commit().await.context("Failed to commit honeypot events")?;
+
+        Ok(recorded)
+    }
+
+    /// Honeypot sessions, most recent first
+    pub async fn list_honeypot_sessions(
+        &self,
+        source_ip: Option<&str>,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<HoneypotSession>> {
+        let sessions = sqlx::query_as::<_, HoneypotSession>(
+            r#"
+            SELECT * FROM honeypot_sessions
+            WHERE ($1::text IS NULL OR source_ip = $1)
+              AND ($2::timestamptz IS NULL OR last_seen >= $2)
+            ORDER BY last_seen DESC
+            LIMIT $3
+            "#
+        )
+        .bind(source_ip)
+        .bind(since)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch honeypot sessions")?;
+
+        Ok(sessions)
+    }
+
+    /// A honeypot session with its credentials and commands in order
+    pub async fn get_honeypot_session(&self, session_id: &str) -> Result<Option<HoneypotSessionDetail>> {
+        let session = sqlx::query_as::<_, HoneypotSession>("SELECT * FROM honeypot_sessions WHERE session_id = $1")
+            .bind(session_id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch honeypot session")?;
+
+        let Some(session) = session else {
+            return Ok(None);
+        };
+
+        let events = sqlx::query_as::<_, HoneypotSessionEvent>(
+            r#"
+            SELECT * FROM honeypot_events
+            WHERE session_id = $1 AND (username IS NOT NULL OR command IS NOT NULL)
+            ORDER BY observed_at
+            "#
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch honeypot session events")?;
+
+        Ok(Some(HoneypotSessionDetail { session, events }))
+    }
+
+    /// Most attempted credential pairs
+    pub async fn top_honeypot_credentials(&self, since: Option<DateTime<Utc>>, limit: i64) -> Result<Vec<CredentialStat>> {
+        let stats = sqlx::query_as::<_, CredentialStat>(
+            r#"
+            SELECT e.username, COALESCE(e.password, '') AS password,
+                   COUNT(*) AS attempts,
+                   COUNT(DISTINCT e.session_id) AS sessions,
+                   COUNT(DISTINCT s.source_ip) AS source_ips,
+                   MIN(e.observed_at) AS first_seen,
+                   MAX(e.observed_at) AS last_seen
+            FROM honeypot_events e
+            JOIN honeypot_sessions s ON s.session_id = e.session_id
+            WHERE e.username IS NOT NULL
+              AND ($1::timestamptz IS NULL OR e.observed_at >= $1)
+            GROUP BY e.username, COALESCE(e.password, '')
+            ORDER BY attempts DESC, last_seen DESC
+            LIMIT $2
+            "#
+        )
+        .bind(since)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch honeypot credential stats")?;
+
+        Ok(stats)
+    }
+
+    /// Most executed commands
+    pub async fn top_honeypot_commands(&self, since: Option<DateTime<Utc>>, limit: i64) -> Result<Vec<CommandStat>> {
+        let stats = sqlx::query_as::<_, CommandStat>(
+            r#"
+            SELECT e.command,
+                   COUNT(*) AS executions,
+                   COUNT(DISTINCT e.session_id) AS sessions,
+                   COUNT(DISTINCT s.source_ip) AS source_ips,
+                   MIN(e.observed_at) AS first_seen,
+                   MAX(e.observed_at) AS last_seen
+            FROM honeypot_events e
+            JOIN honeypot_sessions s ON s.session_id = e.session_id
+            WHERE e.command IS NOT NULL
+              AND ($1::timestamptz IS NULL OR e.observed_at >= $1)
+            GROUP BY e.command
+            ORDER BY executions DESC, last_seen DESC
+            LIMIT $2
+            "#
+        )
+        .bind(since)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch honeypot command stats")?;
+
+        Ok(stats)
+    }
+
+    /// Sessions, attackers, login attempts and commands per `bucket` (hour, day, week)
+    pub async fn honeypot_activity(&self, bucket: &str, since: DateTime<Utc>) -> Result<Vec<HoneypotActivity>> {
+        let activity = sqlx::query_as::<_, HoneypotActivity>(
+            r#"
+            WITH s AS (
+                SELECT date_trunc($1, first_seen) AS bucket,
+                       COUNT(*) AS sessions,
+                       COUNT(DISTINCT source_ip) AS source_ips
+                FROM honeypot_sessions
+                WHERE first_seen >= $2
+                GROUP BY 1
+            ),
+            e AS (
+                SELECT date_trunc($1, observed_at) AS bucket,
+                       COUNT(*) FILTER (WHERE username IS NOT NULL) AS login_attempts,
+                       COUNT(*) FILTER (WHERE command IS NOT NULL) AS commands
+                FROM honeypot_events
+                WHERE observed_at >= $2
+                GROUP BY 1
+            )
+            SELECT COALESCE(s.bucket, e.bucket) AS bucket,
+                   COALESCE(s.sessions, 0) AS sessions,
+                   COALESCE(s.source_ips, 0) AS source_ips,
+                   COALESCE(e.login_attempts, 0) AS login_attempts,
+                   COALESCE(e.commands, 0) AS commands
+            FROM s FULL OUTER JOIN e ON s.bucket = e.bucket
+            ORDER BY 1
+            "#
+        )
+        .bind(bucket)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch honeypot activity")?;
+
+        Ok(activity)
+    }
+
+    // ==================== Sources ====================
 
     /// Create or update a source
-    pub async fn upsert_source(&self, source: &IocSource) -> Result<IocSource> {
+    pub async fn upsert_source(&self, source: &IocSource, ctx: &AuditContext) -> Result<IocSource> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let before = sqlx::query_as::<_, IocSource>("SELECT * FROM ioc_sources WHERE name = $1 FOR UPDATE")
+            .bind(&source.name)
+            .fetch_optional(&mut *tx)
+            .await
+            .context("Failed to fetch existing source")?;
+
         let result = sqlx::query_as::<_, IocSource>(
             r#"
             INSERT INTO ioc_sources (id, name, source_type, url, api_key_required, reliability_score, enabled, created_at, updated_at)
@@ -333,10 +1746,21 @@ impl ThreatIntelRepo {
         .bind(source.api_key_required)
         .bind(source.reliability_score)
         .bind(source.enabled)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .context("Failed to upsert source")?;
 
+        record_audit(&mut *tx, ctx, AuditEntry {
+            action: AuditAction::SourceChange,
+            indicator_id: None,
+            source_id: Some(result.id),
+            before: before.as_ref().map(to_json),
+            after: Some(to_json(&result)),
+        })
+        .await?;
+
+        tx.commit().await.context("Failed to commit source upsert")?;
+
         Ok(result)
     }
 
@@ -352,6 +1776,18 @@ impl ThreatIntelRepo {
         Ok(sources)
     }
 
+    /// Save the position an incremental feed should resume from
+    pub async fn set_source_cursor(&self, source_id: Uuid, cursor: &str) -> Result<()> {
+        sqlx::query("UPDATE ioc_sources SET sync_cursor = $2, updated_at = NOW() WHERE id = $1")
+            .bind(source_id)
+            .bind(cursor)
+            .execute(&self.pool)
+            .await
+            .context("Failed to update source sync cursor")?;
+
+        Ok(())
+    }
+
     /// Update source last fetch time
     pub async fn update_source_fetch_time(&self, source_id: Uuid) -> Result<()> {
         sqlx::query("UPDATE ioc_sources SET last_fetch = NOW(), updated_at = NOW() WHERE id = $1")
@@ -363,6 +1799,190 @@ impl ThreatIntelRepo {
         Ok(())
     }
 
+    // ==================== Allowlist ====================
+
+    /// Reload API-managed allowlist entries into the in-memory allowlist
+    pub async fn reload_allowlist(&self) -> Result<usize> {
+        let entries = self.list_allowlist_entries().await?;
+        self.allowlist.set_entries(&entries);
+        Ok(entries.len())
+    }
+
+    /// List API-managed allowlist entries
+    pub async fn list_allowlist_entries(&self) -> Result<Vec<AllowlistEntry>> {
+        let entries = sqlx::query_as::<_, AllowlistEntry>(
+            "SELECT * FROM allowlist_entries ORDER BY created_at"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch allowlist entries")?;
+
+        Ok(entries)
+    }
+
+    /// Add an allowlist entry; it applies to writes as soon as this returns
+    pub async fn create_allowlist_entry(
+        &self,
+        req: &CreateAllowlistEntryRequest,
+        ctx: &AuditContext,
+    ) -> Result<AllowlistEntry> {
+        // Reject patterns that would not compile before storing them
+        AllowlistRule::new("api", req.match_type, &req.value, req.ioc_type.clone(), req.action, req.max_severity.clone())?;
+
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let entry = sqlx::query_as::<_, AllowlistEntry>(
+            r#"
+            INSERT INTO allowlist_entries (id, match_type, value, ioc_type, action, max_severity, comment, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(req.match_type)
+        .bind(req.value.trim())
+        .bind(&req.ioc_type)
+        .bind(req.action)
+        .bind(&req.max_severity)
+        .bind(&req.comment)
+        .bind(&ctx.actor)
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to create allowlist entry")?;
+
+        record_audit(&mut *tx, ctx, AuditEntry {
+            action: AuditAction::AllowlistCreate,
+            indicator_id: None,
+            source_id: None,
+            before: None,
+            after: Some(to_json(&entry)),
+        })
+        .await?;
+
+        tx.commit().await.context("Failed to commit allowlist entry")?;
+        self.reload_allowlist().await?;
+
+        Ok(entry)
+    }
+
+    /// Remove an allowlist entry, returning whether it existed
+    pub async fn delete_allowlist_entry(&self, id: Uuid, ctx: &AuditContext) -> Result<bool> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let deleted = sqlx::query_as::<_, AllowlistEntry>("DELETE FROM allowlist_entries WHERE id = $1 RETURNING *")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await
+            .context("Failed to delete allowlist entry")?;
+
+        if let Some(ref entry) = deleted {
+            record_audit(&mut *tx, ctx, AuditEntry {
+                action: AuditAction::AllowlistDelete,
+                indicator_id: None,
+                source_id: None,
+                before: Some(to_json(entry)),
+                after: None,
+            })
+            .await?;
+        }
+
+        tx.commit().await.context("Failed to commit allowlist entry removal")?;
+        self.reload_allowlist().await?;
+
+        Ok(deleted.is_some())
+    }
+
+    /// What the allowlist suppressed, per feed, list and action
+    pub async fn get_suppression_report(&self, since: Option<DateTime<Utc>>) -> Result<Vec<SuppressionSummary>> {
+        let report = sqlx::query_as::<_, SuppressionSummary>(
+            r#"
+            SELECT feed, list_name, action,
+                   COUNT(*) AS suppressed,
+                   MAX(created_at) AS last_suppressed_at,
+                   (array_agg(DISTINCT value))[1:5] AS examples
+            FROM allowlist_suppressions
+            WHERE $1::timestamptz IS NULL OR created_at >= $1
+            GROUP BY feed, list_name, action
+            ORDER BY feed, suppressed DESC
+            "#
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch suppression report")?;
+
+        Ok(report)
+    }
+
+    // ==================== Audit ====================
+
+    /// Query the audit log
+    pub async fn list_audit_events(&self, filter: &AuditFilter) -> Result<PaginatedResponse<AuditEvent>> {
+        let page = filter.page.unwrap_or(1).max(1);
+        let per_page = filter.per_page.unwrap_or(50).clamp(1, 1000);
+        let offset = (page - 1) * per_page;
+
+        let push_conditions = |qb: &mut QueryBuilder<'_, Postgres>| {
+            qb.push(" WHERE 1=1");
+            if let Some(indicator_id) = filter.indicator_id {
+                qb.push(" AND indicator_id = ").push_bind(indicator_id);
+            }
+            if let Some(ref actor) = filter.actor {
+                qb.push(" AND actor = ").push_bind(actor.clone());
+            }
+            if let Some(ref action) = filter.action {
+                qb.push(" AND action = ").push_bind(action.clone());
+            }
+            if let Some(since) = filter.since {
+                qb.push(" AND created_at >= ").push_bind(since);
+            }
+        };
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM audit_events");
+        push_conditions(&mut query);
+        query
+            .push(" ORDER BY created_at DESC LIMIT ")
+            .push_bind(per_page)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let events = query
+            .build_query_as::<AuditEvent>()
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to query audit log")?;
+
+        let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM audit_events");
+        push_conditions(&mut count);
+
+        let total: (i64,) = count
+            .build_query_as()
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to count audit events")?;
+
+        Ok(PaginatedResponse {
+            data: events,
+            total: total.0,
+            page,
+            per_page,
+            total_pages: (total.0 as f64 / per_page as f64).ceil() as i64,
+        })
+    }
+
+    /// Full audit history of a single indicator, oldest first
+    pub async fn get_indicator_history(&self, indicator_id: Uuid) -> Result<Vec<AuditEvent>> {
+        let events = sqlx::query_as::<_, AuditEvent>(
+            "SELECT * FROM audit_events WHERE indicator_id = $1 ORDER BY created_at ASC"
+        )
+        .bind(indicator_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch indicator history")?;
+
+        Ok(events)
+    }
+
     // ==================== Statistics ====================
 
     /// Get dashboard statistics
@@ -407,3 +2027,595 @@ impl ThreatIntelRepo {
         })
     }
 }
+
+/// Append the WHERE clause for an indicator filter
+fn push_indicator_filters(qb: &mut QueryBuilder<'_, Postgres>, filter: &IndicatorFilter) {
+    qb.push(" WHERE 1=1");
+    if let Some(ref ioc_type) = filter.ioc_type {
+        qb.push(" AND ioc_type = ").push_bind(ioc_type.clone());
+    }
+    if let Some(ref severity) = filter.severity {
+        qb.push(" AND severity = ").push_bind(severity.clone());
+    }
+    if let Some(min_confidence) = filter.min_confidence {
+        qb.push(" AND confidence >= ").push_bind(min_confidence);
+    }
+    if let Some(min_threat_score) = filter.min_threat_score {
+        qb.push(" AND threat_score >= ").push_bind(min_threat_score);
+    }
+    if let Some(ref tags) = filter.tags {
+        qb.push(" AND tags && ").push_bind(tags.clone());
+    }
+    if let Some(source_id) = filter.source_id {
+        qb.push(" AND ").push_bind(source_id).push(" = ANY(source_ids)");
+    }
+    if let Some(after) = filter.first_seen_after {
+        qb.push(" AND first_seen >= ").push_bind(after);
+    }
+    if let Some(before) = filter.first_seen_before {
+        qb.push(" AND first_seen < ").push_bind(before);
+    }
+    if let Some(ref search) = filter.search {
+        qb.push(" AND value ILIKE ").push_bind(format!("%{}%", refang(search.trim())));
+    }
+    if let Some(ref domain) = filter.registrable_domain {
+        let domain = refang(domain.trim());
+        let registrable = registrable_domain(&domain).unwrap_or_else(|| domain.to_lowercase());
+        qb.push(" AND registrable_domain = ").push_bind(registrable);
+    }
+}
+
+/// Insert or merge a single indicator and audit the change, inside the caller's transaction
+async fn upsert_indicator_in_tx(
+    tx: &mut Transaction<'_, Postgres>,
+    allowlist: &Allowlist,
+    req: &CreateIndicatorRequest,
+    source_id: Option<Uuid>,
+    ctx: &AuditContext,
+) -> Result<Indicator> {
+    let ioc_type = req.ioc_type.clone().or_else(|| detect_ioc_type(&req.value))
+        .ok_or_else(|| anyhow::anyhow!("Could not detect IOC type for: {}", req.value))?;
+    
+    let normalized_value = normalize_ioc(&req.value, &ioc_type);
+    let hash_algorithm = match ioc_type {
+        IocType::Hash => req.hash_algorithm.or_else(|| detect_hash_algorithm(&normalized_value)),
+        _ => None,
+    };
+    let network = match ioc_type {
+        IocType::Cidr => Some(
+            indicator_network(&normalized_value, &ioc_type)
+                .ok_or_else(|| unaligned_range_error(&normalized_value))?,
+        ),
+        _ => None,
+    };
+    let registrable_domain = indicator_registrable_domain(&normalized_value, &ioc_type);
+    let now = Utc::now();
+    let expiration = req.expiration_days.map(|days| now + Duration::days(days as i64));
+
+    let allowlist_hit = allowlist.check(&ioc_type, &normalized_value);
+    let mut tags = req.tags.clone().unwrap_or_default();
+    let mut severity = req.severity.clone().unwrap_or(Severity::Unknown);
+    let mut severity_cap = None;
+    if let Some(hit) = &allowlist_hit {
+        if hit.action == AllowlistAction::Reject {
+            return Err(AllowlistRejection {
+                ioc_type,
+                value: normalized_value,
+                hit: hit.clone(),
+            }
+            .into());
+        }
+        tags.push(ALLOWLISTED_TAG.to_string());
+        tags.push(format!("allowlist:{}", hit.list_name));
+        severity_cap = hit.severity_cap();
+        if let Some(cap) = &severity_cap {
+            severity = severity.min(cap.clone());
+        }
+    }
+
+    let before = sqlx::query_as::<_, Indicator>(
+        "SELECT * FROM indicators WHERE ioc_type = $1 AND value = $2 FOR UPDATE"
+    )
+    .bind(&ioc_type)
+    .bind(&normalized_value)
+    .fetch_optional(&mut **tx)
+    .await
+    .context("Failed to fetch existing indicator")?;
+    
+    let indicator = sqlx::query_as::<_, Indicator>(
+        r#"
+        INSERT INTO indicators (
+            id, ioc_type, value, severity, confidence, threat_score, tlp,
+            first_seen, last_seen, expiration, tags, source_ids, created_at, updated_at,
+            hash_algorithm, network, registrable_domain
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8, $9, $10, $11, $8, $8, $12, $13, $14)
+        ON CONFLICT (ioc_type, value) DO UPDATE SET
+            registrable_domain = EXCLUDED.registrable_domain,
+            hash_algorithm = COALESCE(indicators.hash_algorithm, EXCLUDED.hash_algorithm),
+            severity = LEAST(
+                CASE WHEN EXCLUDED.severity > indicators.severity THEN EXCLUDED.severity ELSE indicators.severity END,
+                COALESCE($15, 'critical'::severity)
+            ),
+            confidence = GREATEST(indicators.confidence, EXCLUDED.confidence),
+            last_seen = EXCLUDED.last_seen,
+            tags = ARRAY(SELECT DISTINCT unnest(array_cat(indicators.tags, EXCLUDED.tags))),
+            source_ids = ARRAY(SELECT DISTINCT unnest(array_cat(indicators.source_ids, EXCLUDED.source_ids))),
+            updated_at = EXCLUDED.updated_at
+        RETURNING *
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(&ioc_type)
+    .bind(&normalized_value)
+    .bind(severity)
+    .bind(req.confidence.unwrap_or(50))
+    .bind(req.confidence.unwrap_or(50)) // Initial threat_score = confidence
+    .bind(req.tlp.clone().unwrap_or(Tlp::Amber))
+    .bind(now)
+    .bind(expiration)
+    .bind(&tags)
+    .bind(source_id.map(|id| vec![id]).unwrap_or_default())
+    .bind(hash_algorithm)
+    .bind(network)
+    .bind(registrable_domain)
+    .bind(severity_cap)
+    .fetch_one(&mut **tx)
+    .await
+    .context("Failed to upsert indicator")?;
+
+    if let Some(hit) = &allowlist_hit {
+        record_suppression(&mut **tx, &feed_name(req, ctx), &indicator.ioc_type, &indicator.value, hit).await?;
+    }
+
+    let source_added = match (&before, source_id) {
+        (Some(existing), Some(id)) => !existing.source_ids.contains(&id),
+        _ => false,
+    };
+
+    record_audit(&mut **tx, ctx, AuditEntry {
+        action: if before.is_some() { AuditAction::Update } else { AuditAction::Create },
+        indicator_id: Some(indicator.id),
+        source_id,
+        before: before.as_ref().map(to_json),
+        after: Some(to_json(&indicator)),
+    })
+    .await?;
+
+    if source_added {
+        record_audit(&mut **tx, ctx, AuditEntry {
+            action: AuditAction::SourceChange,
+            indicator_id: Some(indicator.id),
+            source_id,
+            before: before.as_ref().map(|b| serde_json::json!({ "source_ids": b.source_ids })),
+            after: Some(serde_json::json!({ "source_ids": indicator.source_ids })),
+        })
+        .await?;
+    }
+
+    if let Some(context) = &req.context {
+        sqlx::query(
+            r#"
+            INSERT INTO indicator_context (
+                id, indicator_id, source_id, report_id, report_name, description, report_urls, attack_ids,
+                created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NOW(), NOW())
+            ON CONFLICT (indicator_id, report_id) DO UPDATE SET
+                source_id = COALESCE(EXCLUDED.source_id, indicator_context.source_id),
+                report_name = COALESCE(EXCLUDED.report_name, indicator_context.report_name),
+                description = COALESCE(EXCLUDED.description, indicator_context.description),
+                report_urls = EXCLUDED.report_urls,
+                attack_ids = EXCLUDED.attack_ids,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(indicator.id)
+        .bind(source_id)
+        .bind(&context.report_id)
+        .bind(&context.report_name)
+        .bind(&context.description)
+        .bind(&context.report_urls)
+        .bind(&context.attack_ids)
+        .execute(&mut **tx)
+        .await
+        .context("Failed to store indicator context")?;
+    }
+
+    Ok(indicator)
+}
+
+/// Indicator row joined with its aggregated enrichments
+#[derive(sqlx::FromRow)]
+struct IndicatorWithEnrichments {
+    #[sqlx(flatten)]
+    indicator: Indicator,
+    enrichments: sqlx::types::Json<Vec<Enrichment>>,
+}
+
+/// Stored network containing a queried address
+#[derive(sqlx::FromRow)]
+struct NetworkMatch {
+    queried: IpNetwork,
+    #[sqlx(flatten)]
+    indicator: Indicator,
+}
+
+/// A range that is not a single CIDR block cannot be stored as one indicator
+fn unaligned_range_error(range: &str) -> anyhow::Error {
+    let blocks = parse_ip_range(range)
+        .map(|(start, end)| ip_range_to_networks(start, end))
+        .unwrap_or_default()
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    anyhow::anyhow!("Range {} does not map onto a single CIDR block; import it as: {}", range, blocks)
+}
+
+/// Relation row joined with the indicator on its other end
+#[derive(sqlx::FromRow)]
+struct RelatedRow {
+    relation_id: Uuid,
+    source_indicator_id: Uuid,
+    target_indicator_id: Uuid,
+    relation_type: String,
+    relation_created_at: chrono::DateTime<Utc>,
+    #[sqlx(flatten)]
+    indicator: Indicator,
+}
+
+/// Link two indicators; an existing link of the same type is left untouched
+async fn insert_relation<'e, E: PgExecutor<'e>>(
+    executor: E,
+    source_indicator_id: Uuid,
+    target_indicator_id: Uuid,
+    relation_type: RelationType,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO indicator_relations (id, source_indicator_id, target_indicator_id, relation_type)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (source_indicator_id, target_indicator_id, relation_type) DO NOTHING
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(source_indicator_id)
+    .bind(target_indicator_id)
+    .bind(relation_type.as_str())
+    .execute(executor)
+    .await
+    .context("Failed to link indicators")?;
+
+    Ok(())
+}
+
+/// Feed an indicator came from, for the suppression report
+fn feed_name(req: &CreateIndicatorRequest, ctx: &AuditContext) -> String {
+    req.source.clone().unwrap_or_else(|| ctx.origin.to_string())
+}
+
+/// Remember that an allowlist rule rejected, tagged or capped an indicator
+async fn record_suppression<'e, E: PgExecutor<'e>>(
+    executor: E,
+    feed: &str,
+    ioc_type: &IocType,
+    value: &str,
+    hit: &AllowlistHit,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO allowlist_suppressions (id, feed, ioc_type, value, action, list_name, pattern, entry_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(feed)
+    .bind(ioc_type)
+    .bind(value)
+    .bind(hit.action)
+    .bind(&hit.list_name)
+    .bind(&hit.pattern)
+    .bind(hit.entry_id)
+    .execute(executor)
+    .await
+    .context("Failed to record allowlist suppression")?;
+
+    Ok(())
+}
+
+/// A single mutation to be written to the audit log
+struct AuditEntry {
+    action: AuditAction,
+    indicator_id: Option<Uuid>,
+    source_id: Option<Uuid>,
+    before: Option<Value>,
+    after: Option<Value>,
+}
+
+/// Fill the gaps in `hashes` from a file object that is merged away
+fn absorb_file_object(hashes: &mut FileHashes, file: FileObject) {
+    hashes.md5 = hashes.md5.take().or(file.md5);
+    hashes.sha1 = hashes.sha1.take().or(file.sha1);
+    hashes.sha256 = hashes.sha256.take().or(file.sha256);
+    hashes.sha512 = hashes.sha512.take().or(file.sha512);
+    hashes.ssdeep = hashes.ssdeep.take().or(file.ssdeep);
+    hashes.tlsh = hashes.tlsh.take().or(file.tlsh);
+    hashes.imphash = hashes.imphash.take().or(file.imphash);
+    hashes.size = hashes.size.or(file.size);
+    hashes.file_type = hashes.file_type.take().or(file.file_type);
+    hashes.names.extend(file.names);
+}
+
+/// Append an audit event using the caller's executor so it commits with the mutation
+async fn record_audit<'e, E: PgExecutor<'e>>(
+    executor: E,
+    ctx: &AuditContext,
+    entry: AuditEntry,
+) -> Result<()> {
+    let diff = match (&entry.before, &entry.after) {
+        (Some(before), Some(after)) => Some(json_diff(before, after)),
+        _ => None,
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO audit_events (
+            id, indicator_id, source_id, action, actor, actor_verified, origin, request_id,
+            reason, before, after, diff, created_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, NOW())
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(entry.indicator_id)
+    .bind(entry.source_id)
+    .bind(entry.action.as_str())
+    .bind(&ctx.actor)
+    .bind(ctx.actor_verified)
+    .bind(ctx.origin.to_string())
+    .bind(&ctx.request_id)
+    .bind(&ctx.reason)
+    .bind(entry.before)
+    .bind(entry.after)
+    .bind(diff)
+    .execute(executor)
+    .await
+    .context("Failed to record audit event")?;
+
+    Ok(())
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// Field-level diff of two JSON objects as `{ field: { from, to } }`
+fn json_diff(before: &Value, after: &Value) -> Value {
+    let mut diff = serde_json::Map::new();
+
+    if let (Some(before), Some(after)) = (before.as_object(), after.as_object()) {
+        for (key, new_value) in after {
+            if key == "updated_at" {
+                continue;
+            }
+            let old_value = before.get(key).unwrap_or(&Value::Null);
+            if old_value != new_value {
+                diff.insert(key.clone(), serde_json::json!({ "from": old_value, "to": new_value }));
+            }
+        }
+    }
+
+    Value::Object(diff)
+}
+
+/// These tests need a migrated PostgreSQL database named by `TEST_DATABASE_URL`
+/// and are skipped when it is not set.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AllowlistMatch, PdnsRecordType};
+
+    async fn test_repo() -> Option<ThreatIntelRepo> {
+        let url = std::env::var("TEST_DATABASE_URL").ok()?;
+        Some(ThreatIntelRepo::new(&url).await.expect("connect to TEST_DATABASE_URL"))
+    }
+
+    /// A domain no other test run uses
+    fn unique_domain() -> String {
+        format!("pdns-{}.example", Uuid::new_v4().simple())
+    }
+
+    fn observation(rrname: &str, rrtype: PdnsRecordType, rdata: &str) -> DnsObservation {
+        DnsObservation {
+            rrname: rrname.to_string(),
+            rrtype,
+            rdata: rdata.to_string(),
+        }
+    }
+
+    fn indicator_request(value: &str) -> CreateIndicatorRequest {
+        CreateIndicatorRequest {
+            value: value.to_string(),
+            ioc_type: None,
+            hash_algorithm: None,
+            severity: None,
+            confidence: None,
+            tlp: None,
+            tags: None,
+            source: None,
+            expiration_days: None,
+            context: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn passive_dns_counts_repeat_answers_and_links_known_indicators() {
+        let Some(repo) = test_repo().await else { return };
+        let domain = unique_domain();
+        let ctx = AuditContext::scheduler("test");
+
+        let domain_indicator = repo.upsert_indicator(&indicator_request(&domain), None, &ctx).await.unwrap();
+        let ip_indicator = repo.upsert_indicator(&indicator_request("198.51.100.77"), None, &ctx).await.unwrap();
+
+        let answer = observation(&domain, PdnsRecordType::A, "198.51.100.77");
+        // Duplicates within one batch count once
+        repo.record_passive_dns(&[answer.clone(), answer.clone()]).await.unwrap();
+        repo.record_passive_dns(&[answer, observation(&domain, PdnsRecordType::Mx, "mx.example.net")])
+            .await
+            .unwrap();
+
+        let (forward, _) = repo.get_passive_dns(&domain, 10).await.unwrap();
+        assert_eq!(forward.len(), 2);
+        let a = forward.iter().find(|r| r.rrtype == PdnsRecordType::A).unwrap();
+        assert_eq!(a.count, 2);
+        assert!(a.last_seen >= a.first_seen);
+        let mx = forward.iter().find(|r| r.rrtype == PdnsRecordType::Mx).unwrap();
+        assert_eq!(mx.count, 1);
+
+        let (_, reverse) = repo.get_passive_dns("198.51.100.77", 100).await.unwrap();
+        assert!(reverse.iter().any(|r| r.rrname == domain));
+
+        // Only the A record has indicators on both ends
+        let related = repo.get_related_indicators(domain_indicator.id).await.unwrap();
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].0.relation_type, RelationType::ResolvesTo.as_str());
+        assert_eq!(related[0].1.id, ip_indicator.id);
+
+        repo.delete_indicator(domain_indicator.id, &ctx).await.unwrap();
+        repo.delete_indicator(ip_indicator.id, &ctx).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn resolution_churn_counts_addresses_and_networks_of_one_name() {
+        let Some(repo) = test_repo().await else { return };
+        let domain = unique_domain();
+
+        repo.record_passive_dns(&[
+            observation(&domain, PdnsRecordType::A, "203.0.113.1"),
+            observation(&domain, PdnsRecordType::A, "203.0.113.2"),
+            observation(&domain, PdnsRecordType::A, "198.51.100.1"),
+            observation(&domain, PdnsRecordType::Aaaa, "2001:db8::1"),
+            observation(&domain, PdnsRecordType::Aaaa, "2001:db8::2"),
+            observation(&domain, PdnsRecordType::Ns, "ns1.example.net"),
+            observation(&unique_domain(), PdnsRecordType::A, "192.0.2.1"),
+        ])
+        .await
+        .unwrap();
+
+        // 5 addresses in 203.0.113/16, 198.51/16 and 2001:db8::/32
+        assert_eq!(repo.get_resolution_churn(&domain, Duration::hours(1)).await.unwrap(), (5, 3));
+        assert_eq!(repo.get_resolution_churn(&unique_domain(), Duration::hours(1)).await.unwrap(), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn audit_events_mark_client_supplied_actors_unverified() {
+        let Some(repo) = test_repo().await else { return };
+        let actor = format!("audit-{}", Uuid::new_v4().simple());
+        let req = CreateAllowlistEntryRequest {
+            match_type: AllowlistMatch::Exact,
+            value: unique_domain(),
+            ioc_type: None,
+            action: AllowlistAction::Tag,
+            max_severity: None,
+            comment: None,
+        };
+
+        let entry = repo.create_allowlist_entry(&req, &AuditContext::api(&actor, None)).await.unwrap();
+        repo.delete_allowlist_entry(entry.id, &AuditContext::scheduler(&actor)).await.unwrap();
+
+        let filter = AuditFilter { actor: Some(actor), ..Default::default() };
+        let events = repo.list_audit_events(&filter).await.unwrap().data;
+        let verified = |action: AuditAction| {
+            events.iter().find(|e| e.action == action.as_str()).map(|e| e.actor_verified)
+        };
+        assert_eq!(verified(AuditAction::AllowlistCreate), Some(false));
+        assert_eq!(verified(AuditAction::AllowlistDelete), Some(true));
+    }
+
+    #[tokio::test]
+    async fn score_changes_are_audited_with_before_and_after() {
+        let Some(repo) = test_repo().await else { return };
+        let ctx = AuditContext::scheduler("rescore-test");
+        let indicator = repo.upsert_indicator(&indicator_request(&unique_domain()), None, &ctx).await.unwrap();
+
+        repo.update_threat_score(indicator.id, 95, &ctx).await.unwrap();
+
+        let history = repo.get_indicator_history(indicator.id).await.unwrap();
+        let event = history.iter().find(|e| e.action == AuditAction::ScoreChange.as_str()).unwrap();
+        assert_eq!(event.actor, "rescore-test");
+        assert_eq!(event.before.as_ref().unwrap()["threat_score"], indicator.threat_score);
+        assert_eq!(event.after.as_ref().unwrap()["threat_score"], 95);
+        assert_eq!(event.diff.as_ref().unwrap()["threat_score"]["to"], 95);
+
+        repo.delete_indicator(indicator.id, &ctx).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn expired_indicators_are_deleted_with_one_audit_event_each() {
+        let Some(repo) = test_repo().await else { return };
+        let ctx = AuditContext::scheduler("expire-test");
+        let expired = repo.upsert_indicator(&indicator_request(&unique_domain()), None, &ctx).await.unwrap();
+        let current = repo.upsert_indicator(&indicator_request(&unique_domain()), None, &ctx).await.unwrap();
+
+        sqlx::query("UPDATE indicators SET expiration = NOW() - INTERVAL '1 day' WHERE id = $1")
+            .bind(expired.id)
+            .execute(repo.pool())
+            .await
+            .unwrap();
+
+        assert!(repo.delete_expired(&ctx).await.unwrap() >= 1);
+        assert!(repo.get_indicator(expired.id).await.unwrap().is_none());
+        assert!(repo.get_indicator(current.id).await.unwrap().is_some());
+
+        let history = repo.get_indicator_history(expired.id).await.unwrap();
+        let deletes: Vec<_> = history.iter().filter(|e| e.action == AuditAction::Delete.as_str()).collect();
+        assert_eq!(deletes.len(), 1);
+        assert_eq!(deletes[0].actor, "expire-test");
+        assert_eq!(deletes[0].before.as_ref().unwrap()["id"], expired.id.to_string());
+        assert!(deletes[0].after.is_none());
+
+        repo.delete_indicator(current.id, &ctx).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn source_upserts_are_audited() {
+        let Some(repo) = test_repo().await else { return };
+        let actor = format!("source-{}", Uuid::new_v4().simple());
+        let ctx = AuditContext::scheduler(&actor);
+        let mut source = IocSource {
+            id: Uuid::new_v4(),
+            name: actor.clone(),
+            source_type: "feed".to_string(),
+            url: Some("https://feed.example/iocs.txt".to_string()),
+            api_key_required: false,
+            reliability_score: 50,
+            enabled: true,
+            last_fetch: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            sync_cursor: None,
+        };
+
+        let created = repo.upsert_source(&source, &ctx).await.unwrap();
+        source.reliability_score = 80;
+        repo.upsert_source(&source, &ctx).await.unwrap();
+
+        let filter = AuditFilter { actor: Some(actor), ..Default::default() };
+        let mut events = repo.list_audit_events(&filter).await.unwrap().data;
+        events.sort_by_key(|e| e.created_at);
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e.action == AuditAction::SourceChange.as_str()));
+        assert!(events.iter().all(|e| e.source_id == Some(created.id)));
+        assert!(events[0].before.is_none());
+        assert_eq!(events[1].diff.as_ref().unwrap()["reliability_score"]["from"], 50);
+        assert_eq!(events[1].diff.as_ref().unwrap()["reliability_score"]["to"], 80);
+
+        sqlx::query("DELETE FROM ioc_sources WHERE id = $1")
+            .bind(created.id)
+            .execute(repo.pool())
+            .await
+            .unwrap();
+    }
+}
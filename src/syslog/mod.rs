@@ -0,0 +1,519 @@
+//! Syslog receiver: turns firewall and proxy logs into sightings
+//!
+//! Devices that cannot call the REST API send syslog over UDP or TCP
+//! (RFC 5424 or RFC 3164, plain, CEF or LEEF). The addresses, host names and
+//! URLs in each line are matched against an in-memory copy of the stored
+//! IP, network, domain and URL indicators. Hits are grouped per indicator
+//! and sending device over a short window and recorded as one sighting per
+//! group, with the match count, first and last time and latest log line as
+//! context.
+
+pub mod parse;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use ipnetwork::IpNetwork;
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::net::{TcpListener, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::time::MissedTickBehavior;
+use uuid::Uuid;
+
+use crate::models::{AuditContext, IocType};
+use crate::storage::ThreatIntelRepo;
+use parse::{parse_line, LogRecord, Observable};
+
+/// Sighting source and audit actor
+pub const SOURCE: &str = "syslog";
+
+/// Longest message accepted (UDP datagram or TCP frame)
+const MAX_MESSAGE_BYTES: usize = 64 * 1024;
+
+/// Log text kept in a sighting's context
+const MAX_CONTEXT_CHARS: usize = 2048;
+
+/// Matched lines waiting to be grouped; more are dropped rather than
+/// slowing down the receivers
+const SIGHTING_QUEUE: usize = 10_000;
+
+/// Groups held before the window ends; reaching it stores them early
+const MAX_PENDING_GROUPS: usize = 50_000;
+
+/// Listener settings
+#[derive(Debug, Clone)]
+pub struct SyslogConfig {
+    pub udp: Option<SocketAddr>,
+    pub tcp: Option<SocketAddr>,
+    /// How often the indicator watchlist is reloaded
+    pub refresh_interval: Duration,
+    /// How long matches of an indicator from one device are grouped into a
+    /// single sighting
+    pub aggregate_window: Duration,
+}
+
+/// Stored indicators that log lines are matched against
+#[derive(Debug, Default)]
+pub struct Watchlist {
+    ips: HashMap<IpAddr, Uuid>,
+    networks: Vec<(IpNetwork, Uuid)>,
+    domains: HashMap<String, Uuid>,
+    urls: HashMap<String, Uuid>,
+}
+
+impl Watchlist {
+    /// Build from `(id, type, normalized value)` rows
+    pub fn new(indicators: Vec<(Uuid, IocType, String)>) -> Self {
+        let mut watchlist = Self::default();
+        for (id, ioc_type, value) in indicators {
+            match ioc_type {
+                IocType::Ip => {
+                    if let Ok(ip) = value.parse() {
+                        watchlist.ips.insert(ip, id);
+                    }
+                }
+                IocType::Cidr => {
+                    if let Ok(network) = value.parse() {
+                        watchlist.networks.push((network, id));
+                    }
+                }
+                IocType::Domain => {
+                    watchlist.domains.insert(value, id);
+                }
+                IocType::Url => {
+                    watchlist.urls.insert(value, id);
+                }
+                _ => {}
+            }
+        }
+        // Most specific network first
+        watchlist.networks.sort_by_key(|(network, _)| std::cmp::Reverse(network.prefix()));
+        watchlist
+    }
+
+    pub fn len(&self) -> usize {
+        self.ips.len() + self.networks.len() + self.domains.len() + self.urls.len()
+    }
+
+    /// Indicator matching an observable: an exact IP, else the most specific
+    /// containing network; a domain or any of its parent domains; an exact URL
+    pub fn find(&self, observable: &Observable) -> Option<Uuid> {
+        match observable.ioc_type {
+            IocType::Ip => {
+                let ip: IpAddr = observable.value.parse().ok()?;
+                self.ips.get(&ip).copied().or_else(|| {
+                    self.networks
+                        .iter()
+                        .find(|(network, _)| network.contains(ip))
+                        .map(|(_, id)| *id)
+                })
+            }
+            IocType::Domain => {
+                let mut domain = observable.value.as_str();
+                loop {
+                    if let Some(id) = self.domains.get(domain) {
+                        return Some(*id);
+                    }
+                    domain = domain.split_once('.').map(|(_, parent)| parent).filter(|p| p.contains('.'))?;
+                }
+            }
+            IocType::Url => self.urls.get(&observable.value).copied(),
+            _ => None,
+        }
+    }
+}
+
+/// Watchlist shared by the receivers and swapped on each reload
+#[derive(Clone, Default)]
+pub struct SharedWatchlist(Arc<RwLock<Arc<Watchlist>>>);
+
+impl SharedWatchlist {
+    fn get(&self) -> Arc<Watchlist> {
+        self.0.read().unwrap().clone()
+    }
+
+    fn set(&self, watchlist: Watchlist) {
+        *self.0.write().unwrap() = Arc::new(watchlist);
+    }
+}
+
+/// Indicator hit in a log line, waiting to be stored
+struct LogSighting {
+    indicator_id: Uuid,
+    device: String,
+    observed_at: DateTime<Utc>,
+    context: serde_json::Value,
+}
+
+/// Matches of one indicator from one device in the current window
+struct SightingGroup {
+    count: u64,
+    first_seen: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+    /// Context of the latest match
+    context: serde_json::Value,
+}
+
+type SightingGroups = HashMap<(Uuid, String), SightingGroup>;
+
+/// Count a match into its (indicator, device) group
+fn group_sighting(groups: &mut SightingGroups, sighting: LogSighting) {
+    let LogSighting { indicator_id, device, observed_at, context } = sighting;
+    let group = groups.entry((indicator_id, device)).or_insert_with(|| SightingGroup {
+        count: 0,
+        first_seen: observed_at,
+        last_seen: observed_at,
+        context: serde_json::Value::Null,
+    });
+    group.count += 1;
+    group.first_seen = group.first_seen.min(observed_at);
+    if observed_at >= group.last_seen || group.context.is_null() {
+        group.last_seen = group.last_seen.max(observed_at);
+        group.context = context;
+    }
+}
+
+/// Sighting context of a group: the latest match's context plus the count
+/// and time span
+fn group_context(group: SightingGroup) -> serde_json::Value {
+    let mut context = group.context;
+    if let Some(fields) = context.as_object_mut() {
+        fields.insert("count".to_string(), json!(group.count));
+        fields.insert("first_seen".to_string(), json!(group.first_seen));
+        fields.insert("last_seen".to_string(), json!(group.last_seen));
+    }
+    context
+}
+
+/// Matches of one log line, one per indicator
+fn match_line(line: &str, peer: SocketAddr, watchlist: &Watchlist) -> Vec<LogSighting> {
+    let record = parse_line(line);
+    let device = record.host.clone().unwrap_or_else(|| peer.ip().to_string());
+    // Device clocks are not trusted past the present
+    let observed_at = record.timestamp.map_or_else(Utc::now, |ts| ts.min(Utc::now()));
+    let mut sightings: Vec<LogSighting> = vec![];
+
+    for observable in record.observables() {
+        let Some(indicator_id) = watchlist.find(&observable) else {
+            continue;
+        };
+        if sightings.iter().any(|s| s.indicator_id == indicator_id) {
+            continue;
+        }
+        sightings.push(LogSighting {
+            indicator_id,
+            device: device.clone(),
+            observed_at,
+            context: sighting_context(line, peer, &device, &record, &observable),
+        });
+    }
+
+    sightings
+}
+
+fn sighting_context(line: &str, peer: SocketAddr, device: &str, record: &LogRecord, observable: &Observable) -> serde_json::Value {
+    let log: String = line.chars().take(MAX_CONTEXT_CHARS).collect();
+    json!({
+        "log": log,
+        "truncated": log.len() < line.len(),
+        "format": record.format,
+        "device": device,
+        "peer": peer.to_string(),
+        "app": record.app,
+        "vendor": record.vendor,
+        "product": record.product,
+        "field": observable.field,
+        "matched": observable.value,
+    })
+}
+
+/// Parse, match and queue the lines of one message
+fn handle_message(message: &str, peer: SocketAddr, transport: &'static str, watchlist: &SharedWatchlist, queue: &mpsc::Sender<LogSighting>) {
+    let watchlist = watchlist.get();
+    for line in message.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        metrics::counter!("syslog_messages_total", "transport" => transport).increment(1);
+
+        for sighting in match_line(line, peer, &watchlist) {
+            metrics::counter!("syslog_matches_total").increment(1);
+            if queue.try_send(sighting).is_err() {
+                metrics::counter!("syslog_sightings_dropped_total").increment(1);
+            }
+        }
+    }
+}
+
+/// Bind the configured listeners and start receiving. Bind failures are
+/// returned so a misconfigured port stops startup.
+pub async fn spawn(repo: ThreatIntelRepo, config: SyslogConfig) -> Result<()> {
+    let watchlist = SharedWatchlist::default();
+    let (queue, sightings) = mpsc::channel(SIGHTING_QUEUE);
+
+    if let Some(addr) = config.udp {
+        let socket = UdpSocket::bind(addr)
+            .await
+            .with_context(|| format!("Failed to bind syslog UDP listener on {}", addr))?;
+        tracing::info!("Syslog UDP listener on {}", addr);
+        tokio::spawn(receive_udp(socket, watchlist.clone(), queue.clone()));
+    }
+
+    if let Some(addr) = config.tcp {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to bind syslog TCP listener on {}", addr))?;
+        tracing::info!("Syslog TCP listener on {}", addr);
+        tokio::spawn(receive_tcp(listener, watchlist.clone(), queue.clone()));
+    }
+
+    tokio::spawn(refresh_watchlist(repo.clone(), watchlist, config.refresh_interval));
+    tokio::spawn(store_sightings(repo, sightings, config.aggregate_window));
+
+    Ok(())
+}
+
+/// Reload the watchlist from storage every `interval`
+async fn refresh_watchlist(repo: ThreatIntelRepo, watchlist: SharedWatchlist, interval: Duration) {
+    loop {
+        match repo.list_watch_indicators().await {
+            Ok(indicators) => {
+                let loaded = Watchlist::new(indicators);
+                tracing::debug!(indicators = loaded.len(), "Reloaded syslog watchlist");
+                watchlist.set(loaded);
+            }
+            Err(e) => tracing::warn!(error = %e, "Failed to reload syslog watchlist"),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Group queued matches and store one sighting per group at the end of
+/// each `window`
+async fn store_sightings(repo: ThreatIntelRepo, mut sightings: mpsc::Receiver<LogSighting>, window: Duration) {
+    let ctx = AuditContext::collector(SOURCE);
+    let mut groups = SightingGroups::new();
+    let mut ticks = tokio::time::interval(window);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            received = sightings.recv() => {
+                let Some(sighting) = received else { break };
+                group_sighting(&mut groups, sighting);
+                if groups.len() >= MAX_PENDING_GROUPS {
+                    flush_groups(&repo, &mut groups, &ctx).await;
+                }
+            }
+            _ = ticks.tick() => flush_groups(&repo, &mut groups, &ctx).await,
+        }
+    }
+    flush_groups(&repo, &mut groups, &ctx).await;
+}
+
+async fn flush_groups(repo: &ThreatIntelRepo, groups: &mut SightingGroups, ctx: &AuditContext) {
+    for ((indicator_id, _), group) in groups.drain() {
+        let observed_at = group.last_seen;
+        if let Err(e) = repo
+            .add_sighting(indicator_id, SOURCE, Some(group_context(group)), Some(observed_at), ctx)
+            .await
+        {
+            tracing::warn!(indicator_id = %indicator_id, error = %e, "Failed to record syslog sighting");
+        }
+    }
+}
+
+async fn receive_udp(socket: UdpSocket, watchlist: SharedWatchlist, queue: mpsc::Sender<LogSighting>) {
+    let mut buffer = vec![0u8; MAX_MESSAGE_BYTES];
+    loop {
+        match socket.recv_from(&mut buffer).await {
+            Ok((len, peer)) => {
+                handle_message(&String::from_utf8_lossy(&buffer[..len]), peer, "udp", &watchlist, &queue);
+            }
+            Err(e) => tracing::warn!(error = %e, "Syslog UDP receive failed"),
+        }
+    }
+}
+
+async fn receive_tcp(listener: TcpListener, watchlist: SharedWatchlist, queue: mpsc::Sender<LogSighting>) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                tracing::warn!(error = %e, "Syslog TCP accept failed");
+                continue;
+            }
+        };
+
+        let watchlist = watchlist.clone();
+        let queue = queue.clone();
+        tokio::spawn(async move {
+            let mut reader = BufReader::new(stream);
+            loop {
+                match read_frame(&mut reader).await {
+                    Ok(Some(frame)) => handle_message(&frame, peer, "tcp", &watchlist, &queue),
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::debug!(peer = %peer, error = %e, "Syslog TCP connection closed");
+                        break;
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Next message of a TCP stream, framed by octet counting (`<len> <msg>`,
+/// RFC 6587) or by newlines; `None` at end of stream
+async fn read_frame<R: AsyncBufRead + Unpin>(reader: &mut R) -> std::io::Result<Option<String>> {
+    let mut frame = vec![];
+
+    let starts_with_digit = reader.fill_buf().await?.first().is_some_and(u8::is_ascii_digit);
+    if starts_with_digit {
+        (&mut *reader).take(8).read_until(b' ', &mut frame).await?;
+        let length = std::str::from_utf8(&frame)
+            .ok()
+            .and_then(|prefix| prefix.strip_suffix(' '))
+            .and_then(|digits| digits.parse::<usize>().ok())
+            .filter(|length| *length <= MAX_MESSAGE_BYTES);
+        if let Some(length) = length {
+            let mut message = vec![0u8; length];
+            reader.read_exact(&mut message).await?;
+            return Ok(Some(String::from_utf8_lossy(&message).into_owned()));
+        }
+        // Not a length prefix: the digits start a newline framed message
+    }
+
+    if frame.last() != Some(&b'\n') {
+        let limit = MAX_MESSAGE_BYTES.saturating_sub(frame.len()) as u64;
+        (&mut *reader).take(limit).read_until(b'\n', &mut frame).await?;
+    }
+    if frame.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&frame).into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observable(ioc_type: IocType, value: &str) -> Observable {
+        Observable { ioc_type, value: value.to_string(), field: "message".to_string() }
+    }
+
+    async fn frames(input: &[u8]) -> Vec<String> {
+        let mut reader = BufReader::new(input);
+        let mut frames = vec![];
+        while let Some(frame) = read_frame(&mut reader).await.unwrap() {
+            frames.push(frame);
+        }
+        frames
+    }
+
+    #[tokio::test]
+    async fn reads_octet_counted_and_newline_frames() {
+        let first = "<34>1 2024-05-01T10:00:00Z fw01 app - - - line one\nstill one";
+        let second = "<34>1 2024-05-01T10:00:01Z fw01 app - - - line two";
+        let input = format!("{} {}{} {}<34>Oct 11 22:14:15 fw02 newline framed\n2024-05-01 text\n", first.len(), first, second.len(), second);
+
+        assert_eq!(
+            frames(input.as_bytes()).await,
+            vec![
+                first.to_string(),
+                second.to_string(),
+                "<34>Oct 11 22:14:15 fw02 newline framed\n".to_string(),
+                // Leading digits that are not a length prefix
+                "2024-05-01 text\n".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_length_falls_back_to_newline_framing() {
+        let input = format!("{} <34>message\n", MAX_MESSAGE_BYTES + 1);
+
+        assert_eq!(frames(input.as_bytes()).await, vec![input.clone()]);
+    }
+
+    #[test]
+    fn domains_match_on_parent_domains() {
+        let parent = Uuid::new_v4();
+        let exact = Uuid::new_v4();
+        let watchlist = Watchlist::new(vec![
+            (parent, IocType::Domain, "example.com".to_string()),
+            (exact, IocType::Domain, "login.example.net".to_string()),
+        ]);
+
+        assert_eq!(watchlist.find(&observable(IocType::Domain, "a.b.example.com")), Some(parent));
+        assert_eq!(watchlist.find(&observable(IocType::Domain, "example.com")), Some(parent));
+        assert_eq!(watchlist.find(&observable(IocType::Domain, "login.example.net")), Some(exact));
+        assert_eq!(watchlist.find(&observable(IocType::Domain, "example.net")), None);
+        assert_eq!(watchlist.find(&observable(IocType::Domain, "example.com.evil.test")), None);
+    }
+
+    #[test]
+    fn ips_match_exactly_then_most_specific_network() {
+        let wide = Uuid::new_v4();
+        let narrow = Uuid::new_v4();
+        let host = Uuid::new_v4();
+        let v6 = Uuid::new_v4();
+        let watchlist = Watchlist::new(vec![
+            (wide, IocType::Cidr, "10.0.0.0/8".to_string()),
+            (narrow, IocType::Cidr, "10.1.0.0/16".to_string()),
+            (host, IocType::Ip, "10.1.2.3".to_string()),
+            (v6, IocType::Cidr, "2001:db8::/32".to_string()),
+        ]);
+
+        assert_eq!(watchlist.len(), 4);
+        assert_eq!(watchlist.find(&observable(IocType::Ip, "10.1.2.3")), Some(host));
+        assert_eq!(watchlist.find(&observable(IocType::Ip, "10.1.9.9")), Some(narrow));
+        assert_eq!(watchlist.find(&observable(IocType::Ip, "10.2.0.1")), Some(wide));
+        assert_eq!(watchlist.find(&observable(IocType::Ip, "2001:db8::1")), Some(v6));
+        assert_eq!(watchlist.find(&observable(IocType::Ip, "192.0.2.1")), None);
+    }
+
+    #[test]
+    fn one_sighting_per_indicator_and_line() {
+        let id = Uuid::new_v4();
+        let watchlist = Watchlist::new(vec![(id, IocType::Domain, "bad.example.com".to_string())]);
+        let peer: SocketAddr = "192.0.2.1:514".parse().unwrap();
+
+        let sightings = match_line(
+            "<134>Sep 19 08:26:10 fw01 CEF:0|Vendor|Proxy|1.0|1|Blocked|5|dhost=bad.example.com request=http://bad.example.com/x",
+            peer,
+            &watchlist,
+        );
+
+        assert_eq!(sightings.len(), 1);
+        assert_eq!(sightings[0].device, "fw01");
+        assert_eq!(sightings[0].context["field"], "dhost");
+    }
+
+    #[test]
+    fn groups_matches_per_indicator_and_device() {
+        let id = Uuid::new_v4();
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let sighting = |device: &str, ts: &str, log: &str| LogSighting {
+            indicator_id: id,
+            device: device.to_string(),
+            observed_at: at(ts),
+            context: json!({ "log": log }),
+        };
+
+        let mut groups = SightingGroups::new();
+        group_sighting(&mut groups, sighting("fw01", "2024-05-01T10:00:05Z", "second"));
+        group_sighting(&mut groups, sighting("fw01", "2024-05-01T10:00:00Z", "first"));
+        group_sighting(&mut groups, sighting("fw01", "2024-05-01T10:00:09Z", "third"));
+        group_sighting(&mut groups, sighting("fw02", "2024-05-01T10:00:01Z", "other"));
+
+        assert_eq!(groups.len(), 2);
+        let context = group_context(groups.remove(&(id, "fw01".to_string())).unwrap());
+        assert_eq!(context["count"], 3);
+        assert_eq!(context["first_seen"], json!(at("2024-05-01T10:00:00Z")));
+        assert_eq!(context["last_seen"], json!(at("2024-05-01T10:00:09Z")));
+        assert_eq!(context["log"], "third");
+        assert_eq!(group_context(groups.remove(&(id, "fw02".to_string())).unwrap())["count"], 1);
+    }
+}
@@ -0,0 +1,550 @@
+//! Syslog message parsing: RFC 5424 and RFC 3164 headers, CEF and LEEF
+//! payloads and `key=value` firewall logs
+
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use regex::Regex;
+use serde::Serialize;
+use std::net::IpAddr;
+use std::sync::LazyLock;
+
+use crate::models::ioc_utils::{canonicalize_url, detect_ioc_type, normalize_ioc, url_host};
+use crate::models::IocType;
+
+/// `<PRI>` prefix
+static PRI_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^<(\d{1,3})>").unwrap());
+
+/// RFC 3164 header: `Mmm dd hh:mm:ss HOST MSG`
+static BSD_HEADER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([A-Z][a-z]{2}) {1,2}(\d{1,2}) (\d{2}):(\d{2}):(\d{2}) (\S+) ?(.*)$").unwrap()
+});
+
+/// RFC 3164 with an ISO timestamp, as rsyslog and syslog-ng forward it
+static ISO_HEADER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{4}-\d{2}-\d{2}T\S+) (\S+) ?(.*)$").unwrap());
+
+/// `TAG[pid]: ` at the start of an RFC 3164 message
+static TAG_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([A-Za-z0-9_./-]{1,48})(?:\[\d+\])?: ?(.*)$").unwrap());
+
+/// Start of a CEF or LEEF payload
+static PAYLOAD_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:CEF:\d+|LEEF:\d(?:\.\d)?)\|").unwrap());
+
+/// Key of a CEF extension pair (an escaped `\=` never ends a key)
+static CEF_KEY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|\s)([A-Za-z0-9_.\[\]-]+)=").unwrap());
+
+/// `key=value` / `key="quoted value"` pairs of firewall logs (FortiGate, Sophos, ...)
+static KV_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?:^|\s)([A-Za-z][\w.-]*)=("(?:[^"\\]|\\.)*"|\S*)"#).unwrap());
+
+/// RFC 5424 structured data parameter
+static SD_PARAM_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"([^\s=\]"]+)="((?:[^"\\]|\\.)*)""#).unwrap());
+
+static TEXT_IPV4_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:\d{1,3}\.){3}\d{1,3}\b").unwrap());
+
+static TEXT_URL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\b(?:https?|ftp)://[^\s"'<>|]+"#).unwrap());
+
+/// Fields holding a source or destination address
+const IP_FIELDS: &[&str] = &[
+    "src", "dst", "srcip", "dstip", "src_ip", "dst_ip", "sourceaddress", "destinationaddress",
+    "sourcetranslatedaddress", "destinationtranslatedaddress", "srcprenat", "srcpostnat",
+    "dstprenat", "dstpostnat", "remip", "client_ip", "server_ip", "c-ip", "s-ip",
+];
+
+/// Fields holding a host name
+const HOST_FIELDS: &[&str] = &[
+    "shost", "dhost", "sourcehostname", "destinationhostname", "dstname", "hostname", "domain",
+    "query", "qname", "sni", "server_name", "cs-host",
+];
+
+/// Fields holding a requested URL
+const URL_FIELDS: &[&str] = &["request", "requesturl", "url", "uri", "cs-uri"];
+
+/// Payload format of a log line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Cef,
+    Leef,
+    /// `key=value` pairs without a CEF/LEEF header
+    KeyValue,
+    Text,
+}
+
+/// Syslog message split into its header and payload fields
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub format: LogFormat,
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Host name from the syslog header (the sending device)
+    pub host: Option<String>,
+    pub app: Option<String>,
+    /// CEF/LEEF device vendor and product
+    pub vendor: Option<String>,
+    pub product: Option<String>,
+    /// CEF/LEEF extension, key=value pairs and RFC 5424 structured data
+    pub fields: Vec<(String, String)>,
+    /// Free text of the message (the event name for CEF)
+    pub message: String,
+}
+
+/// IP, domain or URL found in a log line, normalized as an indicator value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Observable {
+    pub ioc_type: IocType,
+    pub value: String,
+    /// Field it came from (`message` for free text)
+    pub field: String,
+}
+
+/// Parse one syslog line. Anything unrecognised is kept as text.
+pub fn parse_line(line: &str) -> LogRecord {
+    let line = line.trim_start_matches('\u{feff}').trim();
+    let rest = match PRI_RE.find(line) {
+        Some(pri) => &line[pri.end()..],
+        None => line,
+    };
+
+    let mut record = LogRecord {
+        format: LogFormat::Text,
+        timestamp: None,
+        host: None,
+        app: None,
+        vendor: None,
+        product: None,
+        fields: vec![],
+        message: String::new(),
+    };
+
+    let message = if let Some(rest) = rest.strip_prefix("1 ") {
+        parse_rfc5424(rest, &mut record)
+    } else {
+        parse_rfc3164(rest, &mut record)
+    };
+
+    match PAYLOAD_RE.find(message) {
+        Some(payload) if payload.as_str().starts_with("CEF") => parse_cef(&message[payload.start()..], &mut record),
+        Some(payload) => parse_leef(&message[payload.start()..], &mut record),
+        None => {
+            let pairs: Vec<(String, String)> = KV_RE
+                .captures_iter(message)
+                .map(|c| (c[1].to_string(), unquote(&c[2])))
+                .collect();
+            if pairs.len() >= 2 {
+                record.format = LogFormat::KeyValue;
+                record.fields.extend(pairs);
+            }
+            record.message = message.to_string();
+        }
+    }
+
+    record
+}
+
+/// `TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA MSG`
+fn parse_rfc5424<'a>(rest: &'a str, record: &mut LogRecord) -> &'a str {
+    let mut parts = rest.splitn(6, ' ');
+    let nil = |v: Option<&str>| v.filter(|v| *v != "-").map(str::to_string);
+
+    record.timestamp = parts
+        .next()
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|ts| ts.with_timezone(&Utc));
+    record.host = nil(parts.next());
+    record.app = nil(parts.next());
+    let _procid = parts.next();
+    let _msgid = parts.next();
+
+    let mut rest = parts.next().unwrap_or_default();
+    if let Some(stripped) = rest.strip_prefix('-') {
+        rest = stripped;
+    } else {
+        while rest.starts_with('[') {
+            let end = structured_data_end(rest);
+            record.fields.extend(
+                SD_PARAM_RE
+                    .captures_iter(&rest[..end])
+                    .map(|c| (c[1].to_string(), c[2].replace("\\\"", "\"").replace("\\]", "]").replace("\\\\", "\\"))),
+            );
+            rest = &rest[end..];
+        }
+    }
+
+    rest.trim_start().trim_start_matches('\u{feff}')
+}
+
+/// Byte offset just past the `]` closing the structured data element at the start
+fn structured_data_end(sd: &str) -> usize {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in sd.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            ']' if !quoted => return i + 1,
+            _ => {}
+        }
+    }
+    sd.len()
+}
+
+/// `Mmm dd hh:mm:ss HOST TAG: MSG`, also accepting an ISO timestamp and a
+/// missing host (devices that put CEF/LEEF right after the timestamp)
+fn parse_rfc3164<'a>(rest: &'a str, record: &mut LogRecord) -> &'a str {
+    let (host, message) = if let Some(caps) = BSD_HEADER_RE.captures(rest) {
+        record.timestamp = bsd_timestamp(&caps[1], &caps[2], &caps[3], &caps[4], &caps[5], Utc::now());
+        (caps.get(6).unwrap(), caps.get(7).unwrap())
+    } else if let Some(caps) = ISO_HEADER_RE.captures(rest) {
+        record.timestamp = DateTime::parse_from_rfc3339(&caps[1]).ok().map(|ts| ts.with_timezone(&Utc));
+        (caps.get(2).unwrap(), caps.get(3).unwrap())
+    } else {
+        return rest;
+    };
+
+    if PAYLOAD_RE.find(host.as_str()).is_some_and(|m| m.start() == 0) {
+        return &rest[host.start()..];
+    }
+    record.host = Some(host.as_str().to_string());
+
+    let message = message.as_str();
+    if PAYLOAD_RE.find(message).is_some_and(|m| m.start() == 0) {
+        return message;
+    }
+    match TAG_RE.captures(message) {
+        Some(caps) => {
+            record.app = Some(caps[1].to_string());
+            caps.get(2).map_or("", |m| m.as_str())
+        }
+        None => message,
+    }
+}
+
+/// RFC 3164 timestamps have no year or zone: take them as UTC in the current
+/// year, or the previous one when that would put them more than a day ahead
+fn bsd_timestamp(month: &str, day: &str, hour: &str, minute: &str, second: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let month = MONTHS.iter().position(|m| *m == month)? as u32 + 1;
+    let (day, hour, minute, second) = (day.parse().ok()?, hour.parse().ok()?, minute.parse().ok()?, second.parse().ok()?);
+
+    let at = |year: i32| {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|d| d.and_hms_opt(hour, minute, second))
+            .map(|t| Utc.from_utc_datetime(&t))
+    };
+    match at(now.year()) {
+        Some(ts) if ts > now + chrono::Duration::days(1) => at(now.year() - 1),
+        ts => ts,
+    }
+}
+
+/// Split on `|` not escaped as `\|`, into at most `max` parts
+fn split_header(payload: &str, max: usize) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in payload.char_indices() {
+        if parts.len() + 1 == max {
+            break;
+        }
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '|' => {
+                parts.push(&payload[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&payload[start..]);
+    parts
+}
+
+fn unescape_header(value: &str) -> String {
+    value.replace("\\|", "|").replace("\\\\", "\\")
+}
+
+/// `CEF:Version|Vendor|Product|Version|SignatureID|Name|Severity|Extension`
+fn parse_cef(payload: &str, record: &mut LogRecord) {
+    let header = split_header(payload, 8);
+    record.format = LogFormat::Cef;
+    record.vendor = header.get(1).map(|v| unescape_header(v));
+    record.product = header.get(2).map(|v| unescape_header(v));
+    record.message = header.get(5).map(|v| unescape_header(v)).unwrap_or_default();
+
+    let Some(extension) = header.get(7) else {
+        return;
+    };
+    let keys: Vec<_> = CEF_KEY_RE.captures_iter(extension).collect();
+    for (i, caps) in keys.iter().enumerate() {
+        let value_start = caps.get(0).unwrap().end();
+        let value_end = keys.get(i + 1).map_or(extension.len(), |next| next.get(0).unwrap().start());
+        let value = extension[value_start..value_end]
+            .trim_end()
+            .replace("\\=", "=")
+            .replace("\\n", "\n")
+            .replace("\\r", "\r")
+            .replace("\\\\", "\\");
+        record.fields.push((caps[1].to_string(), value));
+    }
+}
+
+/// `LEEF:1.0|Vendor|Product|Version|EventID|attrs` (tab separated) or
+/// `LEEF:2.0|Vendor|Product|Version|EventID|Delimiter|attrs`
+fn parse_leef(payload: &str, record: &mut LogRecord) {
+    let v2 = payload.starts_with("LEEF:2");
+    let header = split_header(payload, if v2 { 7 } else { 6 });
+    record.format = LogFormat::Leef;
+    record.vendor = header.get(1).map(|v| v.to_string());
+    record.product = header.get(2).map(|v| v.to_string());
+    record.message = header.get(4).map(|v| v.to_string()).unwrap_or_default();
+
+    let delimiter = match header.get(5) {
+        Some(d) if v2 => leef_delimiter(d),
+        _ => '\t',
+    };
+    let Some(attributes) = header.last().filter(|_| header.len() == if v2 { 7 } else { 6 }) else {
+        return;
+    };
+
+    if attributes.contains(delimiter) || delimiter != '\t' {
+        record.fields.extend(
+            attributes
+                .split(delimiter)
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(k, v)| (k.trim().to_string(), v.trim().to_string())),
+        );
+    } else {
+        // Senders that flatten the tabs to spaces
+        record.fields.extend(KV_RE.captures_iter(attributes).map(|c| (c[1].to_string(), unquote(&c[2]))));
+    }
+}
+
+/// LEEF 2.0 delimiter: a character or its hex code (`x09`, `0x5E`); tab when empty
+fn leef_delimiter(field: &str) -> char {
+    let hex = field.strip_prefix("0x").or_else(|| field.strip_prefix('x'));
+    match hex {
+        Some(code) if !code.is_empty() => u32::from_str_radix(code, 16).ok().and_then(char::from_u32).unwrap_or('\t'),
+        _ => field.chars().next().unwrap_or('\t'),
+    }
+}
+
+fn unquote(value: &str) -> String {
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => inner.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => value.to_string(),
+    }
+}
+
+impl LogRecord {
+    /// Source/destination addresses, host names and URLs of the record: the
+    /// known CEF/LEEF/firewall fields first, then IPs and URLs in the text
+    pub fn observables(&self) -> Vec<Observable> {
+        let mut observables: Vec<Observable> = vec![];
+        let mut push = |ioc_type: IocType, value: String, field: &str| {
+            if !observables.iter().any(|o| o.ioc_type == ioc_type && o.value == value) {
+                observables.push(Observable { ioc_type, value, field: field.to_string() });
+            }
+        };
+
+        for (key, value) in &self.fields {
+            let key_lower = key.to_lowercase();
+            let value = value.trim();
+            if value.is_empty() || value == "-" {
+                continue;
+            }
+
+            if IP_FIELDS.contains(&key_lower.as_str()) {
+                if let Ok(ip) = value.parse::<IpAddr>() {
+                    push(IocType::Ip, ip.to_string(), key);
+                }
+            } else if HOST_FIELDS.contains(&key_lower.as_str()) {
+                if let Some(ioc_type @ (IocType::Ip | IocType::Domain)) = detect_ioc_type(value) {
+                    push(ioc_type.clone(), normalize_ioc(value, &ioc_type), key);
+                }
+            } else if URL_FIELDS.contains(&key_lower.as_str()) {
+                // Paths without a host (`uri=/index.php`) say nothing on their own
+                if value.starts_with('/') {
+                    continue;
+                }
+                let url = if value.contains("://") { value.to_string() } else { format!("http://{}", value) };
+                if let Some(url) = canonicalize_url(&url) {
+                    if let Some((ioc_type, host)) = url_host(&url) {
+                        push(ioc_type, host, key);
+                    }
+                    push(IocType::Url, url, key);
+                }
+            }
+        }
+
+        for url in TEXT_URL_RE.find_iter(&self.message).filter_map(|m| canonicalize_url(m.as_str().trim_end_matches(['.', ',', ')', ';']))) {
+            if let Some((ioc_type, host)) = url_host(&url) {
+                push(ioc_type, host, "message");
+            }
+            push(IocType::Url, url, "message");
+        }
+        for ip in TEXT_IPV4_RE.find_iter(&self.message).filter_map(|m| m.as_str().parse::<IpAddr>().ok()) {
+            push(IocType::Ip, ip.to_string(), "message");
+        }
+
+        observables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field<'a>(record: &'a LogRecord, key: &str) -> Option<&'a str> {
+        record.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    fn utc(ts: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(ts).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn rfc5424_structured_data_escapes() {
+        let record = parse_line(
+            r#"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut="3" eventSource="Application" note="a \"quoted\" \] value"][origin ip="192.0.2.1"] An application event"#,
+        );
+
+        assert_eq!(record.timestamp, Some(utc("2003-10-11T22:14:15.003Z")));
+        assert_eq!(record.host.as_deref(), Some("mymachine.example.com"));
+        assert_eq!(record.app.as_deref(), Some("evntslog"));
+        assert_eq!(field(&record, "eventSource"), Some("Application"));
+        assert_eq!(field(&record, "note"), Some(r#"a "quoted" ] value"#));
+        assert_eq!(field(&record, "ip"), Some("192.0.2.1"));
+        assert_eq!(record.message, "An application event");
+    }
+
+    #[test]
+    fn rfc5424_without_structured_data() {
+        let record = parse_line("<34>1 2003-10-11T22:14:15.003Z mymachine.example.com su - ID47 - 'su root' failed for lonvick on /dev/pts/8");
+
+        assert_eq!(record.app.as_deref(), Some("su"));
+        assert!(record.fields.is_empty());
+        assert_eq!(record.format, LogFormat::Text);
+        assert_eq!(record.message, "'su root' failed for lonvick on /dev/pts/8");
+    }
+
+    #[test]
+    fn rfc3164_header_and_tag() {
+        let record = parse_line("<34>Oct 11 22:14:15 mymachine sshd[4721]: Failed password for root from 198.51.100.23 port 52113 ssh2");
+
+        assert_eq!(record.host.as_deref(), Some("mymachine"));
+        assert_eq!(record.app.as_deref(), Some("sshd"));
+        assert_eq!(record.message, "Failed password for root from 198.51.100.23 port 52113 ssh2");
+        assert_eq!(
+            record.observables(),
+            vec![Observable { ioc_type: IocType::Ip, value: "198.51.100.23".to_string(), field: "message".to_string() }]
+        );
+    }
+
+    #[test]
+    fn bsd_timestamp_rolls_back_a_year_after_new_year() {
+        let now = utc("2025-01-01T00:10:00Z");
+
+        // Sent just before midnight on New Year's Eve
+        assert_eq!(bsd_timestamp("Dec", "31", "23", "59", "59", now), Some(utc("2024-12-31T23:59:59Z")));
+        assert_eq!(bsd_timestamp("Jan", "1", "00", "05", "00", now), Some(utc("2025-01-01T00:05:00Z")));
+        // Clock skew of less than a day stays in the current year
+        assert_eq!(bsd_timestamp("Jan", "1", "12", "00", "00", now), Some(utc("2025-01-01T12:00:00Z")));
+        assert_eq!(bsd_timestamp("Feb", "30", "00", "00", "00", now), None);
+        assert_eq!(bsd_timestamp("Foo", "1", "00", "00", "00", now), None);
+    }
+
+    #[test]
+    fn cef_header_and_escaped_extension() {
+        let record = parse_line(
+            r"<134>Sep 19 08:26:10 fw01 CEF:0|Check Point|VPN-1 \| FireWall-1|R81|100|Blocked URL|8|src=10.0.0.1 dst=203.0.113.9 dhost=bad.example.com request=http://bad.example.com/get?id\=7 msg=Access denied by policy act=Block",
+        );
+
+        assert_eq!(record.format, LogFormat::Cef);
+        assert_eq!(record.host.as_deref(), Some("fw01"));
+        assert_eq!(record.vendor.as_deref(), Some("Check Point"));
+        assert_eq!(record.product.as_deref(), Some("VPN-1 | FireWall-1"));
+        assert_eq!(record.message, "Blocked URL");
+        assert_eq!(field(&record, "request"), Some("http://bad.example.com/get?id=7"));
+        assert_eq!(field(&record, "msg"), Some("Access denied by policy"));
+        assert_eq!(field(&record, "act"), Some("Block"));
+
+        let observables = record.observables();
+        let values: Vec<_> = observables.iter().map(|o| (o.ioc_type.clone(), o.field.as_str())).collect();
+        assert_eq!(
+            values,
+            vec![
+                (IocType::Ip, "src"),
+                (IocType::Ip, "dst"),
+                (IocType::Domain, "dhost"),
+                (IocType::Url, "request"),
+            ]
+        );
+        assert_eq!(observables[2].value, "bad.example.com");
+    }
+
+    #[test]
+    fn cef_right_after_timestamp() {
+        let record = parse_line("<134>Sep 19 08:26:10 CEF:0|Trend Micro|Deep Security Agent|20.0|4000000|Eicar_test_file|6|src=192.0.2.44");
+
+        assert_eq!(record.format, LogFormat::Cef);
+        assert_eq!(record.host, None);
+        assert_eq!(record.vendor.as_deref(), Some("Trend Micro"));
+        assert_eq!(field(&record, "src"), Some("192.0.2.44"));
+    }
+
+    #[test]
+    fn leef_1_tab_separated() {
+        let record = parse_line(
+            "<13>Jan 18 11:07:53 192.168.1.1 LEEF:1.0|Microsoft|MSExchange|4.0 SP1|15345|src=192.0.2.10\tdst=172.50.123.1\tsev=5\tusrName=joe.black",
+        );
+
+        assert_eq!(record.format, LogFormat::Leef);
+        assert_eq!(record.vendor.as_deref(), Some("Microsoft"));
+        assert_eq!(record.message, "15345");
+        assert_eq!(field(&record, "dst"), Some("172.50.123.1"));
+        assert_eq!(field(&record, "usrName"), Some("joe.black"));
+    }
+
+    #[test]
+    fn leef_1_with_flattened_tabs() {
+        let record = parse_line("LEEF:1.0|Palo Alto Networks|PAN-OS|10.1|THREAT|src=192.0.2.10 dst=198.51.100.1 url=\"evil.example.net/a b\"");
+
+        assert_eq!(field(&record, "dst"), Some("198.51.100.1"));
+        assert_eq!(field(&record, "url"), Some("evil.example.net/a b"));
+    }
+
+    #[test]
+    fn leef_2_custom_delimiters() {
+        let caret = parse_line("LEEF:2.0|Lancope|StealthWatch|1.0|41|^|src=10.0.50.1^dst=10.0.50.2^sev=5");
+        assert_eq!(field(&caret, "src"), Some("10.0.50.1"));
+        assert_eq!(field(&caret, "dst"), Some("10.0.50.2"));
+
+        // Pipes after the delimiter field belong to the attributes
+        let pipe = parse_line("LEEF:2.0|Vendor|Product|1.0|login|x7C|src=10.0.0.1|usrName=alice");
+        assert_eq!(field(&pipe, "usrName"), Some("alice"));
+        let hex = parse_line("LEEF:2.0|Vendor|Product|1.0|login|0x3B|src=10.0.0.1;usrName=alice");
+        assert_eq!(field(&hex, "usrName"), Some("alice"));
+
+        assert_eq!(leef_delimiter("x09"), '\t');
+        assert_eq!(leef_delimiter("0x5E"), '^');
+        assert_eq!(leef_delimiter("^"), '^');
+        assert_eq!(leef_delimiter(""), '\t');
+    }
+
+    #[test]
+    fn key_value_firewall_log() {
+        let record = parse_line(
+            r#"<189>date=2024-05-01 time=10:00:00 devname="FGT60E" type="traffic" srcip=192.0.2.10 dstip=203.0.113.5 hostname="Bad.Example.com" url="/index.php" action="deny""#,
+        );
+
+        assert_eq!(record.format, LogFormat::KeyValue);
+        assert_eq!(field(&record, "devname"), Some("FGT60E"));
+        let values: Vec<_> = record.observables().into_iter().map(|o| o.value).collect();
+        assert_eq!(values, vec!["192.0.2.10", "203.0.113.5", "bad.example.com"]);
+    }
+}